@@ -0,0 +1,197 @@
+// src/analysis/metrics.rs
+//
+// Output metrics: single numbers summarising a whole run, for analyses that run the model many times and compare
+// the runs (sensitivity.rs).  Metrics are named as the time-series measures are (simulation/timeseries.rs):
+//
+//     deaths                    deaths during the run
+//     deaths_<cause>            deaths from background_mortality, sepsis_related or drug_toxicity_related
+//     new_infections            infections acquired during the run, with all bacteria
+//     new_infections(bacteria)  infections acquired with the bacteria
+//     infected(bacteria)        proportion of the living infected with the bacteria (level > 0.001)
+//     sepsis(bacteria)          proportion of the living with sepsis from the bacteria
+//     using_drug(drug)          proportion of the living taking the drug
+//     any_r(bacteria, drug)     proportion of infections with the bacteria with any_r > 0 to the drug
+//
+// The proportions are taken over every day of the run (e.g. infected person-days over living person-days), which
+// is steadier than the state on the last day; a proportion with nothing to divide by is 0.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use rayon::prelude::*;
+
+use crate::config::catalog::Catalog;
+use crate::simulation::output::OutputSink;
+use crate::simulation::population::Individual;
+use crate::simulation::simulation::Simulation;
+
+const CAUSES_OF_DEATH: [&str; 3] = ["background_mortality", "sepsis_related", "drug_toxicity_related"];
+
+/// A number summarising a run (see the top of this file).
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputMetric {
+    Deaths(Option<&'static str>), // cause, or all causes
+    NewInfections(Option<usize>), // bacteria, or all bacteria
+    Infected(usize),
+    Sepsis(usize),
+    UsingDrug(usize),
+    AnyR(usize, usize),
+}
+
+impl OutputMetric {
+    /// Parses a metric, with bacteria and drug names from `catalog`.
+    pub fn parse(catalog: &Catalog, text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (name, args) = match text.split_once('(') {
+            Some((name, rest)) => {
+                let args = rest.strip_suffix(')').ok_or_else(|| format!("missing \")\" in \"{}\"", text))?;
+                (name.trim(), args.split(',').map(str::trim).collect::<Vec<_>>())
+            }
+            None => (text, Vec::new()),
+        };
+        let arity = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(format!("\"{}\" takes {} argument(s)", name, n))
+            }
+        };
+        let bacteria = |arg: &str| {
+            catalog.bacteria.iter().position(|b| b.name == arg).ok_or_else(|| format!("unknown bacteria \"{}\"", arg))
+        };
+        let drug = |arg: &str| catalog.drugs.iter().position(|d| d.name == arg).ok_or_else(|| format!("unknown drug \"{}\"", arg));
+
+        match name {
+            "deaths" => arity(0).map(|_| OutputMetric::Deaths(None)),
+            "new_infections" if args.is_empty() => Ok(OutputMetric::NewInfections(None)),
+            "new_infections" => arity(1).and(bacteria(args[0]).map(|b_idx| OutputMetric::NewInfections(Some(b_idx)))),
+            "infected" => arity(1).and(bacteria(args[0]).map(OutputMetric::Infected)),
+            "sepsis" => arity(1).and(bacteria(args[0]).map(OutputMetric::Sepsis)),
+            "using_drug" => arity(1).and(drug(args[0]).map(OutputMetric::UsingDrug)),
+            "any_r" => {
+                arity(2)?;
+                Ok(OutputMetric::AnyR(bacteria(args[0])?, drug(args[1])?))
+            }
+            _ => match name.strip_prefix("deaths_").and_then(|cause| CAUSES_OF_DEATH.iter().find(|&&c| c == cause)) {
+                Some(&cause) => arity(0).map(|_| OutputMetric::Deaths(Some(cause))),
+                None => Err(format!("unknown metric \"{}\"", name)),
+            },
+        }
+    }
+
+    /// The metric as it is written, e.g. "any_r(escherichia coli, amoxicillin)", with the names of `catalog`.
+    pub fn name(&self, catalog: &Catalog) -> String {
+        let bacteria = |b_idx: usize| &catalog.bacteria[b_idx].name;
+        let drug = |d_idx: usize| &catalog.drugs[d_idx].name;
+        match *self {
+            OutputMetric::Deaths(None) => "deaths".to_string(),
+            OutputMetric::Deaths(Some(cause)) => format!("deaths_{}", cause),
+            OutputMetric::NewInfections(None) => "new_infections".to_string(),
+            OutputMetric::NewInfections(Some(b_idx)) => format!("new_infections({})", bacteria(b_idx)),
+            OutputMetric::Infected(b_idx) => format!("infected({})", bacteria(b_idx)),
+            OutputMetric::Sepsis(b_idx) => format!("sepsis({})", bacteria(b_idx)),
+            OutputMetric::UsingDrug(d_idx) => format!("using_drug({})", drug(d_idx)),
+            OutputMetric::AnyR(b_idx, d_idx) => format!("any_r({}, {})", bacteria(b_idx), drug(d_idx)),
+        }
+    }
+
+    // What one individual adds to the metric on one day, as (numerator, denominator); counts have no denominator.
+    fn count(&self, individual: &Individual, time_step: usize) -> (f64, f64) {
+        let living = individual.date_of_death.is_none();
+        let flag = |condition: bool| if condition { 1.0 } else { 0.0 };
+        match *self {
+            OutputMetric::Deaths(cause) => (
+                flag(individual.date_of_death == Some(time_step) && cause.is_none_or(|c| individual.cause_of_death == Some(c))),
+                0.0,
+            ),
+            OutputMetric::NewInfections(bacteria) => {
+                let new = |b_idx: usize| individual.level[b_idx] > 0.001 && individual.date_last_infected[b_idx] == time_step as i32;
+                match bacteria {
+                    Some(b_idx) => (flag(new(b_idx)), 0.0),
+                    None => ((0..individual.level.len()).filter(|&b_idx| new(b_idx)).count() as f64, 0.0),
+                }
+            }
+            _ if !living => (0.0, 0.0),
+            OutputMetric::Infected(b_idx) => (flag(individual.level[b_idx] > 0.001), 1.0),
+            OutputMetric::Sepsis(b_idx) => (flag(individual.sepsis[b_idx]), 1.0),
+            OutputMetric::UsingDrug(d_idx) => (flag(individual.cur_use_drug[d_idx]), 1.0),
+            OutputMetric::AnyR(b_idx, d_idx) => {
+                let infected = individual.level[b_idx] > 0.001;
+                (flag(infected && individual.resistances[b_idx][d_idx].any_r > 0.0), flag(infected))
+            }
+        }
+    }
+
+    fn is_proportion(&self) -> bool {
+        !matches!(self, OutputMetric::Deaths(_) | OutputMetric::NewInfections(_))
+    }
+}
+
+/// Where a `MetricsCollector` leaves the metric values, in the order of its metrics, when the run finishes.
+pub type MetricsHandle = Arc<Mutex<Option<Vec<f64>>>>;
+
+/// Output sink working out a set of metrics over a run (see the top of this file).
+pub struct MetricsCollector {
+    metrics: Vec<OutputMetric>,
+    totals: Vec<(f64, f64)>, // [metric] (numerator, denominator) so far
+    values: MetricsHandle,
+}
+
+impl MetricsCollector {
+    pub fn new(metrics: Vec<OutputMetric>) -> Self {
+        let totals = vec![(0.0, 0.0); metrics.len()];
+        MetricsCollector { metrics, totals, values: Arc::new(Mutex::new(None)) }
+    }
+
+    /// A handle to the metric values, filled in when the run finishes.
+    pub fn values_handle(&self) -> MetricsHandle {
+        Arc::clone(&self.values)
+    }
+}
+
+impl OutputSink for MetricsCollector {
+    fn step(&mut self, simulation: &Simulation, time_step: usize) -> io::Result<()> {
+        let metrics = &self.metrics;
+        let day = simulation
+            .population
+            .par_iter()
+            .filter(|individual| individual.date_of_death.is_none_or(|day| day == time_step))
+            .fold(
+                || vec![(0.0, 0.0); metrics.len()],
+                |mut totals, individual| {
+                    for (total, metric) in totals.iter_mut().zip(metrics) {
+                        let (numerator, denominator) = metric.count(&individual, time_step);
+                        total.0 += numerator;
+                        total.1 += denominator;
+                    }
+                    totals
+                },
+            )
+            .reduce(|| vec![(0.0, 0.0); metrics.len()], add_totals);
+        self.totals = add_totals(std::mem::take(&mut self.totals), day);
+        Ok(())
+    }
+
+    fn finish(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        let values = self
+            .metrics
+            .iter()
+            .zip(&self.totals)
+            .map(|(metric, &(numerator, denominator))| match metric.is_proportion() {
+                true if denominator > 0.0 => numerator / denominator,
+                true => 0.0,
+                false => numerator,
+            })
+            .collect();
+        *self.values.lock().unwrap() = Some(values);
+        Ok(())
+    }
+}
+
+fn add_totals(mut a: Vec<(f64, f64)>, b: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    for (x, y) in a.iter_mut().zip(b) {
+        x.0 += y.0;
+        x.1 += y.1;
+    }
+    a
+}
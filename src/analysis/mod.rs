@@ -0,0 +1,3 @@
+pub mod metrics; // single numbers summarising a run (deaths, infections, resistance, ...) for comparing runs
+pub mod replicates; // a scenario run with several seeds, and its time series summarised over the runs
+pub mod sensitivity; // global sensitivity analysis over parameter ranges: Latin hypercube, Morris and Sobol designs
@@ -0,0 +1,170 @@
+// src/analysis/replicates.rs
+//
+// Replicates: a scenario run several times with different seeds, and its time series summarised over the runs.  A
+// single run of a stochastic model is one draw; the spread over replicates shows how much of a result is chance.
+//
+// Every replicate is built with the same master seed and its own replicate number (`SimulationBuilder::replicate`),
+// which gives it a seed of its own derived from the master seed, and keeps its time series in memory:
+//
+//     let mut summary = ReplicateSummary::new(DEFAULT_AGE_BANDS.to_vec());
+//     for replicate in 0..20 {
+//         let counts = TimeSeriesHandle::default();
+//         let mut simulation = Simulation::builder()
+//             .seed(42)
+//             .replicate(replicate)
+//             .output(TimeSeriesWriter::new(std::io::sink()).keep_counts(counts.clone()))
+//             .build()?;
+//         simulation.run()?;
+//         summary.add(&simulation, std::mem::take(&mut *counts.lock().unwrap()));
+//     }
+//     summary.write_csv(&mut std::io::stdout())?;
+//
+// The summary has one row per reporting day and time-series cell (stratum, measure, bacteria, drug) that is
+// non-zero in some replicate, with the mean, median and 2.5th and 97.5th percentiles of the count over the
+// replicates (a replicate without the row counts 0):
+//
+//     date,time_step,region,age_band,sex,measure,bacteria,drug,mean,median,p2_5,p97_5
+//
+// The replicates should share the reporting interval and age bands.  Percentiles are interpolated between the
+// sorted counts.
+//
+// When some parameters are declared as distributions (config/distribution.rs), every replicate draws its own values
+// of them; given their keys with `parameters`, the summary also records the values each replicate used:
+//
+//     replicate,seed,<key>,<key>,...
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use crate::config::catalog::Catalog;
+use crate::config::schedule::CalendarDate;
+use crate::simulation::output::csv_field;
+use crate::simulation::simulation::Simulation;
+use crate::simulation::timeseries::{cell_columns, CellKey, TimeSeriesCounts};
+
+/// The time series of a set of replicates, summarised (see the top of this file).
+#[derive(Debug, Clone)]
+pub struct ReplicateSummary {
+    age_bands: Vec<u32>,
+    catalog: Option<Arc<Catalog>>, // of the replicates, for the names; from the first one added
+    seeds: Vec<u64>,
+    parameter_keys: Vec<String>,
+    parameter_values: Vec<Vec<f64>>,             // [replicate] value of each of parameter_keys
+    dates: BTreeMap<usize, CalendarDate>,        // of each reporting time step
+    cells: BTreeMap<(usize, CellKey), Vec<u64>>, // [replicate] count, short by the replicates added since without it
+}
+
+impl ReplicateSummary {
+    /// An empty summary of time series with these age bands (the lower limit of each band in years).
+    pub fn new(age_bands: Vec<u32>) -> Self {
+        ReplicateSummary {
+            age_bands,
+            catalog: None,
+            seeds: Vec::new(),
+            parameter_keys: Vec::new(),
+            parameter_values: Vec::new(),
+            dates: BTreeMap::new(),
+            cells: BTreeMap::new(),
+        }
+    }
+
+    /// Also record the value each replicate used for these parameters (usually those declared as distributions).
+    pub fn parameters(mut self, keys: Vec<String>) -> Self {
+        self.parameter_keys = keys;
+        self
+    }
+
+    /// Adds a finished replicate: the simulation (for its seed and dates) and the counts its time series kept.
+    pub fn add(&mut self, simulation: &Simulation, counts: TimeSeriesCounts) {
+        let replicate = self.seeds.len();
+        self.catalog.get_or_insert_with(|| simulation.catalog.clone());
+        self.seeds.push(simulation.seed);
+        let parameters = &simulation.parameters;
+        self.parameter_values.push(self.parameter_keys.iter().map(|key| parameters.get(key).unwrap_or(f64::NAN)).collect());
+        for ((time_step, key), count) in counts {
+            self.dates.entry(time_step).or_insert_with(|| simulation.date_at(time_step));
+            let values = self.cells.entry((time_step, key)).or_default();
+            values.resize(replicate, 0);
+            values.push(count);
+        }
+    }
+
+    /// The number of replicates added.
+    pub fn replicates(&self) -> usize {
+        self.seeds.len()
+    }
+
+    /// The seed of each replicate, in the order added.
+    pub fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+
+    /// Writes the summary as CSV.
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "date,time_step,region,age_band,sex,measure,bacteria,drug,mean,median,p2_5,p97_5")?;
+        let Some(catalog) = &self.catalog else {
+            return out.flush(); // no replicates, so no rows
+        };
+        for (&(time_step, key), values) in &self.cells {
+            let mut values: Vec<f64> = values.iter().map(|&count| count as f64).collect();
+            values.resize(self.replicates(), 0.0);
+            values.sort_by(f64::total_cmp);
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                self.dates[&time_step],
+                time_step,
+                cell_columns(catalog, &self.age_bands, key),
+                mean,
+                quantile(&values, 0.5),
+                quantile(&values, 0.025),
+                quantile(&values, 0.975)
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Writes the seed of each replicate and the values it used for the recorded parameters as CSV.
+    pub fn write_parameters_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let keys: Vec<String> = self.parameter_keys.iter().map(|key| csv_field(key)).collect();
+        writeln!(out, "replicate,seed,{}", keys.join(","))?;
+        for (replicate, (seed, values)) in self.seeds.iter().zip(&self.parameter_values).enumerate() {
+            let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            writeln!(out, "{},{},{}", replicate + 1, seed, values.join(","))?;
+        }
+        out.flush()
+    }
+}
+
+// The quantile `p` of sorted values, interpolating between neighbours.
+fn quantile(sorted: &[f64], p: f64) -> f64 {
+    let position = p * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_of_a_sorted_vector() {
+        let sorted = [1.0, 2.0, 4.0, 8.0, 16.0];
+        assert_eq!(quantile(&sorted, 0.0), 1.0);
+        assert_eq!(quantile(&sorted, 0.5), 4.0);
+        assert_eq!(quantile(&sorted, 1.0), 16.0);
+        assert_eq!(quantile(&sorted, 0.25), 2.0); // exactly on a value
+        assert_eq!(quantile(&sorted, 0.625), 6.0); // half way between 4 and 8
+        assert_eq!(quantile(&[1.0, 3.0], 0.025), 1.05);
+    }
+
+    #[test]
+    fn quantiles_of_a_single_value() {
+        for p in [0.0, 0.025, 0.5, 0.975, 1.0] {
+            assert_eq!(quantile(&[7.0], p), 7.0);
+        }
+    }
+}
@@ -0,0 +1,462 @@
+// src/analysis/sensitivity.rs
+//
+// Global sensitivity analysis: how much each of a set of parameters moves chosen output metrics (metrics.rs) when
+// the parameters vary together over their ranges.  The ranges are given as a priors file (calibration/priors.rs)
+// of uniform or log_uniform distributions; a design of points in the unit cube is laid out, mapped through the
+// ranges (evenly on the log scale for log_uniform), and the model is run at every point, quietly and in parallel.
+//
+// Three designs:
+//
+//   lhs     Latin hypercube of `samples` points: each parameter's range is cut into `samples` equal slices and
+//           every slice is used once.  Reports the Spearman rank correlation of each parameter with each metric.
+//   morris  Morris elementary effects over `samples` trajectories on a grid of `levels` levels: each trajectory
+//           starts at a random grid point and moves one parameter at a time, in random order, by
+//           levels / (2 (levels - 1)) of its range.  Reports mu (mean effect), mu_star (mean absolute effect) and
+//           sigma (standard deviation of the effects), with effects per whole range.  (k + 1) runs per trajectory.
+//   sobol   Saltelli's scheme with two Latin hypercubes A and B of `samples` points and, for each parameter, A with
+//           that column taken from B.  Reports first-order (Saltelli 2010) and total-order (Jansen 1999) Sobol
+//           indices.  (k + 2) runs per sample.
+//
+// Every run uses the same seed (common random numbers), so differences between points come from the parameters
+// and not from the chance events of the run.  The design is drawn from the analysis's seed, so an analysis
+// repeats exactly for the same seed whatever the number of threads.  Statistics that cannot be worked out (a
+// metric that does not vary) are left empty.
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::analysis::metrics::{MetricsCollector, OutputMetric};
+use crate::calibration::priors::Prior;
+use crate::calibration::targets::CalibrationError;
+use crate::config::catalog::Catalog;
+use crate::config::demography::Demography;
+use crate::config::loader::ParameterSet;
+use crate::simulation::builder::{BuildError, RunLength};
+use crate::simulation::output::csv_field;
+use crate::simulation::rng::{individual_rng, random_master_seed, RngStream, SimRng};
+use crate::simulation::simulation::Simulation;
+
+/// The design of an analysis (see the top of this file).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SensitivityMethod {
+    LatinHypercube { samples: usize },
+    Morris { trajectories: usize, levels: usize },
+    Sobol { samples: usize },
+}
+
+impl SensitivityMethod {
+    /// The names of the statistics reported for each parameter and metric.
+    pub fn statistics(&self) -> &'static [&'static str] {
+        match self {
+            SensitivityMethod::LatinHypercube { .. } => &["spearman"],
+            SensitivityMethod::Morris { .. } => &["mu", "mu_star", "sigma"],
+            SensitivityMethod::Sobol { .. } => &["first_order", "total_order"],
+        }
+    }
+}
+
+/// The statistics of one parameter for one metric, in the order of `SensitivityMethod::statistics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityIndex {
+    pub parameter: String,
+    pub metric: String,
+    pub values: Vec<Option<f64>>,
+}
+
+/// The result of an analysis: the design, the metric values at each point, and the statistics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityResult {
+    pub method: SensitivityMethod,
+    pub keys: Vec<String>,
+    pub metrics: Vec<String>,
+    pub points: Vec<Vec<f64>>,  // [run] parameter values, in the order of the keys
+    pub outputs: Vec<Vec<f64>>, // [run] metric values, in the order of the metrics
+    pub indices: Vec<SensitivityIndex>,
+    pub seed: u64, // of every run
+}
+
+impl SensitivityResult {
+    /// The number of model runs made, one per point of the design.
+    pub fn runs(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Writes the design as CSV: the run number, one column per parameter key, then one per metric.
+    pub fn write_design_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let header: Vec<String> = self.keys.iter().chain(&self.metrics).map(|name| csv_field(name)).collect();
+        writeln!(out, "run,{}", header.join(","))?;
+        for (run, (point, output)) in self.points.iter().zip(&self.outputs).enumerate() {
+            let values: Vec<String> = point.iter().chain(output).map(|v| v.to_string()).collect();
+            writeln!(out, "{},{}", run, values.join(","))?;
+        }
+        out.flush()
+    }
+
+    /// Writes the statistics as CSV: parameter, metric, then one column per statistic.
+    pub fn write_indices_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "parameter,metric,{}", self.method.statistics().join(","))?;
+        for index in &self.indices {
+            let values: Vec<String> = index.values.iter().map(|v| v.map_or(String::new(), |v| v.to_string())).collect();
+            writeln!(out, "{},{},{}", csv_field(&index.parameter), csv_field(&index.metric), values.join(","))?;
+        }
+        out.flush()
+    }
+}
+
+/// A sensitivity analysis of the ranges' parameters.
+pub struct SensitivityAnalysis {
+    ranges: Vec<Prior>,
+    metrics: Vec<OutputMetric>,
+    parameters: Option<ParameterSet>,
+    catalog: Option<Arc<Catalog>>,
+    demography: Option<Arc<Demography>>,
+    population_size: usize,
+    run_length: RunLength,
+    method: SensitivityMethod,
+    seed: Option<u64>,
+}
+
+impl SensitivityAnalysis {
+    /// An analysis with default parameters otherwise, a Morris design of 10 trajectories on 4 levels, and runs of
+    /// 2,000 individuals over 365 days.
+    pub fn new(ranges: Vec<Prior>, metrics: Vec<OutputMetric>) -> Self {
+        SensitivityAnalysis {
+            ranges,
+            metrics,
+            parameters: None,
+            catalog: None,
+            demography: None,
+            population_size: 2_000,
+            run_length: RunLength::Days(365),
+            method: SensitivityMethod::Morris { trajectories: 10, levels: 4 },
+            seed: None,
+        }
+    }
+
+    /// The parameters the design's values are set on (start date, scenario overrides, ...).
+    pub fn parameters(mut self, parameters: ParameterSet) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    /// The catalog of the runs (see `SimulationBuilder::catalog`).
+    pub fn catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    /// The demographic data of the runs (see `SimulationBuilder::demography`).
+    pub fn demography(mut self, demography: Arc<Demography>) -> Self {
+        self.demography = Some(demography);
+        self
+    }
+
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    pub fn run_length(mut self, run_length: RunLength) -> Self {
+        self.run_length = run_length;
+        self
+    }
+
+    pub fn method(mut self, method: SensitivityMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// The seed the design and the runs' seed are drawn from; drawn at random if not given.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Lays out the design, runs the model at every point and works out the statistics.
+    pub fn run(&self) -> Result<SensitivityResult, CalibrationError> {
+        if self.ranges.is_empty() || self.metrics.is_empty() {
+            return Err(BuildError::InvalidSetting("a sensitivity analysis needs parameter ranges and metrics".to_string()).into());
+        }
+        if let Some(range) = self.ranges.iter().find(|range| range.distribution.from_unit(0.5).is_none()) {
+            return Err(BuildError::InvalidSetting(format!("the range of \"{}\" must be uniform or log_uniform", range.key)).into());
+        }
+        let k = self.ranges.len();
+        let mut rng = individual_rng(self.seed.unwrap_or_else(random_master_seed), RngStream::Design, 0, 0);
+        let run_seed: u64 = rng.gen();
+
+        let too_small = match self.method {
+            SensitivityMethod::LatinHypercube { samples } => (samples < 3).then_some("a Latin hypercube needs at least 3 samples"),
+            SensitivityMethod::Morris { trajectories, levels } => {
+                (trajectories < 2 || levels < 2).then_some("a Morris design needs at least 2 trajectories and 2 levels")
+            }
+            SensitivityMethod::Sobol { samples } => (samples < 2).then_some("a Sobol design needs at least 2 samples"),
+        };
+        if let Some(message) = too_small {
+            return Err(BuildError::InvalidSetting(message.to_string()).into());
+        }
+
+        let design: Vec<Vec<f64>> = match self.method {
+            SensitivityMethod::LatinHypercube { samples } => latin_hypercube(&mut rng, samples, k),
+            SensitivityMethod::Morris { trajectories, levels } => {
+                (0..trajectories).flat_map(|_| morris_trajectory(&mut rng, k, levels)).collect()
+            }
+            SensitivityMethod::Sobol { samples } => sobol_design(&mut rng, samples, k),
+        };
+        let points: Vec<Vec<f64>> = design
+            .iter()
+            .map(|unit| self.ranges.iter().zip(unit).map(|(range, &u)| range.distribution.from_unit(u).expect("ranges are uniform or log-uniform")).collect())
+            .collect();
+        let catalog = self.catalog.clone().unwrap_or_else(|| Arc::new(Catalog::default_catalog()));
+        let outputs = points.par_iter().map(|point| self.run_point(point, run_seed)).collect::<Result<Vec<_>, _>>()?;
+
+        let mut indices = Vec::new();
+        for (i, range) in self.ranges.iter().enumerate() {
+            for (m, metric) in self.metrics.iter().enumerate() {
+                let y: Vec<f64> = outputs.iter().map(|output| output[m]).collect();
+                let values = match self.method {
+                    SensitivityMethod::LatinHypercube { .. } => {
+                        let x: Vec<f64> = design.iter().map(|unit| unit[i]).collect();
+                        vec![spearman(&x, &y)]
+                    }
+                    SensitivityMethod::Morris { .. } => morris_statistics(&design, &y, i),
+                    SensitivityMethod::Sobol { samples } => sobol_indices(&y, samples, i),
+                };
+                indices.push(SensitivityIndex { parameter: range.key.clone(), metric: metric.name(&catalog), values });
+            }
+        }
+
+        Ok(SensitivityResult {
+            method: self.method,
+            keys: self.ranges.iter().map(|range| range.key.clone()).collect(),
+            metrics: self.metrics.iter().map(|metric| metric.name(&catalog)).collect(),
+            points,
+            outputs,
+            indices,
+            seed: run_seed,
+        })
+    }
+
+    // The metric values of one run.
+    fn run_point(&self, values: &[f64], seed: u64) -> Result<Vec<f64>, CalibrationError> {
+        let mut builder =
+            Simulation::builder().population_size(self.population_size).run_length(self.run_length).seed(seed).quiet(true);
+        if let Some(parameters) = &self.parameters {
+            builder = builder.parameters(parameters.clone());
+        }
+        if let Some(catalog) = &self.catalog {
+            builder = builder.catalog(catalog.clone());
+        }
+        if let Some(demography) = &self.demography {
+            builder = builder.demography(demography.clone());
+        }
+        for (range, &value) in self.ranges.iter().zip(values) {
+            builder = builder.parameter(&range.key, value);
+        }
+        let collector = MetricsCollector::new(self.metrics.clone());
+        let metrics = collector.values_handle();
+        let mut simulation = builder.output(collector).build()?;
+        simulation.run().map_err(CalibrationError::Run)?;
+        let values = metrics.lock().unwrap().take().expect("the metrics are left when the run finishes");
+        Ok(values)
+    }
+}
+
+// `samples` points in the unit cube, one in each slice of every dimension.
+fn latin_hypercube(rng: &mut SimRng, samples: usize, k: usize) -> Vec<Vec<f64>> {
+    let mut points = vec![vec![0.0; k]; samples];
+    for i in 0..k {
+        let mut slices: Vec<usize> = (0..samples).collect();
+        slices.shuffle(rng);
+        for (point, slice) in points.iter_mut().zip(slices) {
+            point[i] = (slice as f64 + rng.gen::<f64>()) / samples as f64;
+        }
+    }
+    points
+}
+
+// Saltelli's design: the blocks A and B, then A with column i from B for each parameter i.
+fn sobol_design(rng: &mut SimRng, samples: usize, k: usize) -> Vec<Vec<f64>> {
+    let a = latin_hypercube(rng, samples, k);
+    let b = latin_hypercube(rng, samples, k);
+    let mut design = [a.clone(), b.clone()].concat();
+    for i in 0..k {
+        design.extend(a.iter().zip(&b).map(|(a_row, b_row)| {
+            let mut row = a_row.clone();
+            row[i] = b_row[i];
+            row
+        }));
+    }
+    design
+}
+
+// A Morris trajectory of k + 1 points, each differing from the one before in one parameter.
+fn morris_trajectory(rng: &mut SimRng, k: usize, levels: usize) -> Vec<Vec<f64>> {
+    let delta = morris_delta(levels);
+    let mut point: Vec<f64> = (0..k).map(|_| rng.gen_range(0..levels) as f64 / (levels - 1) as f64).collect();
+    let mut order: Vec<usize> = (0..k).collect();
+    order.shuffle(rng);
+    let mut trajectory = vec![point.clone()];
+    for i in order {
+        point[i] += if point[i] + delta <= 1.0 + 1e-9 { delta } else { -delta };
+        trajectory.push(point.clone());
+    }
+    trajectory
+}
+
+fn morris_delta(levels: usize) -> f64 {
+    levels as f64 / (2.0 * (levels - 1) as f64)
+}
+
+// mu, mu_star and sigma of the elementary effects of parameter i.
+fn morris_statistics(design: &[Vec<f64>], y: &[f64], i: usize) -> Vec<Option<f64>> {
+    let k = design[0].len();
+    let effects: Vec<f64> = design
+        .chunks(k + 1)
+        .zip(y.chunks(k + 1))
+        .filter_map(|(points, outputs)| {
+            // the step of the trajectory that moved parameter i
+            (1..=k)
+                .find(|&step| points[step][i] != points[step - 1][i])
+                .map(|step| (outputs[step] - outputs[step - 1]) / (points[step][i] - points[step - 1][i]))
+        })
+        .collect();
+    let n = effects.len() as f64;
+    let mu = effects.iter().sum::<f64>() / n;
+    let mu_star = effects.iter().map(|e| e.abs()).sum::<f64>() / n;
+    let sigma = (effects.iter().map(|e| (e - mu).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    vec![Some(mu), Some(mu_star), Some(sigma)]
+}
+
+// First- and total-order indices of parameter i from the outputs at A, B and the A-with-B-columns blocks.
+fn sobol_indices(y: &[f64], samples: usize, i: usize) -> Vec<Option<f64>> {
+    let (f_a, rest) = y.split_at(samples);
+    let (f_b, rest) = rest.split_at(samples);
+    let f_ab = &rest[i * samples..(i + 1) * samples];
+    let all: Vec<f64> = f_a.iter().chain(f_b).copied().collect();
+    let mean = all.iter().sum::<f64>() / all.len() as f64;
+    let variance = all.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / all.len() as f64;
+    if variance <= 0.0 {
+        return vec![None, None];
+    }
+    let n = samples as f64;
+    let first = f_a.iter().zip(f_b).zip(f_ab).map(|((a, b), ab)| b * (ab - a)).sum::<f64>() / n / variance;
+    let total = f_a.iter().zip(f_ab).map(|(a, ab)| (a - ab).powi(2)).sum::<f64>() / (2.0 * n) / variance;
+    vec![Some(first), Some(total)]
+}
+
+// Spearman's rank correlation, or None if either variable is constant.
+fn spearman(x: &[f64], y: &[f64]) -> Option<f64> {
+    let (rx, ry) = (ranks(x), ranks(y));
+    let n = x.len() as f64;
+    let mean = (n + 1.0) / 2.0;
+    let covariance: f64 = rx.iter().zip(&ry).map(|(a, b)| (a - mean) * (b - mean)).sum();
+    let sx: f64 = rx.iter().map(|a| (a - mean).powi(2)).sum();
+    let sy: f64 = ry.iter().map(|b| (b - mean).powi(2)).sum();
+    (sx > 0.0 && sy > 0.0).then(|| covariance / (sx * sy).sqrt())
+}
+
+// Ranks from 1, with ties given their average rank.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0; // average of start + 1 ..= end
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn close(value: Option<f64>, expected: f64, tolerance: f64) -> bool {
+        value.is_some_and(|v| (v - expected).abs() <= tolerance)
+    }
+
+    #[test]
+    fn ranks_average_ties() {
+        assert_eq!(ranks(&[3.0, 1.0, 2.0, 2.0]), vec![4.0, 1.0, 2.5, 2.5]);
+        assert_eq!(ranks(&[5.0]), vec![1.0]);
+        assert_eq!(ranks(&[7.0, 7.0, 7.0]), vec![2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn spearman_of_monotone_and_constant_variables() {
+        let x = [0.1, 0.4, 0.2, 0.9, 0.5];
+        let increasing: Vec<f64> = x.iter().map(|v| v * v * v).collect();
+        let decreasing: Vec<f64> = x.iter().map(|v| -v.exp()).collect();
+        assert!(close(spearman(&x, &increasing), 1.0, 1e-12));
+        assert!(close(spearman(&x, &decreasing), -1.0, 1e-12));
+        assert_eq!(spearman(&x, &[2.0; 5]), None);
+    }
+
+    #[test]
+    fn latin_hypercube_uses_every_slice_once() {
+        let mut rng = individual_rng(1, RngStream::Design, 0, 0);
+        let points = latin_hypercube(&mut rng, 10, 3);
+        for i in 0..3 {
+            let mut slices: Vec<usize> = points.iter().map(|point| (point[i] * 10.0) as usize).collect();
+            slices.sort_unstable();
+            assert_eq!(slices, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn morris_effects_of_a_linear_function() {
+        // y = 2 x0 - 5 x1, with x2 having no effect: every elementary effect is the coefficient
+        let mut rng = individual_rng(2, RngStream::Design, 0, 0);
+        let design: Vec<Vec<f64>> = (0..20).flat_map(|_| morris_trajectory(&mut rng, 3, 4)).collect();
+        let y: Vec<f64> = design.iter().map(|x| 2.0 * x[0] - 5.0 * x[1]).collect();
+        let expected = [(2.0, 2.0), (-5.0, 5.0), (0.0, 0.0)];
+        for (i, &(mu, mu_star)) in expected.iter().enumerate() {
+            let statistics = morris_statistics(&design, &y, i);
+            assert!(close(statistics[0], mu, 1e-9), "mu of x{}: {:?}", i, statistics);
+            assert!(close(statistics[1], mu_star, 1e-9), "mu_star of x{}: {:?}", i, statistics);
+            assert!(close(statistics[2], 0.0, 1e-9), "sigma of x{}: {:?}", i, statistics);
+        }
+    }
+
+    #[test]
+    fn sobol_indices_of_the_ishigami_function() {
+        // f = sin x1 + 7 sin^2 x2 + 0.1 x3^4 sin x1 on [-pi, pi]^3, with known indices
+        // first order 0.3139, 0.4424, 0; total order 0.5576, 0.4424, 0.2437
+        let samples = 20_000;
+        let mut rng = individual_rng(3, RngStream::Design, 0, 0);
+        let design = sobol_design(&mut rng, samples, 3);
+        let y: Vec<f64> = design
+            .iter()
+            .map(|unit| {
+                let x: Vec<f64> = unit.iter().map(|u| -PI + 2.0 * PI * u).collect();
+                x[0].sin() + 7.0 * x[1].sin().powi(2) + 0.1 * x[2].powi(4) * x[0].sin()
+            })
+            .collect();
+        let expected = [(0.3139, 0.5576), (0.4424, 0.4424), (0.0, 0.2437)];
+        for (i, &(first, total)) in expected.iter().enumerate() {
+            let indices = sobol_indices(&y, samples, i);
+            assert!(close(indices[0], first, 0.03), "first order of x{}: {:?}", i + 1, indices);
+            assert!(close(indices[1], total, 0.03), "total order of x{}: {:?}", i + 1, indices);
+        }
+    }
+
+    #[test]
+    fn sobol_indices_of_a_constant_are_empty() {
+        let mut rng = individual_rng(4, RngStream::Design, 0, 0);
+        let design = sobol_design(&mut rng, 10, 2);
+        assert_eq!(sobol_indices(&vec![1.0; design.len()], 10, 0), vec![None, None]);
+    }
+}
@@ -0,0 +1,439 @@
+// src/calibration/abc.rs
+//
+// Fitting parameters by approximate Bayesian computation (ABC): parameter sets are drawn from the priors
+// (priors.rs), the model is run with each (with a small population, quietly and in parallel), and the sets whose
+// runs come close enough to the calibration targets (targets.rs) are kept as a sample from the posterior.  The
+// distance of a run from the targets is its total weighted deviance (fit.rs), so 0 would be a perfect fit.
+//
+// Two methods:
+//
+//   rejection  `draws` sets are drawn from the priors and run; the `particles` closest are kept (or, with a
+//              tolerance, all those within it), with equal weights.
+//   smc        sequential Monte Carlo (population Monte Carlo, after Beaumont et al. 2009): generation 0 is
+//              `particles` sets drawn from the priors; each later generation lowers the tolerance to the `quantile`
+//              of the previous generation's distances and fills itself with sets within it, each made by taking a
+//              set of the previous generation (by weight) and moving every parameter by a normal step with twice
+//              the weighted variance of that parameter in the previous generation.  Steps are taken on the working
+//              scale of the prior (the log for log_uniform), and the new sets are weighted by prior density over
+//              the chance of being proposed.  It stops after `generations` generations, once the tolerance is
+//              down to the one given, or when a generation needs more than `max_runs_per_generation` runs (the
+//              last full generation is then the result).
+//
+// Every run has its own seed, and the sets, seeds and acceptance order are drawn from the fit's seed, not from the
+// order in which runs finish, so a fit repeats exactly for the same seed whatever the number of threads.
+//
+// The fit prints nothing; a caller that wants to follow it gives a `progress` callback, which is called with an
+// `AbcProgress` as each generation (or the rejection batch) finishes.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rayon::prelude::*;
+
+use crate::calibration::fit::CalibrationWriter;
+use crate::calibration::priors::Prior;
+use crate::calibration::targets::{CalibrationError, Target};
+use crate::config::catalog::Catalog;
+use crate::config::demography::Demography;
+use crate::config::loader::ParameterSet;
+use crate::simulation::builder::{BuildError, RunLength};
+use crate::simulation::output::csv_field;
+use crate::simulation::rng::{individual_rng, random_master_seed, RngStream, SimRng};
+use crate::simulation::simulation::Simulation;
+
+/// How the posterior is sampled (see the top of this file).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AbcMethod {
+    Rejection { draws: usize },
+    Smc { generations: usize, quantile: f64 },
+}
+
+/// A parameter set of the posterior sample, with the values in the order of the priors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub values: Vec<f64>,
+    pub weight: f64,
+    pub distance: f64,
+    pub seed: u64, // of the run that was accepted
+}
+
+/// The result of a fit: a weighted sample of parameter sets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posterior {
+    pub keys: Vec<String>,
+    pub particles: Vec<Particle>,
+    pub tolerance: f64,     // the largest distance accepted
+    pub generations: usize, // completed (1 for rejection)
+    pub runs: usize,        // model runs made
+}
+
+impl Posterior {
+    /// Writes the sample as CSV: weight, distance, seed, then one column per parameter key.
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let keys: Vec<String> = self.keys.iter().map(|key| csv_field(key)).collect();
+        writeln!(out, "weight,distance,seed,{}", keys.join(","))?;
+        for particle in &self.particles {
+            let values: Vec<String> = particle.values.iter().map(|v| v.to_string()).collect();
+            writeln!(out, "{},{},{},{}", particle.weight, particle.distance, particle.seed, values.join(","))?;
+        }
+        out.flush()
+    }
+}
+
+/// How far a fit has got, reported to the `progress` callback.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbcProgress {
+    /// The rejection batch has been run and the closest sets kept.
+    Rejection { accepted: usize, runs: usize, tolerance: f64 },
+    /// An SMC generation is complete (generation 0 is drawn from the priors).
+    Generation { generation: usize, particles: usize, tolerance: f64, runs: usize },
+    /// An SMC generation could not be filled within the runs allowed, so the fit stops at the one before.
+    Stopped { generation: usize, accepted: usize, particles: usize, tolerance: f64, runs: usize },
+}
+
+impl fmt::Display for AbcProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AbcProgress::Rejection { accepted, runs, tolerance } => {
+                write!(f, "rejection: {} of {} runs accepted, tolerance {:.4}", accepted, runs, tolerance)
+            }
+            AbcProgress::Generation { generation: 0, particles, tolerance, .. } => {
+                write!(f, "generation 0: {} particles from the priors, largest distance {:.4}", particles, tolerance)
+            }
+            AbcProgress::Generation { generation, particles, tolerance, runs } => {
+                write!(f, "generation {}: tolerance {:.4}, {} particles from {} runs", generation, tolerance, particles, runs)
+            }
+            AbcProgress::Stopped { generation, accepted, particles, tolerance, runs } => write!(
+                f,
+                "generation {}: only {} of {} particles within {:.4} after {} runs; stopping",
+                generation, accepted, particles, tolerance, runs
+            ),
+        }
+    }
+}
+
+type ProgressCallback = Box<dyn Fn(&AbcProgress) + Send + Sync>;
+
+/// An ABC fit of the priors' parameters to the targets.
+pub struct AbcFit {
+    priors: Vec<Prior>,
+    targets: Vec<Target>,
+    parameters: Option<ParameterSet>,
+    catalog: Option<Arc<Catalog>>,
+    demography: Option<Arc<Demography>>,
+    population_size: usize,
+    run_length: RunLength,
+    particles: usize,
+    method: AbcMethod,
+    tolerance: Option<f64>,
+    max_runs_per_generation: usize,
+    seed: Option<u64>,
+    progress: Option<ProgressCallback>,
+}
+
+impl AbcFit {
+    /// A fit with default parameters otherwise, 100 particles by ABC-SMC over 5 generations with quantile 0.5, and
+    /// runs of 2,000 individuals over 365 days.
+    pub fn new(priors: Vec<Prior>, targets: Vec<Target>) -> Self {
+        AbcFit {
+            priors,
+            targets,
+            parameters: None,
+            catalog: None,
+            demography: None,
+            population_size: 2_000,
+            run_length: RunLength::Days(365),
+            particles: 100,
+            method: AbcMethod::Smc { generations: 5, quantile: 0.5 },
+            tolerance: None,
+            max_runs_per_generation: 10_000,
+            seed: None,
+            progress: None,
+        }
+    }
+
+    /// The parameters the priors' values are set on (start date, scenario overrides, ...).
+    pub fn parameters(mut self, parameters: ParameterSet) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    /// The catalog of the runs (see `SimulationBuilder::catalog`).
+    pub fn catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    /// The demographic data of the runs (see `SimulationBuilder::demography`).
+    pub fn demography(mut self, demography: Arc<Demography>) -> Self {
+        self.demography = Some(demography);
+        self
+    }
+
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    pub fn run_length(mut self, run_length: RunLength) -> Self {
+        self.run_length = run_length;
+        self
+    }
+
+    /// The size of the posterior sample.
+    pub fn particles(mut self, particles: usize) -> Self {
+        self.particles = particles;
+        self
+    }
+
+    pub fn method(mut self, method: AbcMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// The largest distance to accept: for rejection, instead of keeping the closest sets; for SMC, where to stop.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// For SMC, the most runs a generation may take before the fit stops.
+    pub fn max_runs_per_generation(mut self, runs: usize) -> Self {
+        self.max_runs_per_generation = runs;
+        self
+    }
+
+    /// The seed everything in the fit is drawn from; drawn at random if not given.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Calls `callback` as the fit goes along (see `AbcProgress`).
+    pub fn progress<F: Fn(&AbcProgress) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    fn report(&self, progress: AbcProgress) {
+        if let Some(callback) = &self.progress {
+            callback(&progress);
+        }
+    }
+
+    /// Runs the fit.
+    pub fn run(&self) -> Result<Posterior, CalibrationError> {
+        if self.priors.is_empty() || self.targets.is_empty() || self.particles == 0 {
+            return Err(BuildError::InvalidSetting("a fit needs priors, targets and at least one particle".to_string()).into());
+        }
+        let seed = self.seed.unwrap_or_else(random_master_seed);
+        match self.method {
+            AbcMethod::Rejection { draws } => self.rejection(seed, draws),
+            AbcMethod::Smc { generations, quantile } => {
+                if quantile <= 0.0 || quantile >= 1.0 || quantile.is_nan() {
+                    return Err(BuildError::InvalidSetting(format!("the quantile {} is not between 0 and 1", quantile)).into());
+                }
+                self.smc(seed, generations.max(1), quantile)
+            }
+        }
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.priors.iter().map(|prior| prior.key.clone()).collect()
+    }
+
+    // The distance of one run from the targets.
+    fn distance(&self, values: &[f64], seed: u64) -> Result<f64, CalibrationError> {
+        let mut builder =
+            Simulation::builder().population_size(self.population_size).run_length(self.run_length).seed(seed).quiet(true);
+        if let Some(parameters) = &self.parameters {
+            builder = builder.parameters(parameters.clone());
+        }
+        if let Some(catalog) = &self.catalog {
+            builder = builder.catalog(catalog.clone());
+        }
+        if let Some(demography) = &self.demography {
+            builder = builder.demography(demography.clone());
+        }
+        for (prior, &value) in self.priors.iter().zip(values) {
+            builder = builder.parameter(&prior.key, value);
+        }
+        let writer = CalibrationWriter::new(io::sink(), self.targets.clone());
+        let report = writer.report_handle();
+        let mut simulation = builder.output(writer).build()?;
+        simulation.run().map_err(CalibrationError::Run)?;
+        let report = report.lock().unwrap().take().expect("the report is left when the run finishes");
+        if report.covered().next().is_none() {
+            return Err(BuildError::InvalidSetting("the runs do not reach any of the target years".to_string()).into());
+        }
+        Ok(report.total_deviance())
+    }
+
+    // Runs a batch of (values, seed) in parallel, returning the distances in order.
+    fn run_batch(&self, batch: &[(Vec<f64>, u64)]) -> Result<Vec<f64>, CalibrationError> {
+        batch.par_iter().map(|(values, seed)| self.distance(values, *seed)).collect()
+    }
+
+    fn draw_from_priors(&self, rng: &mut SimRng) -> (Vec<f64>, u64) {
+        let values = self.priors.iter().map(|prior| prior.distribution.sample(rng)).collect();
+        (values, rng.gen())
+    }
+
+    fn rejection(&self, seed: u64, draws: usize) -> Result<Posterior, CalibrationError> {
+        let mut rng = individual_rng(seed, RngStream::Fitting, 0, 0);
+        let batch: Vec<(Vec<f64>, u64)> = (0..draws.max(self.particles)).map(|_| self.draw_from_priors(&mut rng)).collect();
+        let distances = self.run_batch(&batch)?;
+
+        let accepted = closest(&distances, self.particles, self.tolerance);
+        let weight = 1.0 / accepted.len().max(1) as f64;
+        let particles: Vec<Particle> = accepted
+            .iter()
+            .map(|&i| Particle { values: batch[i].0.clone(), weight, distance: distances[i], seed: batch[i].1 })
+            .collect();
+        let tolerance = particles.iter().map(|p| p.distance).fold(0.0, f64::max);
+        self.report(AbcProgress::Rejection { accepted: particles.len(), runs: batch.len(), tolerance });
+        Ok(Posterior { keys: self.keys(), particles, tolerance, generations: 1, runs: batch.len() })
+    }
+
+    fn smc(&self, seed: u64, generations: usize, quantile: f64) -> Result<Posterior, CalibrationError> {
+        // generation 0: the priors
+        let mut rng = individual_rng(seed, RngStream::Fitting, 0, 0);
+        let batch: Vec<(Vec<f64>, u64)> = (0..self.particles).map(|_| self.draw_from_priors(&mut rng)).collect();
+        let distances = self.run_batch(&batch)?;
+        let weight = 1.0 / self.particles as f64;
+        let mut particles: Vec<Particle> = batch
+            .into_iter()
+            .zip(&distances)
+            .map(|((values, seed), &distance)| Particle { values, weight, distance, seed })
+            .collect();
+        let mut tolerance = distances.iter().copied().fold(0.0, f64::max);
+        let mut runs = self.particles;
+        let mut completed = 1;
+        self.report(AbcProgress::Generation { generation: 0, particles: self.particles, tolerance, runs });
+
+        for generation in 1..generations {
+            if self.tolerance.is_some_and(|target| tolerance <= target) {
+                break;
+            }
+            let mut sorted: Vec<f64> = particles.iter().map(|p| p.distance).collect();
+            sorted.sort_by(f64::total_cmp);
+            let mut next_tolerance = sorted[((sorted.len() - 1) as f64 * quantile) as usize];
+            if let Some(target) = self.tolerance {
+                next_tolerance = next_tolerance.max(target);
+            }
+
+            // the previous generation on the working scale, and the step size of each parameter
+            let working: Vec<Vec<f64>> = particles
+                .iter()
+                .map(|p| self.priors.iter().zip(&p.values).map(|(prior, &v)| prior.distribution.to_working(v)).collect())
+                .collect();
+            let step_sd: Vec<f64> = (0..self.priors.len())
+                .map(|k| {
+                    let mean: f64 = particles.iter().zip(&working).map(|(p, w)| p.weight * w[k]).sum();
+                    let variance: f64 = particles.iter().zip(&working).map(|(p, w)| p.weight * (w[k] - mean).powi(2)).sum();
+                    (2.0 * variance).sqrt().max(1e-12)
+                })
+                .collect();
+
+            let mut rng = individual_rng(seed, RngStream::Fitting, 0, generation);
+            let mut accepted: Vec<(Vec<f64>, Vec<f64>, f64, u64)> = Vec::new(); // (values, working values, distance, seed)
+            let mut generation_runs = 0;
+            while accepted.len() < self.particles && generation_runs < self.max_runs_per_generation {
+                let batch_size = (self.particles - accepted.len()).max(8).min(self.max_runs_per_generation - generation_runs);
+                let proposals: Vec<(Vec<f64>, Vec<f64>)> =
+                    (0..batch_size).map(|_| self.propose(&mut rng, &particles, &working, &step_sd)).collect();
+                let batch: Vec<(Vec<f64>, u64)> = proposals.iter().map(|(values, _)| (values.clone(), rng.gen())).collect();
+                let distances = self.run_batch(&batch)?;
+                generation_runs += batch.len();
+                for ((proposal, (_, seed)), distance) in proposals.into_iter().zip(batch).zip(distances) {
+                    if distance <= next_tolerance && accepted.len() < self.particles {
+                        accepted.push((proposal.0, proposal.1, distance, seed));
+                    }
+                }
+            }
+            runs += generation_runs;
+            if accepted.len() < self.particles {
+                self.report(AbcProgress::Stopped {
+                    generation,
+                    accepted: accepted.len(),
+                    particles: self.particles,
+                    tolerance: next_tolerance,
+                    runs: generation_runs,
+                });
+                break;
+            }
+
+            // importance weights: prior density over the density of being proposed from the previous generation
+            let kernel = |from: &[f64], to: &[f64]| -> f64 {
+                from.iter().zip(to).zip(&step_sd).map(|((a, b), sd)| (-(a - b).powi(2) / (2.0 * sd * sd)).exp() / sd).product()
+            };
+            let mut new_particles: Vec<Particle> = accepted
+                .into_iter()
+                .map(|(values, working_values, distance, seed)| {
+                    let prior: f64 =
+                        self.priors.iter().zip(&working_values).map(|(p, &w)| p.distribution.working_density(w).unwrap_or(0.0)).product();
+                    let proposal: f64 = particles.iter().zip(&working).map(|(p, w)| p.weight * kernel(w, &working_values)).sum();
+                    Particle { values, weight: prior / proposal.max(f64::MIN_POSITIVE), distance, seed }
+                })
+                .collect();
+            let total: f64 = new_particles.iter().map(|p| p.weight).sum();
+            for particle in &mut new_particles {
+                particle.weight /= total;
+            }
+            particles = new_particles;
+            tolerance = next_tolerance;
+            completed = generation + 1;
+            self.report(AbcProgress::Generation { generation, particles: self.particles, tolerance, runs: generation_runs });
+        }
+
+        Ok(Posterior { keys: self.keys(), particles, tolerance, generations: completed, runs })
+    }
+
+    // A set moved from one of the previous generation, inside the priors; returns (values, working values).
+    fn propose(&self, rng: &mut SimRng, particles: &[Particle], working: &[Vec<f64>], step_sd: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        loop {
+            let mut pick = rng.gen::<f64>();
+            let mut ancestor = particles.len() - 1;
+            for (i, particle) in particles.iter().enumerate() {
+                if pick < particle.weight {
+                    ancestor = i;
+                    break;
+                }
+                pick -= particle.weight;
+            }
+            let moved: Vec<f64> = working[ancestor].iter().zip(step_sd).map(|(&w, &sd)| w + sd * rng.sample::<f64, _>(StandardNormal)).collect();
+            if self.priors.iter().zip(&moved).all(|(prior, &w)| prior.distribution.working_density(w).unwrap_or(0.0) > 0.0) {
+                let values = self.priors.iter().zip(&moved).map(|(prior, &w)| prior.distribution.from_working(w)).collect();
+                return (values, moved);
+            }
+        }
+    }
+}
+
+// The runs a rejection fit keeps, closest first: those within `tolerance` if given, else the `particles` closest.
+fn closest(distances: &[f64], particles: usize, tolerance: Option<f64>) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..distances.len()).collect();
+    order.sort_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+    match tolerance {
+        Some(tolerance) => order.into_iter().filter(|&i| distances[i] <= tolerance).collect(),
+        None => order.into_iter().take(particles).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejection_keeps_the_closest_runs() {
+        let distances = [4.0, 0.5, 9.0, 2.0, 0.5, 7.0];
+        // ties keep their draw order
+        assert_eq!(closest(&distances, 3, None), [1, 4, 3]);
+        assert_eq!(closest(&distances, 10, None), [1, 4, 3, 0, 5, 2]);
+        assert_eq!(closest(&distances, 3, Some(4.0)), [1, 4, 3, 0]);
+        assert_eq!(closest(&distances, 3, Some(0.1)), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,360 @@
+// src/calibration/fit.rs
+//
+// Goodness of fit of a run to the calibration targets (targets.rs).
+//
+// `CalibrationWriter` is an output sink that gathers, during the run, the simulated statistic matching each target
+// (for the target's calendar year, bacteria, drug and age band), then scores each target with its likelihood and
+// writes one row per target as CSV:
+//
+//     target,year,bacteria,drug,age_band,observed,simulated,likelihood,log_likelihood,deviance,weight
+//     incidence,1990,escherichia coli,,0-4,1250,1102.6,poisson,-9.21,6.12,1
+//     ...
+//     overall,,,,,,,,-1234.5,567.8,
+//
+// The deviance is twice the log-likelihood lost against a perfect fit (the simulated value equal to the observed
+// one), so 0 is a perfect fit and, for a normal likelihood, it is the squared number of sds between the two.  The
+// overall row sums the log-likelihoods and deviances of the targets times their weights.  Targets for years the
+// run does not reach (or with no one in the stratum) have no simulated value and are left out of the overall sums.
+//
+// Person-years are counted as person-days / 365.25, over the days of the year that were run; a run covering part of
+// a year gives rates for that part.  Drug usage counts the DrugStarted events, so with drug_usage targets the
+// simulation records events.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use rayon::prelude::*;
+
+use crate::calibration::targets::{Likelihood, Target, TargetKind};
+use crate::config::catalog::Catalog;
+use crate::config::distribution::ln_gamma;
+use crate::simulation::events::{Event, EventRecord};
+use crate::simulation::output::{csv_field, merge_counts, OutputSink};
+use crate::simulation::simulation::Simulation;
+
+// Simulated values are kept at least this far from 0 (and proportions from 1), so a target whose event did not
+// happen in a small run gets a poor but finite score.
+const FLOOR: f64 = 1e-9;
+
+const DAYS_PER_YEAR: f64 = 365.25;
+
+// Ages are counted in whole years, the last group being this age and over.
+const OLDEST: u32 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Stat {
+    PersonDays,
+    NewInfections,
+    SepsisDeaths,
+    InfectedDays,
+    ResistantDays,
+    DrugStarts,
+}
+
+// (stat, bacteria, drug, age in years); NONE where the stat is not per bacteria, drug or age
+type Key = (Stat, usize, usize, u32);
+
+const NONE: usize = usize::MAX;
+
+type Counts = HashMap<Key, u64>;
+
+/// The fit of the run to one target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetFit {
+    pub target: Target,
+    pub simulated: Option<f64>, // None if the run did not cover the target
+    pub log_likelihood: Option<f64>,
+    pub deviance: Option<f64>,
+}
+
+/// The fit of a run to every target.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CalibrationReport {
+    pub fits: Vec<TargetFit>,
+}
+
+impl CalibrationReport {
+    /// The targets the run covered.
+    pub fn covered(&self) -> impl Iterator<Item = &TargetFit> {
+        self.fits.iter().filter(|fit| fit.simulated.is_some())
+    }
+
+    /// Weighted sum of the log-likelihoods of the covered targets.
+    pub fn total_log_likelihood(&self) -> f64 {
+        self.covered().map(|fit| fit.target.weight * fit.log_likelihood.unwrap_or(0.0)).sum()
+    }
+
+    /// Weighted sum of the deviances of the covered targets.
+    pub fn total_deviance(&self) -> f64 {
+        self.covered().map(|fit| fit.target.weight * fit.deviance.unwrap_or(0.0)).sum()
+    }
+
+    /// Writes the report as CSV (see the top of this file), naming bacteria and drugs from `catalog`.
+    pub fn write_csv<W: Write>(&self, catalog: &Catalog, out: &mut W) -> io::Result<()> {
+        let optional = |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+        writeln!(out, "target,year,bacteria,drug,age_band,observed,simulated,likelihood,log_likelihood,deviance,weight")?;
+        for fit in &self.fits {
+            let target = &fit.target;
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                target.kind.name(),
+                target.year,
+                csv_field(target.bacteria.map_or("", |b| &catalog.bacteria[b].name)),
+                csv_field(target.drug.map_or("", |d| &catalog.drugs[d].name)),
+                target.age.map_or(String::new(), |age| age.to_string()),
+                target.value,
+                optional(fit.simulated),
+                target.likelihood.name(),
+                optional(fit.log_likelihood),
+                optional(fit.deviance),
+                target.weight
+            )?;
+        }
+        writeln!(out, "overall,,,,,,,,{},{},", self.total_log_likelihood(), self.total_deviance())
+    }
+}
+
+/// Log-likelihood of a target's observed value given the simulated one, and the deviance.
+pub fn score(target: &Target, simulated: f64) -> (f64, f64) {
+    let (log_likelihood, saturated) = match target.likelihood {
+        Likelihood::Poisson => {
+            let person_years = target.denominator.unwrap_or(100_000.0);
+            let observed = target.value * person_years / 100_000.0;
+            let expected = (simulated * person_years / 100_000.0).max(FLOOR);
+            let log_poisson = |mean: f64| x_log_y(observed, mean) - mean - ln_gamma(observed + 1.0);
+            (log_poisson(expected), log_poisson(observed))
+        }
+        Likelihood::Binomial => {
+            let tested = target.denominator.unwrap_or(1.0);
+            let resistant = target.value * tested;
+            let log_binomial = |p: f64| {
+                ln_gamma(tested + 1.0) - ln_gamma(resistant + 1.0) - ln_gamma(tested - resistant + 1.0)
+                    + x_log_y(resistant, p)
+                    + x_log_y(tested - resistant, 1.0 - p)
+            };
+            (log_binomial(simulated.clamp(FLOOR, 1.0 - FLOOR)), log_binomial(target.value))
+        }
+        Likelihood::Normal => {
+            let sd = target.sd.unwrap_or(1.0);
+            let log_normal = |mean: f64| -0.5 * (2.0 * std::f64::consts::PI * sd * sd).ln() - (target.value - mean).powi(2) / (2.0 * sd * sd);
+            (log_normal(simulated), log_normal(target.value))
+        }
+    };
+    (log_likelihood, 2.0 * (saturated - log_likelihood).max(0.0))
+}
+
+// x ln y, taken as 0 when x is 0
+fn x_log_y(x: f64, y: f64) -> f64 {
+    if x == 0.0 {
+        0.0
+    } else {
+        x * y.ln()
+    }
+}
+
+/// Where the report of a `CalibrationWriter` is left at the end of the run, for code that wants the scores rather
+/// than the CSV.
+pub type ReportHandle = Arc<Mutex<Option<CalibrationReport>>>;
+
+/// Output sink comparing a run with calibration targets (see the top of this file).
+pub struct CalibrationWriter<W: Write + Send> {
+    out: W,
+    targets: Vec<Target>,
+    years: HashSet<i32>,
+    resistance_pairs: Vec<Vec<usize>>, // by bacteria, the drugs of resistance targets
+    counts: HashMap<i32, Counts>,      // by calendar year
+    report: ReportHandle,
+}
+
+impl<W: Write + Send> CalibrationWriter<W> {
+    /// Compares the run with `targets`, writing the report to `out` at the end.
+    pub fn new(out: W, targets: Vec<Target>) -> Self {
+        let years = targets.iter().map(|target| target.year).collect();
+        let num_bacteria = targets.iter().filter_map(|target| target.bacteria).max().map_or(0, |b_idx| b_idx + 1);
+        let mut resistance_pairs = vec![Vec::new(); num_bacteria];
+        for target in &targets {
+            if let (TargetKind::Resistance, Some(b_idx), Some(d_idx)) = (target.kind, target.bacteria, target.drug) {
+                if !resistance_pairs[b_idx].contains(&d_idx) {
+                    resistance_pairs[b_idx].push(d_idx);
+                }
+            }
+        }
+        CalibrationWriter { out, targets, years, resistance_pairs, counts: HashMap::new(), report: ReportHandle::default() }
+    }
+
+    /// A handle to the report, filled in when the run finishes.
+    pub fn report_handle(&self) -> ReportHandle {
+        Arc::clone(&self.report)
+    }
+
+    // (a free function of the resistance pairs, so the rayon closures do not need `W: Sync`)
+    fn count_day(resistance_pairs: &[Vec<usize>], simulation: &Simulation, time_step: usize) -> Counts {
+        simulation
+            .population
+            .par_iter()
+            // alive at the start of the day
+            .filter(|i| i.date_of_death.is_none_or(|day| day == time_step))
+            .fold(Counts::new, |mut counts, individual| {
+                let age = ((individual.age / 365) as u32).min(OLDEST);
+                let mut add = |stat, b_idx, d_idx| *counts.entry((stat, b_idx, d_idx, age)).or_insert(0) += 1;
+                add(Stat::PersonDays, NONE, NONE);
+                let died_of_sepsis = individual.date_of_death == Some(time_step)
+                    && individual.cause_of_death == Some("sepsis_related");
+                for b_idx in 0..individual.level.len() {
+                    if died_of_sepsis && individual.sepsis[b_idx] {
+                        add(Stat::SepsisDeaths, b_idx, NONE);
+                    }
+                    if individual.level[b_idx] <= 0.001 {
+                        continue;
+                    }
+                    add(Stat::InfectedDays, b_idx, NONE);
+                    if individual.date_last_infected[b_idx] == time_step as i32 {
+                        add(Stat::NewInfections, b_idx, NONE);
+                    }
+                    for &d_idx in resistance_pairs.get(b_idx).into_iter().flatten() {
+                        if individual.resistances[b_idx][d_idx].any_r > 0.0 {
+                            add(Stat::ResistantDays, b_idx, d_idx);
+                        }
+                    }
+                }
+                counts
+            })
+            .reduce(Counts::new, merge_counts)
+    }
+
+    // The sum of a count over the ages of the target.
+    fn total(&self, target: &Target, stat: Stat, b_idx: usize, d_idx: usize) -> f64 {
+        let Some(counts) = self.counts.get(&target.year) else {
+            return 0.0;
+        };
+        (0..=OLDEST)
+            .filter(|&age| target.age.is_none_or(|range| range.contains(age)))
+            .filter_map(|age| counts.get(&(stat, b_idx, d_idx, age)))
+            .sum::<u64>() as f64
+    }
+
+    fn simulated(&self, target: &Target) -> Option<f64> {
+        let b_idx = target.bacteria.unwrap_or(NONE);
+        let d_idx = target.drug.unwrap_or(NONE);
+        let (numerator, denominator) = match target.kind {
+            TargetKind::DrugUsage => (
+                self.total(target, Stat::DrugStarts, NONE, d_idx), // drug usage targets are for all ages
+                self.total(target, Stat::PersonDays, NONE, NONE) / DAYS_PER_YEAR / 100_000.0,
+            ),
+            TargetKind::Incidence => (
+                self.total(target, Stat::NewInfections, b_idx, NONE),
+                self.total(target, Stat::PersonDays, NONE, NONE) / DAYS_PER_YEAR / 100_000.0,
+            ),
+            TargetKind::Deaths => (
+                self.total(target, Stat::SepsisDeaths, b_idx, NONE),
+                self.total(target, Stat::PersonDays, NONE, NONE) / DAYS_PER_YEAR / 100_000.0,
+            ),
+            TargetKind::Resistance => {
+                (self.total(target, Stat::ResistantDays, b_idx, d_idx), self.total(target, Stat::InfectedDays, b_idx, NONE))
+            }
+        };
+        (denominator > 0.0).then(|| numerator / denominator)
+    }
+}
+
+impl<W: Write + Send> OutputSink for CalibrationWriter<W> {
+    fn wants_events(&self) -> bool {
+        self.targets.iter().any(|target| target.kind == TargetKind::DrugUsage)
+    }
+
+    fn events(&mut self, simulation: &Simulation, time_step: usize, events: &[EventRecord]) -> io::Result<()> {
+        let year = simulation.date_at(time_step).year;
+        if !self.years.contains(&year) {
+            return Ok(());
+        }
+        let counts = self.counts.entry(year).or_default();
+        for record in events {
+            if let Event::DrugStarted { drug, .. } = record.event {
+                *counts.entry((Stat::DrugStarts, NONE, drug, 0)).or_insert(0) += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, simulation: &Simulation, time_step: usize) -> io::Result<()> {
+        let year = simulation.date_at(time_step).year;
+        if self.years.contains(&year) {
+            let day = Self::count_day(&self.resistance_pairs, simulation, time_step);
+            let counts = self.counts.entry(year).or_default();
+            *counts = merge_counts(std::mem::take(counts), day);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, simulation: &Simulation) -> io::Result<()> {
+        let fits = self
+            .targets
+            .iter()
+            .map(|target| {
+                let simulated = self.simulated(target);
+                let scores = simulated.map(|value| score(target, value));
+                TargetFit {
+                    target: target.clone(),
+                    simulated,
+                    log_likelihood: scores.map(|(log_likelihood, _)| log_likelihood),
+                    deviance: scores.map(|(_, deviance)| deviance),
+                }
+            })
+            .collect();
+        let report = CalibrationReport { fits };
+        report.write_csv(&simulation.catalog, &mut self.out)?;
+        *self.report.lock().unwrap() = Some(report);
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target_with(likelihood: Likelihood, value: f64, denominator: Option<f64>, sd: Option<f64>) -> Target {
+        let kind = if likelihood == Likelihood::Binomial { TargetKind::Resistance } else { TargetKind::Incidence };
+        Target { kind, year: 2000, bacteria: Some(0), drug: None, age: None, value, denominator, sd, likelihood, weight: 1.0 }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn poisson_score() {
+        // 100 cases observed in 100,000 person-years
+        let target = target_with(Likelihood::Poisson, 100.0, None, None);
+        let (log_likelihood, deviance) = score(&target, 100.0);
+        assert!(close(log_likelihood, 100.0 * 100.0f64.ln() - 100.0 - ln_gamma(101.0)));
+        assert_eq!(deviance, 0.0);
+        let (_, deviance) = score(&target, 120.0);
+        assert!(close(deviance, 2.0 * (100.0 * (100.0f64 / 120.0).ln() + 20.0)));
+        // the rate is per 100,000 person-years whatever the denominator
+        let (_, deviance) = score(&target_with(Likelihood::Poisson, 100.0, Some(200_000.0), None), 120.0);
+        assert!(close(deviance, 2.0 * (200.0 * (200.0f64 / 240.0).ln() + 40.0)));
+    }
+
+    #[test]
+    fn binomial_score() {
+        // 30 of 100 isolates resistant
+        let target = target_with(Likelihood::Binomial, 0.3, Some(100.0), None);
+        let (log_likelihood, deviance) = score(&target, 0.5);
+        let ln_choose = ln_gamma(101.0) - ln_gamma(31.0) - ln_gamma(71.0);
+        assert!(close(log_likelihood, ln_choose + 100.0 * 0.5f64.ln()));
+        assert!(close(deviance, 2.0 * (30.0 * (0.3f64 / 0.5).ln() + 70.0 * (0.7f64 / 0.5).ln())));
+        assert_eq!(score(&target, 0.3).1, 0.0);
+        // a simulated proportion of 0 gives a poor but finite score
+        assert!(score(&target, 0.0).0.is_finite());
+    }
+
+    #[test]
+    fn normal_score() {
+        let target = target_with(Likelihood::Normal, 10.0, None, Some(2.0));
+        let (log_likelihood, deviance) = score(&target, 13.0);
+        assert!(close(log_likelihood, -0.5 * (2.0 * std::f64::consts::PI * 4.0).ln() - 9.0 / 8.0));
+        assert!(close(deviance, 9.0 / 4.0));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod abc; // fitting parameters to the targets by ABC rejection or ABC-SMC
+pub mod fit; // simulated statistics matching the targets, likelihoods and the goodness-of-fit report
+pub mod priors; // the parameters a fit varies and their prior distributions
+pub mod targets; // observed statistics (drug usage, incidence, deaths, resistance) read from CSV files
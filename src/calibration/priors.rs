@@ -0,0 +1,121 @@
+// src/calibration/priors.rs
+//
+// Priors for a fit: which parameters to vary, and over what distribution (see config/distribution.rs).  A priors
+// file is TOML (or JSON) mapping parameter keys to distribution tables:
+//
+//     "escherichia coli_acquisition_prob_baseline" = { distribution = "log_uniform", low = 0.0002, high = 0.01 }
+//     "*_acquisition_prob_baseline" = { distribution = "log_uniform", low = 0.0001, high = 0.01 }
+//
+// Any distribution but "empirical" (which has no density) can be a prior.  A '*' in a key matches any run of
+// characters, so one entry can give every bacteria (or every bacteria-drug pair, as in
+// "drug_*_for_bacteria_*_resistance_emergence_rate_per_day_baseline") its own parameter with the same prior.  The
+// keys are matched against the parameter registry (schema.rs); an entry matching nothing, a key matched by two
+// entries and a prior reaching outside the range allowed for the key are errors.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::calibration::targets::CalibrationError;
+use crate::config::catalog::Catalog;
+use crate::config::distribution::ParameterDistribution;
+use crate::config::loader::ParameterFileFormat;
+use crate::config::schema::{ParamKind, ParameterSchema};
+
+/// The prior of one parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prior {
+    pub key: String,
+    pub distribution: ParameterDistribution,
+}
+
+/// Reads a priors file, giving one prior per parameter key of `catalog`'s registry, in registry order.
+pub fn load_priors(catalog: &Catalog, path: &Path) -> Result<Vec<Prior>, CalibrationError> {
+    let invalid = |message: String| CalibrationError::Invalid { kind: "priors file", path: path.to_path_buf(), message };
+    let contents = fs::read_to_string(path).map_err(|source| CalibrationError::Io { path: path.to_path_buf(), source })?;
+    let parsed: Value = match ParameterFileFormat::from_path(path) {
+        Some(ParameterFileFormat::Toml) => toml::from_str(&contents).map_err(|e| invalid(e.to_string()))?,
+        Some(ParameterFileFormat::Json) => serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))?,
+        None => return Err(invalid("a priors file must have a .toml or .json extension".to_string())),
+    };
+    let Value::Object(entries) = parsed else {
+        return Err(invalid("expected a table of parameter keys and distributions at the top level".to_string()));
+    };
+
+    let schema = ParameterSchema::for_catalog(catalog);
+    let mut priors: Vec<(usize, Prior, &str)> = Vec::new(); // (registry position, prior, entry it came from)
+    for (pattern, spec) in &entries {
+        let Value::Object(spec) = spec else {
+            return Err(invalid(format!("\"{}\" should be a distribution table", pattern)));
+        };
+        let distribution =
+            ParameterDistribution::from_table(spec, pattern, path).map_err(|message| invalid(format!("\"{}\": {}", pattern, message)))?;
+        if let ParameterDistribution::Empirical { .. } = distribution {
+            return Err(invalid(format!("\"{}\": a prior needs a density, which an empirical distribution does not have", pattern)));
+        }
+        let (low, high) = distribution.bounds();
+        let mut matched = false;
+        for (position, spec) in schema.iter().enumerate() {
+            if spec.kind != ParamKind::Number || !matches(pattern, &spec.key) {
+                continue;
+            }
+            matched = true;
+            if low < spec.min || high > spec.max {
+                return Err(invalid(format!(
+                    "the prior of \"{}\" ({} to {}) reaches outside its allowed range {} to {}",
+                    spec.key, low, high, spec.min, spec.max
+                )));
+            }
+            if let Some((_, _, other)) = priors.iter().find(|(_, prior, _)| prior.key == spec.key) {
+                return Err(invalid(format!("\"{}\" is matched by both \"{}\" and \"{}\"", spec.key, other, pattern)));
+            }
+            priors.push((position, Prior { key: spec.key.clone(), distribution: distribution.clone() }, pattern));
+        }
+        if !matched {
+            return Err(invalid(format!("\"{}\" matches no numeric parameter", pattern)));
+        }
+    }
+    priors.sort_by_key(|(position, _, _)| *position);
+    Ok(priors.into_iter().map(|(_, prior, _)| prior).collect())
+}
+
+// Whether `key` matches `pattern`, where '*' stands for any run of characters.
+fn matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty(); // no '*'
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_patterns_match_keys() {
+        let key = "escherichia coli_acquisition_prob_baseline";
+        assert!(matches(key, key));
+        assert!(!matches("escherichia coli_acquisition_prob", key));
+        assert!(matches("*_acquisition_prob_baseline", key));
+        assert!(matches("escherichia coli_*", key));
+        assert!(matches("*coli*prob*", key));
+        assert!(matches("*", key));
+        assert!(!matches("*_clearance_prob_baseline", key));
+        assert!(!matches("klebsiella pneumoniae_*", key));
+        // the prefix and suffix may not overlap
+        assert!(!matches("escherichia coli_acquisition*acquisition_prob_baseline", key));
+    }
+}
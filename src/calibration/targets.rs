@@ -0,0 +1,382 @@
+// src/calibration/targets.rs
+//
+// Calibration targets: observed statistics the model should reproduce, read from CSV files with a header row.
+// Lines starting with '#' and blank lines are skipped.  Columns (in any order; only target, year and value are
+// needed by every row):
+//
+//     target       drug_usage   courses of a drug started per 100,000 person-years          [drug]
+//                  incidence    infections with a bacteria acquired per 100,000 person-years [bacteria, age_band]
+//                  deaths       sepsis-related deaths with a bacteria per 100,000 person-years [bacteria, age_band]
+//                  resistance   proportion (0-1) of infections with a bacteria that have any_r > 0 to a drug
+//                               (over infected person-days)                                 [bacteria, drug, age_band]
+//     year         calendar year the value is for
+//     bacteria     bacteria name, as in the catalog
+//     drug         drug name, as in the catalog
+//     age_band     e.g. 15-49 or 70+ (years); empty for all ages
+//     value        the observed value
+//     denominator  for rates, the person-years the observed rate is based on (default 100,000); for resistance,
+//                  the number of isolates tested (required for a binomial likelihood)
+//     sd           standard deviation of the observed value (required for a normal likelihood)
+//     likelihood   poisson, binomial or normal (default: poisson for rates, binomial for resistance)
+//     weight       multiplies the target's contribution to the overall fit (default 1)
+//
+// For example:
+//
+//     target,year,bacteria,drug,age_band,value,denominator
+//     incidence,1990,escherichia coli,,0-4,1250,250000
+//     resistance,1990,escherichia coli,amoxicillin,,0.32,410
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::catalog::Catalog;
+use crate::simulation::builder::BuildError;
+
+/// Errors raised while reading a targets or priors file, or while making the model runs of a fit or an analysis.
+#[derive(Debug)]
+pub enum CalibrationError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, line: usize, message: String },
+    Invalid { kind: &'static str, path: PathBuf, message: String }, // kind of file, e.g. "priors file"
+    Build(BuildError),
+    Run(std::io::Error), // an output of a model run failed
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CalibrationError::Io { path, source } => write!(f, "could not read {}: {}", path.display(), source),
+            CalibrationError::Parse { path, line, message } => {
+                write!(f, "invalid calibration target at {} line {}: {}", path.display(), line, message)
+            }
+            CalibrationError::Invalid { kind, path, message } => write!(f, "invalid {} {}: {}", kind, path.display(), message),
+            CalibrationError::Build(e) => write!(f, "{}", e),
+            CalibrationError::Run(e) => write!(f, "a model run failed to write its outputs: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CalibrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CalibrationError::Io { source, .. } => Some(source),
+            CalibrationError::Build(e) => Some(e),
+            CalibrationError::Run(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<BuildError> for CalibrationError {
+    fn from(e: BuildError) -> Self {
+        CalibrationError::Build(e)
+    }
+}
+
+/// What a target measures (see the top of this file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetKind {
+    DrugUsage,
+    Incidence,
+    Deaths,
+    Resistance,
+}
+
+impl TargetKind {
+    pub const ALL: [TargetKind; 4] = [TargetKind::DrugUsage, TargetKind::Incidence, TargetKind::Deaths, TargetKind::Resistance];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetKind::DrugUsage => "drug_usage",
+            TargetKind::Incidence => "incidence",
+            TargetKind::Deaths => "deaths",
+            TargetKind::Resistance => "resistance",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TargetKind> {
+        TargetKind::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    /// Rates per 100,000 person-years, as opposed to a proportion.
+    pub fn is_rate(&self) -> bool {
+        !matches!(self, TargetKind::Resistance)
+    }
+}
+
+/// How the observed value is assumed to vary around the simulated one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Likelihood {
+    /// the observed count (value × denominator / 100,000) is Poisson with the simulated rate
+    Poisson,
+    /// the observed number resistant (value × denominator) is binomial with the simulated proportion
+    Binomial,
+    /// the observed value is normal around the simulated value, with the target's sd
+    Normal,
+}
+
+impl Likelihood {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Likelihood::Poisson => "poisson",
+            Likelihood::Binomial => "binomial",
+            Likelihood::Normal => "normal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Likelihood> {
+        [Likelihood::Poisson, Likelihood::Binomial, Likelihood::Normal].into_iter().find(|l| l.name() == name)
+    }
+}
+
+/// An age band in whole years, `max_years` included; no upper limit if `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AgeRange {
+    pub min_years: u32,
+    pub max_years: Option<u32>,
+}
+
+impl AgeRange {
+    /// Parses "15-49" or "70+".
+    pub fn parse(text: &str) -> Option<AgeRange> {
+        if let Some(min) = text.strip_suffix('+') {
+            return Some(AgeRange { min_years: min.trim().parse().ok()?, max_years: None });
+        }
+        let (min, max) = text.split_once('-')?;
+        let range = AgeRange { min_years: min.trim().parse().ok()?, max_years: Some(max.trim().parse().ok()?) };
+        (range.max_years >= Some(range.min_years)).then_some(range)
+    }
+
+    pub fn contains(&self, years: u32) -> bool {
+        years >= self.min_years && self.max_years.is_none_or(|max| years <= max)
+    }
+}
+
+impl fmt::Display for AgeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max_years {
+            Some(max) => write!(f, "{}-{}", self.min_years, max),
+            None => write!(f, "{}+", self.min_years),
+        }
+    }
+}
+
+/// One observed statistic.  Bacteria and drugs are indices into the catalog's bacteria and drugs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub kind: TargetKind,
+    pub year: i32,
+    pub bacteria: Option<usize>,
+    pub drug: Option<usize>,
+    pub age: Option<AgeRange>, // all ages if None
+    pub value: f64,
+    pub denominator: Option<f64>,
+    pub sd: Option<f64>,
+    pub likelihood: Likelihood,
+    pub weight: f64,
+}
+
+const COLUMNS: [&str; 10] =
+    ["target", "year", "bacteria", "drug", "age_band", "value", "denominator", "sd", "likelihood", "weight"];
+
+/// Reads the targets in the given files, in order, with bacteria and drug names from `catalog`.
+pub fn load_targets<P: AsRef<Path>>(catalog: &Catalog, paths: &[P]) -> Result<Vec<Target>, CalibrationError> {
+    let mut targets = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|source| CalibrationError::Io { path: path.to_path_buf(), source })?;
+        targets.extend(parse_targets(catalog, &text).map_err(|(line, message)| CalibrationError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        })?);
+    }
+    Ok(targets)
+}
+
+/// Reads targets from the text of a targets file; errors come with their line number.
+pub fn parse_targets(catalog: &Catalog, text: &str) -> Result<Vec<Target>, (usize, String)> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let (header_line, header) = lines.next().ok_or((1, "the file has no header row".to_string()))?;
+    let header: Vec<&str> = header.split(',').map(str::trim).collect();
+    for name in &header {
+        if !COLUMNS.contains(name) {
+            return Err((header_line, format!("unknown column \"{}\" (the columns are {})", name, COLUMNS.join(", "))));
+        }
+    }
+    for required in ["target", "year", "value"] {
+        if !header.contains(&required) {
+            return Err((header_line, format!("the \"{}\" column is missing", required)));
+        }
+    }
+
+    lines
+        .map(|(line_number, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != header.len() {
+                return Err((line_number, format!("{} fields where the header has {}", fields.len(), header.len())));
+            }
+            let fields: HashMap<&str, &str> =
+                header.iter().copied().zip(fields).filter(|(_, field)| !field.is_empty()).collect();
+            parse_target(catalog, &fields).map_err(|message| (line_number, message))
+        })
+        .collect()
+}
+
+// The non-empty fields of a row, by column name.
+fn parse_target(catalog: &Catalog, fields: &HashMap<&str, &str>) -> Result<Target, String> {
+    let field = |name: &str| fields.get(name).copied();
+    let number = |name: &str| -> Result<Option<f64>, String> {
+        field(name)
+            .map(|text| text.parse::<f64>().map_err(|_| format!("{} \"{}\" is not a number", name, text)))
+            .transpose()
+    };
+
+    let kind = field("target").ok_or("the target is empty")?;
+    let kind = TargetKind::from_name(kind).ok_or_else(|| {
+        let names: Vec<&str> = TargetKind::ALL.iter().map(|k| k.name()).collect();
+        format!("unknown target \"{}\" (one of {})", kind, names.join(", "))
+    })?;
+    let year = field("year").ok_or("the year is empty")?;
+    let year = year.parse::<i32>().map_err(|_| format!("year \"{}\" is not a year", year))?;
+    let bacteria = field("bacteria")
+        .map(|name| catalog.bacteria.iter().position(|b| b.name == name).ok_or_else(|| format!("unknown bacteria \"{}\"", name)))
+        .transpose()?;
+    let drug = field("drug")
+        .map(|name| catalog.drugs.iter().position(|d| d.name == name).ok_or_else(|| format!("unknown drug \"{}\"", name)))
+        .transpose()?;
+    let age = field("age_band")
+        .map(|text| AgeRange::parse(text).ok_or_else(|| format!("age band \"{}\" is not like 15-49 or 70+", text)))
+        .transpose()?;
+    let value = number("value")?.ok_or("the value is empty")?;
+    let denominator = number("denominator")?;
+    let sd = number("sd")?;
+    let likelihood = match field("likelihood") {
+        Some(name) => Likelihood::from_name(name).ok_or_else(|| format!("unknown likelihood \"{}\"", name))?,
+        None if kind.is_rate() => Likelihood::Poisson,
+        None => Likelihood::Binomial,
+    };
+    let weight = number("weight")?.unwrap_or(1.0);
+
+    let (needs_bacteria, needs_drug) = match kind {
+        TargetKind::DrugUsage => (false, true),
+        TargetKind::Incidence | TargetKind::Deaths => (true, false),
+        TargetKind::Resistance => (true, true),
+    };
+    if needs_bacteria != bacteria.is_some() {
+        return Err(format!("a {} target {} a bacteria", kind.name(), if needs_bacteria { "needs" } else { "has no" }));
+    }
+    if needs_drug != drug.is_some() {
+        return Err(format!("a {} target {} a drug", kind.name(), if needs_drug { "needs" } else { "has no" }));
+    }
+    if kind == TargetKind::DrugUsage && age.is_some() {
+        return Err("drug_usage targets are for all ages".to_string());
+    }
+    if value < 0.0 || (!kind.is_rate() && value > 1.0) {
+        return Err(format!("value {} is out of range", value));
+    }
+    if weight < 0.0 {
+        return Err(format!("weight {} is negative", weight));
+    }
+    match likelihood {
+        Likelihood::Binomial if kind.is_rate() => return Err("a binomial likelihood is for resistance targets".to_string()),
+        Likelihood::Binomial if denominator.is_none_or(|n| n < 1.0) => {
+            return Err("a binomial likelihood needs the number tested as the denominator".to_string())
+        }
+        Likelihood::Poisson if !kind.is_rate() => return Err("a poisson likelihood is for rates".to_string()),
+        Likelihood::Normal if sd.is_none_or(|sd| sd <= 0.0) => {
+            return Err("a normal likelihood needs a positive sd".to_string())
+        }
+        _ => {}
+    }
+
+    Ok(Target { kind, year, bacteria, drug, age, value, denominator, sd, likelihood, weight })
+}
+
+impl Target {
+    /// A short description, e.g. "incidence 1990 escherichia coli 0-4", with the names of `catalog`.
+    pub fn label(&self, catalog: &Catalog) -> String {
+        let mut label = format!("{} {}", self.kind.name(), self.year);
+        if let Some(b_idx) = self.bacteria {
+            label.push(' ');
+            label.push_str(&catalog.bacteria[b_idx].name);
+        }
+        if let Some(d_idx) = self.drug {
+            label.push(' ');
+            label.push_str(&catalog.drugs[d_idx].name);
+        }
+        if let Some(age) = self.age {
+            label.push_str(&format!(" {}", age));
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_rows_of_a_targets_file() {
+        let catalog = Catalog::default_catalog();
+        let text = "# targets\n\
+                    target,year,bacteria,drug,age_band,value,denominator\n\
+                    \n\
+                    incidence,1990,escherichia coli,,0-4,1250,250000\n\
+                    resistance,1990,escherichia coli,amoxicillin,,0.32,410\n";
+        let targets = parse_targets(&catalog, text).unwrap();
+        let e_coli = catalog.bacteria.iter().position(|b| b.name == "escherichia coli");
+        let amoxicillin = catalog.drugs.iter().position(|d| d.name == "amoxicillin");
+        assert_eq!(
+            targets,
+            [
+                Target {
+                    kind: TargetKind::Incidence,
+                    year: 1990,
+                    bacteria: e_coli,
+                    drug: None,
+                    age: Some(AgeRange { min_years: 0, max_years: Some(4) }),
+                    value: 1250.0,
+                    denominator: Some(250_000.0),
+                    sd: None,
+                    likelihood: Likelihood::Poisson,
+                    weight: 1.0,
+                },
+                Target {
+                    kind: TargetKind::Resistance,
+                    year: 1990,
+                    bacteria: e_coli,
+                    drug: amoxicillin,
+                    age: None,
+                    value: 0.32,
+                    denominator: Some(410.0),
+                    sd: None,
+                    likelihood: Likelihood::Binomial,
+                    weight: 1.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_come_with_the_line_of_the_file() {
+        let catalog = Catalog::default_catalog();
+        let error = |text: &str| parse_targets(&catalog, text).unwrap_err();
+        assert_eq!(error("").0, 1);
+        assert!(error("target,year,value,colour\n").1.contains("unknown column \"colour\""));
+        assert!(error("target,value\n").1.contains("\"year\" column is missing"));
+        // (comment and blank lines are counted)
+        let (line, message) = error("# targets\ntarget,year,bacteria,value\n\nincidence,1990,,12\n");
+        assert_eq!((line, message.as_str()), (4, "a incidence target needs a bacteria"));
+        assert!(error("target,year,bacteria,value\nincidence,1990,unobtainium,12\n").1.contains("unknown bacteria"));
+        assert!(error("target,year,bacteria,drug,value\nresistance,1990,escherichia coli,amoxicillin,1.5\n")
+            .1
+            .contains("out of range"));
+    }
+}
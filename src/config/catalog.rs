@@ -0,0 +1,672 @@
+// src/config/catalog.rs
+//
+// The bacteria and drug catalog: which pathogens and antibiotics the model contains, and their fixed attributes, and
+// the regions people live in and travel between.
+//
+// The catalog is read from a .toml or .json file (see catalogs/default.toml for the format and the compiled-in
+// default).  A catalog without regions has those of the default catalog.  The order of the entries is the index
+// order used everywhere else: per-individual vectors are sized from it, and the parameter defaults and registry are
+// built over it.  A simulation carries the catalog it was built with (see `SimulationBuilder::catalog`), and the
+// parameters, population and outputs of that simulation take their names from it.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::loader::ParameterFileFormat;
+use crate::config::{region_key_suffixes, AGE_RISK_TEMPLATES};
+
+// The default catalog, compiled into the binary.
+const DEFAULT_CATALOG: &str = include_str!("../../catalogs/default.toml");
+
+/// Highest infectious syndrome id.
+pub const MAX_SYNDROME_ID: u32 = 10;
+
+/// The regions with parameter defaults compiled into config.rs, which a catalog region can take its defaults from.
+pub const BUILT_IN_REGIONS: [&str; 6] = ["north_america", "south_america", "africa", "asia", "europe", "oceania"];
+
+/// Broad bacterial groups, which select the default drug potency pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GramGroup {
+    GramPositiveCocci,
+    GramNegativeEnterobacterales,
+    GramNegativeNonFermenting,
+    GramNegativeFastidious,
+    EntericPathogen,
+    Atypical,
+    AnaerobeSporeForming,
+    GramPositiveRod,
+}
+
+/// Sepsis risk category of a bacterium, scaling its sepsis risk by the matching "{category}_sepsis_risk_multiplier".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SepsisRiskCategory {
+    High,
+    #[default]
+    Moderate,
+    Low,
+}
+
+/// Ways a drug can be given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrugRoute {
+    Oral,
+    Intravenous,
+    Intramuscular,
+    Topical,
+}
+
+/// One bacterium in the catalog.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BacteriumSpec {
+    pub name: String,
+    pub gram_group: GramGroup,
+    #[serde(default)]
+    pub sepsis_risk: SepsisRiskCategory,
+    #[serde(default = "default_age_risk_template")]
+    pub age_risk_template: String,
+    #[serde(default = "default_syndromes")]
+    pub syndromes: Vec<(u32, f64)>, // (syndrome id, probability) for the syndrome of a new infection
+}
+
+/// Antibiotic classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrugClass {
+    Penicillin,
+    Cephalosporin,
+    Carbapenem,
+    Monobactam,
+    Macrolide,
+    Lincosamide,
+    Aminoglycoside,
+    Fluoroquinolone,
+    Tetracycline,
+    Glycopeptide,
+    Oxazolidinone,
+    Streptogramin,
+    FolatePathwayInhibitor,
+    Amphenicol,
+    Nitrofuran,
+    Pleuromutilin,
+    Fusidane,
+    Nitroimidazole,
+}
+
+impl DrugClass {
+    pub const ALL: [DrugClass; 18] = [
+        DrugClass::Penicillin,
+        DrugClass::Cephalosporin,
+        DrugClass::Carbapenem,
+        DrugClass::Monobactam,
+        DrugClass::Macrolide,
+        DrugClass::Lincosamide,
+        DrugClass::Aminoglycoside,
+        DrugClass::Fluoroquinolone,
+        DrugClass::Tetracycline,
+        DrugClass::Glycopeptide,
+        DrugClass::Oxazolidinone,
+        DrugClass::Streptogramin,
+        DrugClass::FolatePathwayInhibitor,
+        DrugClass::Amphenicol,
+        DrugClass::Nitrofuran,
+        DrugClass::Pleuromutilin,
+        DrugClass::Fusidane,
+        DrugClass::Nitroimidazole,
+    ];
+
+    /// The name used in catalog files, parameter keys and outputs.
+    pub fn name(self) -> &'static str {
+        match self {
+            DrugClass::Penicillin => "penicillin",
+            DrugClass::Cephalosporin => "cephalosporin",
+            DrugClass::Carbapenem => "carbapenem",
+            DrugClass::Monobactam => "monobactam",
+            DrugClass::Macrolide => "macrolide",
+            DrugClass::Lincosamide => "lincosamide",
+            DrugClass::Aminoglycoside => "aminoglycoside",
+            DrugClass::Fluoroquinolone => "fluoroquinolone",
+            DrugClass::Tetracycline => "tetracycline",
+            DrugClass::Glycopeptide => "glycopeptide",
+            DrugClass::Oxazolidinone => "oxazolidinone",
+            DrugClass::Streptogramin => "streptogramin",
+            DrugClass::FolatePathwayInhibitor => "folate_pathway_inhibitor",
+            DrugClass::Amphenicol => "amphenicol",
+            DrugClass::Nitrofuran => "nitrofuran",
+            DrugClass::Pleuromutilin => "pleuromutilin",
+            DrugClass::Fusidane => "fusidane",
+            DrugClass::Nitroimidazole => "nitroimidazole",
+        }
+    }
+}
+
+impl fmt::Display for DrugClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Antibiotic subclasses, each within one `DrugClass`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrugSubclass {
+    NaturalPenicillin,
+    Aminopenicillin,
+    ExtendedSpectrumPenicillin,
+    #[serde(rename = "cephalosporin_1st_gen")]
+    Cephalosporin1stGen,
+    #[serde(rename = "cephalosporin_2nd_gen")]
+    Cephalosporin2ndGen,
+    #[serde(rename = "cephalosporin_3rd_gen")]
+    Cephalosporin3rdGen,
+    #[serde(rename = "cephalosporin_4th_gen")]
+    Cephalosporin4thGen,
+    #[serde(rename = "cephalosporin_5th_gen")]
+    Cephalosporin5thGen,
+    Carbapenem,
+    Monobactam,
+    Macrolide,
+    Lincosamide,
+    Aminoglycoside,
+    Fluoroquinolone,
+    Tetracycline,
+    SemiSyntheticTetracycline,
+    Glycopeptide,
+    Oxazolidinone,
+    Streptogramin,
+    SulfonamideTrimethoprim,
+    Amphenicol,
+    Nitrofuran,
+    Pleuromutilin,
+    Fusidane,
+    Nitroimidazole,
+}
+
+impl DrugSubclass {
+    pub const ALL: [DrugSubclass; 25] = [
+        DrugSubclass::NaturalPenicillin,
+        DrugSubclass::Aminopenicillin,
+        DrugSubclass::ExtendedSpectrumPenicillin,
+        DrugSubclass::Cephalosporin1stGen,
+        DrugSubclass::Cephalosporin2ndGen,
+        DrugSubclass::Cephalosporin3rdGen,
+        DrugSubclass::Cephalosporin4thGen,
+        DrugSubclass::Cephalosporin5thGen,
+        DrugSubclass::Carbapenem,
+        DrugSubclass::Monobactam,
+        DrugSubclass::Macrolide,
+        DrugSubclass::Lincosamide,
+        DrugSubclass::Aminoglycoside,
+        DrugSubclass::Fluoroquinolone,
+        DrugSubclass::Tetracycline,
+        DrugSubclass::SemiSyntheticTetracycline,
+        DrugSubclass::Glycopeptide,
+        DrugSubclass::Oxazolidinone,
+        DrugSubclass::Streptogramin,
+        DrugSubclass::SulfonamideTrimethoprim,
+        DrugSubclass::Amphenicol,
+        DrugSubclass::Nitrofuran,
+        DrugSubclass::Pleuromutilin,
+        DrugSubclass::Fusidane,
+        DrugSubclass::Nitroimidazole,
+    ];
+
+    /// The name used in catalog files, parameter keys and outputs.
+    pub fn name(self) -> &'static str {
+        match self {
+            DrugSubclass::NaturalPenicillin => "natural_penicillin",
+            DrugSubclass::Aminopenicillin => "aminopenicillin",
+            DrugSubclass::ExtendedSpectrumPenicillin => "extended_spectrum_penicillin",
+            DrugSubclass::Cephalosporin1stGen => "cephalosporin_1st_gen",
+            DrugSubclass::Cephalosporin2ndGen => "cephalosporin_2nd_gen",
+            DrugSubclass::Cephalosporin3rdGen => "cephalosporin_3rd_gen",
+            DrugSubclass::Cephalosporin4thGen => "cephalosporin_4th_gen",
+            DrugSubclass::Cephalosporin5thGen => "cephalosporin_5th_gen",
+            DrugSubclass::Carbapenem => "carbapenem",
+            DrugSubclass::Monobactam => "monobactam",
+            DrugSubclass::Macrolide => "macrolide",
+            DrugSubclass::Lincosamide => "lincosamide",
+            DrugSubclass::Aminoglycoside => "aminoglycoside",
+            DrugSubclass::Fluoroquinolone => "fluoroquinolone",
+            DrugSubclass::Tetracycline => "tetracycline",
+            DrugSubclass::SemiSyntheticTetracycline => "semi_synthetic_tetracycline",
+            DrugSubclass::Glycopeptide => "glycopeptide",
+            DrugSubclass::Oxazolidinone => "oxazolidinone",
+            DrugSubclass::Streptogramin => "streptogramin",
+            DrugSubclass::SulfonamideTrimethoprim => "sulfonamide_trimethoprim",
+            DrugSubclass::Amphenicol => "amphenicol",
+            DrugSubclass::Nitrofuran => "nitrofuran",
+            DrugSubclass::Pleuromutilin => "pleuromutilin",
+            DrugSubclass::Fusidane => "fusidane",
+            DrugSubclass::Nitroimidazole => "nitroimidazole",
+        }
+    }
+
+    /// The class this subclass belongs to.
+    pub fn class(self) -> DrugClass {
+        match self {
+            DrugSubclass::NaturalPenicillin
+            | DrugSubclass::Aminopenicillin
+            | DrugSubclass::ExtendedSpectrumPenicillin => DrugClass::Penicillin,
+            DrugSubclass::Cephalosporin1stGen
+            | DrugSubclass::Cephalosporin2ndGen
+            | DrugSubclass::Cephalosporin3rdGen
+            | DrugSubclass::Cephalosporin4thGen
+            | DrugSubclass::Cephalosporin5thGen => DrugClass::Cephalosporin,
+            DrugSubclass::Carbapenem => DrugClass::Carbapenem,
+            DrugSubclass::Monobactam => DrugClass::Monobactam,
+            DrugSubclass::Macrolide => DrugClass::Macrolide,
+            DrugSubclass::Lincosamide => DrugClass::Lincosamide,
+            DrugSubclass::Aminoglycoside => DrugClass::Aminoglycoside,
+            DrugSubclass::Fluoroquinolone => DrugClass::Fluoroquinolone,
+            DrugSubclass::Tetracycline | DrugSubclass::SemiSyntheticTetracycline => DrugClass::Tetracycline,
+            DrugSubclass::Glycopeptide => DrugClass::Glycopeptide,
+            DrugSubclass::Oxazolidinone => DrugClass::Oxazolidinone,
+            DrugSubclass::Streptogramin => DrugClass::Streptogramin,
+            DrugSubclass::SulfonamideTrimethoprim => DrugClass::FolatePathwayInhibitor,
+            DrugSubclass::Amphenicol => DrugClass::Amphenicol,
+            DrugSubclass::Nitrofuran => DrugClass::Nitrofuran,
+            DrugSubclass::Pleuromutilin => DrugClass::Pleuromutilin,
+            DrugSubclass::Fusidane => DrugClass::Fusidane,
+            DrugSubclass::Nitroimidazole => DrugClass::Nitroimidazole,
+        }
+    }
+}
+
+impl fmt::Display for DrugSubclass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A drug class or subclass: the unit that class-level parameters and output summaries refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DrugGroup {
+    Class(DrugClass),
+    Subclass(DrugSubclass),
+}
+
+impl DrugGroup {
+    /// Whether `drug` is in this class or subclass.
+    pub fn contains(self, drug: &DrugSpec) -> bool {
+        match self {
+            DrugGroup::Class(class) => drug.class == class,
+            DrugGroup::Subclass(subclass) => drug.subclass == subclass,
+        }
+    }
+
+    /// The prefix standing in for "drug_{name}_" in class-level parameter keys.
+    pub fn key_prefix(self) -> String {
+        match self {
+            DrugGroup::Class(class) => format!("drug_class_{}_", class.name()),
+            DrugGroup::Subclass(subclass) => format!("drug_subclass_{}_", subclass.name()),
+        }
+    }
+}
+
+impl fmt::Display for DrugGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DrugGroup::Class(class) => write!(f, "{}", class),
+            DrugGroup::Subclass(subclass) => write!(f, "{}", subclass),
+        }
+    }
+}
+
+/// One drug in the catalog.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DrugSpec {
+    pub name: String,
+    pub class: DrugClass,
+    pub subclass: DrugSubclass,
+    pub half_life_days: f64,
+    #[serde(default = "default_spectrum_breadth")]
+    pub spectrum_breadth: f64, // 1.0 = narrow, 5.0 = very broad
+    pub routes: Vec<DrugRoute>,
+}
+
+/// One region in the catalog: somewhere people live, and travel to from the other regions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegionSpec {
+    pub name: String,
+    #[serde(default)]
+    pub population_share: Option<f64>, // relative share of the people living here; None = by the demographic data
+    #[serde(default)]
+    pub defaults_from: Option<String>, // a built-in region whose parameter defaults this one starts from
+    #[serde(default)]
+    pub parameters: BTreeMap<String, f64>, // defaults for "{name}_{key}", keyed without the region name
+    #[serde(default)]
+    pub travel: BTreeMap<String, f64>, // relative chance of each destination; empty = every other region alike
+}
+
+fn default_age_risk_template() -> String {
+    "respiratory".to_string()
+}
+
+fn default_syndromes() -> Vec<(u32, f64)> {
+    (1..=MAX_SYNDROME_ID).map(|id| (id, 0.1)).collect()
+}
+
+fn default_spectrum_breadth() -> f64 {
+    3.0
+}
+
+/// Errors raised while reading or checking a catalog file.
+#[derive(Debug)]
+pub enum CatalogError {
+    Io { path: PathBuf, source: std::io::Error },
+    UnsupportedFormat { path: PathBuf },
+    Parse { path: PathBuf, message: String },
+    Invalid { path: PathBuf, message: String },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io { path, source } => write!(f, "could not read catalog {}: {}", path.display(), source),
+            CatalogError::UnsupportedFormat { path } => {
+                write!(f, "catalog {} must have a .toml or .json extension", path.display())
+            }
+            CatalogError::Parse { path, message } => write!(f, "could not parse catalog {}: {}", path.display(), message),
+            CatalogError::Invalid { path, message } => write!(f, "invalid catalog {}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatalogError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The bacteria, drugs and regions in the model, in index order.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Catalog {
+    pub bacteria: Vec<BacteriumSpec>,
+    pub drugs: Vec<DrugSpec>,
+    #[serde(default)]
+    pub regions: Vec<RegionSpec>,
+}
+
+impl Catalog {
+    /// The compiled-in default catalog (catalogs/default.toml).
+    pub fn default_catalog() -> Self {
+        Catalog::from_contents(DEFAULT_CATALOG, ParameterFileFormat::Toml, Path::new("catalogs/default.toml"))
+            .expect("the compiled-in default catalog is invalid")
+    }
+
+    /// Reads and checks a catalog file.
+    pub fn from_file(path: &Path) -> Result<Self, CatalogError> {
+        let format = ParameterFileFormat::from_path(path)
+            .ok_or_else(|| CatalogError::UnsupportedFormat { path: path.to_path_buf() })?;
+        let contents =
+            std::fs::read_to_string(path).map_err(|source| CatalogError::Io { path: path.to_path_buf(), source })?;
+        Catalog::from_contents(&contents, format, path)
+    }
+
+    /// Parses and checks a catalog given as file contents.  `origin` is only used to label errors.
+    pub fn from_contents(contents: &str, format: ParameterFileFormat, origin: &Path) -> Result<Self, CatalogError> {
+        let mut catalog: Catalog = match format {
+            ParameterFileFormat::Toml => toml::from_str(contents)
+                .map_err(|e| CatalogError::Parse { path: origin.to_path_buf(), message: e.to_string() })?,
+            ParameterFileFormat::Json => serde_json::from_str(contents)
+                .map_err(|e| CatalogError::Parse { path: origin.to_path_buf(), message: e.to_string() })?,
+        };
+        if catalog.regions.is_empty() {
+            catalog.regions = Catalog::default_catalog().regions;
+        }
+        catalog
+            .check()
+            .map_err(|message| CatalogError::Invalid { path: origin.to_path_buf(), message })?;
+        Ok(catalog)
+    }
+
+    // Checks the things serde cannot: names, template names and value ranges.
+    fn check(&self) -> Result<(), String> {
+        if self.bacteria.is_empty() || self.drugs.is_empty() {
+            return Err("a catalog needs at least one bacterium and one drug".to_string());
+        }
+        let mut seen = HashSet::new();
+        for name in self.bacteria.iter().map(|b| &b.name).chain(self.drugs.iter().map(|d| &d.name)) {
+            if name.trim().is_empty() {
+                return Err("names must not be empty".to_string());
+            }
+            if !seen.insert(name.as_str()) {
+                return Err(format!("\"{}\" is listed more than once", name));
+            }
+        }
+        for bacterium in &self.bacteria {
+            if !AGE_RISK_TEMPLATES.contains_key(bacterium.age_risk_template.as_str()) {
+                return Err(format!(
+                    "bacterium \"{}\" has unknown age_risk_template \"{}\"",
+                    bacterium.name, bacterium.age_risk_template
+                ));
+            }
+            if bacterium.syndromes.is_empty() {
+                return Err(format!("bacterium \"{}\" needs at least one syndrome", bacterium.name));
+            }
+            for &(id, probability) in &bacterium.syndromes {
+                if !(1..=MAX_SYNDROME_ID).contains(&id) {
+                    return Err(format!("bacterium \"{}\" has syndrome id {} outside 1 to {}", bacterium.name, id, MAX_SYNDROME_ID));
+                }
+                if !(probability >= 0.0 && probability.is_finite()) {
+                    return Err(format!("bacterium \"{}\" has syndrome probability {}", bacterium.name, probability));
+                }
+            }
+            if bacterium.syndromes.iter().map(|&(_, p)| p).sum::<f64>() <= 0.0 {
+                return Err(format!("bacterium \"{}\" has syndrome probabilities summing to zero", bacterium.name));
+            }
+        }
+        self.check_regions()?;
+        for drug in &self.drugs {
+            if drug.subclass.class() != drug.class {
+                return Err(format!(
+                    "drug \"{}\" has subclass {} which is not in class {}",
+                    drug.name, drug.subclass, drug.class
+                ));
+            }
+            if !(drug.half_life_days > 0.0 && drug.half_life_days.is_finite()) {
+                return Err(format!("drug \"{}\" needs a positive half_life_days", drug.name));
+            }
+            if !(1.0..=5.0).contains(&drug.spectrum_breadth) {
+                return Err(format!("drug \"{}\" has spectrum_breadth {} outside 1.0 to 5.0", drug.name, drug.spectrum_breadth));
+            }
+            if drug.routes.is_empty() {
+                return Err(format!("drug \"{}\" needs at least one route", drug.name));
+            }
+        }
+        Ok(())
+    }
+
+    // Checks the regions: names usable in parameter keys, shares, defaults, parameter keys and travel destinations.
+    fn check_regions(&self) -> Result<(), String> {
+        if self.regions.is_empty() {
+            return Err("a catalog needs at least one region".to_string());
+        }
+        let mut seen = HashSet::new();
+        for region in &self.regions {
+            let name = &region.name;
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+                return Err(format!("region \"{}\" must be named with lower-case letters, digits and underscores", name));
+            }
+            if name == "home" {
+                return Err("\"home\" cannot be a region name: it stands for the region someone lives in".to_string());
+            }
+            if !seen.insert(name.as_str()) {
+                return Err(format!("region \"{}\" is listed more than once", name));
+            }
+        }
+        let shares: Vec<f64> = self.regions.iter().filter_map(|r| r.population_share).collect();
+        if !shares.is_empty() {
+            if shares.len() != self.regions.len() {
+                return Err("either every region has a population_share or none has".to_string());
+            }
+            if !shares.iter().all(|share| *share >= 0.0 && share.is_finite()) {
+                return Err("region population shares must be non-negative numbers".to_string());
+            }
+            if shares.iter().sum::<f64>() <= 0.0 {
+                return Err("region population shares sum to zero".to_string());
+            }
+        }
+        let suffixes: HashSet<String> = region_key_suffixes(self).into_iter().map(|(suffix, _)| suffix).collect();
+        for region in &self.regions {
+            if let Some(from) = &region.defaults_from {
+                if !BUILT_IN_REGIONS.contains(&from.as_str()) {
+                    return Err(format!(
+                        "region \"{}\" takes its defaults from \"{}\", which is not one of {}",
+                        region.name,
+                        from,
+                        BUILT_IN_REGIONS.join(", ")
+                    ));
+                }
+            }
+            for (key, value) in &region.parameters {
+                if !suffixes.contains(key) {
+                    return Err(format!("region \"{}\" has parameter \"{}\", which is not a region parameter", region.name, key));
+                }
+                if !value.is_finite() {
+                    return Err(format!("region \"{}\" has parameter \"{}\" = {}", region.name, key, value));
+                }
+            }
+            for (destination, weight) in &region.travel {
+                if destination == &region.name || !seen.contains(destination.as_str()) {
+                    return Err(format!("region \"{}\" has travel to \"{}\", which is not another region", region.name, destination));
+                }
+                if !(*weight >= 0.0 && weight.is_finite()) {
+                    return Err(format!("region \"{}\" has travel weight {} to \"{}\"", region.name, weight, destination));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The relative chance of travelling to each region (in catalog order) from region number `from`: its `travel`
+    /// weights, or every other region alike if it has none.
+    pub fn travel_weights(&self, from: usize) -> Vec<f64> {
+        let travel = &self.regions[from].travel;
+        self.regions
+            .iter()
+            .enumerate()
+            .map(|(to, region)| {
+                if to == from {
+                    0.0
+                } else if travel.is_empty() {
+                    1.0
+                } else {
+                    travel.get(&region.name).copied().unwrap_or(0.0)
+                }
+            })
+            .collect()
+    }
+
+    /// The bacteria names, in index order.
+    pub fn bacteria_names(&self) -> Vec<&str> {
+        self.bacteria.iter().map(|b| b.name.as_str()).collect()
+    }
+
+    /// The drug names, in index order.
+    pub fn drug_names(&self) -> Vec<&str> {
+        self.drugs.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    /// The region names, in index order.
+    pub fn region_names(&self) -> Vec<&str> {
+        self.regions.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    /// The catalog entry for a bacterium, by name.
+    pub fn bacterium(&self, name: &str) -> Option<&BacteriumSpec> {
+        self.bacteria.iter().find(|b| b.name == name)
+    }
+
+    /// Indices of the drugs in a class or subclass, in catalog order.
+    pub fn drug_indices_in(&self, group: DrugGroup) -> Vec<usize> {
+        (0..self.drugs.len()).filter(|&d| group.contains(&self.drugs[d])).collect()
+    }
+
+    /// Names of the drugs in a class or subclass, in catalog order.
+    pub fn drug_names_in(&self, group: DrugGroup) -> Vec<&str> {
+        self.drugs.iter().filter(|d| group.contains(d)).map(|d| d.name.as_str()).collect()
+    }
+
+    /// The classes and subclasses with at least one drug in the catalog: classes first, each in declaration order.
+    pub fn drug_groups(&self) -> Vec<DrugGroup> {
+        let classes = DrugClass::ALL.iter().map(|&c| DrugGroup::Class(c));
+        let subclasses = DrugSubclass::ALL.iter().map(|&s| DrugGroup::Subclass(s));
+        classes.chain(subclasses).filter(|&g| self.drugs.iter().any(|d| g.contains(d))).collect()
+    }
+
+    /// For a parameter key that names a drug class or subclass where a drug name would go
+    /// ("drug_class_{class}_..." or "drug_subclass_{subclass}_...", anywhere in the key), the group and the per-drug
+    /// keys it stands for.  Returns None for ordinary keys.
+    pub fn expand_group_key(&self, key: &str) -> Option<(DrugGroup, Vec<String>)> {
+        let groups = DrugClass::ALL
+            .iter()
+            .map(|&c| DrugGroup::Class(c))
+            .chain(DrugSubclass::ALL.iter().map(|&s| DrugGroup::Subclass(s)));
+        // the longest matching prefix wins, so no group name can shadow a longer one
+        let (group, at, prefix) = groups
+            .filter_map(|g| {
+                let prefix = g.key_prefix();
+                key.find(&prefix).map(|at| (g, at, prefix))
+            })
+            .max_by_key(|(_, _, prefix)| prefix.len())?;
+        let keys = self
+            .drug_names_in(group)
+            .into_iter()
+            .map(|name| format!("{}drug_{}_{}", &key[..at], name, &key[at + prefix.len()..]))
+            .collect();
+        Some((group, keys))
+    }
+
+    /// Writes the catalog as a tab-separated table, one row per bacterium or drug.
+    pub fn write_listing<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "bacteria\tgram_group\tsepsis_risk\tage_risk_template\tsyndromes")?;
+        for b in &self.bacteria {
+            let syndromes: Vec<String> = b.syndromes.iter().map(|(id, p)| format!("{}:{}", id, p)).collect();
+            writeln!(
+                out,
+                "{}\t{:?}\t{:?}\t{}\t{}",
+                b.name, b.gram_group, b.sepsis_risk, b.age_risk_template, syndromes.join(",")
+            )?;
+        }
+        writeln!(out)?;
+        writeln!(out, "drug\tclass\tsubclass\thalf_life_days\tspectrum_breadth\troutes")?;
+        for d in &self.drugs {
+            let routes: Vec<String> = d.routes.iter().map(|r| format!("{:?}", r)).collect();
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                d.name, d.class, d.subclass, d.half_life_days, d.spectrum_breadth, routes.join(",")
+            )?;
+        }
+        writeln!(out)?;
+        writeln!(out, "region\tpopulation_share\tdefaults_from\tparameters\ttravel")?;
+        for r in &self.regions {
+            let parameters: Vec<String> = r.parameters.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+            let travel: Vec<String> = r.travel.iter().map(|(to, weight)| format!("{}:{}", to, weight)).collect();
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}",
+                r.name,
+                r.population_share.map(|share| share.to_string()).unwrap_or_default(),
+                r.defaults_from.as_deref().unwrap_or(""),
+                parameters.join(","),
+                travel.join(",")
+            )?;
+        }
+        Ok(())
+    }
+}
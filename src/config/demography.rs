@@ -0,0 +1,498 @@
+// src/config/demography.rs
+//
+// Demographic data by region and calendar year: the age-sex pyramids the initial population is drawn from, the
+// total population, crude birth rate and sex ratio at birth from which births over the run are generated, and the
+// life tables background mortality follows.
+//
+// The data are CSV files in the column layout of the UN World Population Prospects (WPP) downloads, so that WPP
+// extracts can be used as they are (see demography/*.csv for the columns and the compiled-in default).  A directory
+// given with --demography holds any of
+//
+//     population.csv   population on 1 January by five-year age group and sex
+//                      (WPP2022_Population1JanuaryByAge5GroupSex)
+//     indicators.csv   total population, crude birth rate and sex ratio at birth (WPP2022_Demographic_Indicators)
+//     life_table.csv   probability of dying within a year (qx) by sex and single year of age
+//                      (WPP2022_Life_Table_Complete_Medium_Male and _Female)
+//
+// and files missing from it are taken from the compiled-in data.  Each catalog region takes the rows of the location
+// with its name or, failing that, of the region in its `defaults_from`; a region without rows of its own gets only
+// the shape of those, and the catalog then has to size the regions with population_share values.  Like the catalog,
+// the demography is carried by the simulation built with it (see `SimulationBuilder::demography`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::catalog::Catalog;
+
+// The default data, compiled into the binary.
+const DEFAULT_POPULATION: &str = include_str!("../../demography/population.csv");
+const DEFAULT_INDICATORS: &str = include_str!("../../demography/indicators.csv");
+const DEFAULT_LIFE_TABLE: &str = include_str!("../../demography/life_table.csv");
+
+/// Errors raised while reading or checking demographic data.
+#[derive(Debug)]
+pub enum DemographyError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, line: usize, message: String },
+    Invalid { message: String },
+}
+
+impl fmt::Display for DemographyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DemographyError::Io { path, source } => write!(f, "could not read {}: {}", path.display(), source),
+            DemographyError::Parse { path, line, message } => {
+                write!(f, "invalid demographic data at {} line {}: {}", path.display(), line, message)
+            }
+            DemographyError::Invalid { message } => write!(f, "invalid demographic data: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DemographyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DemographyError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// One age group of a pyramid: people aged `start_years` to `start_years + span_years`, by sex (any unit).
+#[derive(Debug, Clone, Copy)]
+pub struct AgeGroup {
+    pub start_years: u32,
+    pub span_years: u32, // 5 for the open-ended oldest group, whose ages are spread over its first five years
+    pub male: f64,
+    pub female: f64,
+}
+
+/// The population of a location on 1 January of `year`, by age group and sex.
+#[derive(Debug, Clone)]
+pub struct Pyramid {
+    pub year: i32,
+    pub groups: Vec<AgeGroup>,
+}
+
+/// The demographic indicators of a location in a year.
+#[derive(Debug, Clone, Copy)]
+pub struct Indicators {
+    pub year: i32,
+    pub population: f64,         // total on 1 January, thousands
+    pub crude_birth_rate: f64,   // births per 1,000 population per year
+    pub sex_ratio_at_birth: f64, // male births per 100 female births
+}
+
+impl Indicators {
+    /// The probability that a birth is male.
+    pub fn male_birth_probability(&self) -> f64 {
+        self.sex_ratio_at_birth / (100.0 + self.sex_ratio_at_birth)
+    }
+}
+
+/// The life table of a location in a year: qx, the probability of dying before the next birthday, by single year of
+/// age from 0, for males (`qx[0]`) and females (`qx[1]`).  The last age is open-ended.
+#[derive(Debug, Clone)]
+pub struct LifeTable {
+    pub year: i32,
+    pub qx: [Vec<f64>; 2],
+}
+
+/// Pyramids, indicators and life tables by location, each in year order.
+#[derive(Debug, Clone)]
+pub struct Demography {
+    pyramids: BTreeMap<String, Vec<Pyramid>>,
+    indicators: BTreeMap<String, Vec<Indicators>>,
+    life_tables: BTreeMap<String, Vec<LifeTable>>,
+}
+
+impl Demography {
+    /// The compiled-in data (demography/*.csv).
+    pub fn default_demography() -> Self {
+        Demography::from_contents(DEFAULT_POPULATION, DEFAULT_INDICATORS, DEFAULT_LIFE_TABLE, Path::new("demography"))
+            .expect("the compiled-in demographic data are invalid")
+    }
+
+    /// Reads population.csv, indicators.csv and life_table.csv from `dir`, taking any that is missing from the
+    /// compiled-in data.
+    pub fn from_dir(dir: &Path) -> Result<Self, DemographyError> {
+        let read = |name: &str, default: &'static str| -> Result<String, DemographyError> {
+            let path = dir.join(name);
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => Ok(contents),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && dir.is_dir() => Ok(default.to_string()),
+                Err(source) => Err(DemographyError::Io { path, source }),
+            }
+        };
+        let population = read("population.csv", DEFAULT_POPULATION)?;
+        let indicators = read("indicators.csv", DEFAULT_INDICATORS)?;
+        let life_table = read("life_table.csv", DEFAULT_LIFE_TABLE)?;
+        Demography::from_contents(&population, &indicators, &life_table, dir)
+    }
+
+    /// Parses the contents of population.csv, indicators.csv and life_table.csv.  `origin` is the directory they are
+    /// from, only used to label errors.
+    pub fn from_contents(
+        population: &str,
+        indicators: &str,
+        life_table: &str,
+        origin: &Path,
+    ) -> Result<Self, DemographyError> {
+        let population_path = origin.join("population.csv");
+        let mut pyramids: BTreeMap<String, BTreeMap<i32, Vec<AgeGroup>>> = BTreeMap::new();
+        let columns = ["Location", "Time", "AgeGrpStart", "AgeGrpSpan", "PopMale", "PopFemale"];
+        for (line, row) in csv_rows(population, &columns)
+            .map_err(|(line, message)| DemographyError::Parse { path: population_path.clone(), line, message })?
+        {
+            let parse_error = |message: String| DemographyError::Parse { path: population_path.clone(), line, message };
+            let span = number::<i32>(&row, "AgeGrpSpan").map_err(parse_error)?;
+            let group = AgeGroup {
+                start_years: number(&row, "AgeGrpStart").map_err(parse_error)?,
+                span_years: if span < 0 { 5 } else { span as u32 },
+                male: count(&row, "PopMale").map_err(parse_error)?,
+                female: count(&row, "PopFemale").map_err(parse_error)?,
+            };
+            if group.span_years == 0 {
+                return Err(parse_error("AgeGrpSpan must not be 0".to_string()));
+            }
+            let year = number(&row, "Time").map_err(parse_error)?;
+            pyramids.entry(location_name(row["Location"])).or_default().entry(year).or_default().push(group);
+        }
+
+        let indicators_path = origin.join("indicators.csv");
+        let mut by_location: BTreeMap<String, Vec<Indicators>> = BTreeMap::new();
+        for (line, row) in csv_rows(indicators, &["Location", "Time", "TPopulation1Jan", "CBR", "SRB"])
+            .map_err(|(line, message)| DemographyError::Parse { path: indicators_path.clone(), line, message })?
+        {
+            let parse_error = |message: String| DemographyError::Parse { path: indicators_path.clone(), line, message };
+            let entry = Indicators {
+                year: number(&row, "Time").map_err(parse_error)?,
+                population: count(&row, "TPopulation1Jan").map_err(parse_error)?,
+                crude_birth_rate: count(&row, "CBR").map_err(parse_error)?,
+                sex_ratio_at_birth: count(&row, "SRB").map_err(parse_error)?,
+            };
+            if entry.sex_ratio_at_birth == 0.0 {
+                return Err(parse_error("SRB must be more than 0".to_string()));
+            }
+            by_location.entry(location_name(row["Location"])).or_default().push(entry);
+        }
+        for rows in by_location.values_mut() {
+            rows.sort_by_key(|i| i.year);
+            rows.dedup_by_key(|i| i.year);
+        }
+
+        let pyramids = pyramids
+            .into_iter()
+            .map(|(location, years)| {
+                let years: Vec<Pyramid> = years
+                    .into_iter()
+                    .map(|(year, mut groups)| {
+                        groups.sort_by_key(|g| g.start_years);
+                        Pyramid { year, groups }
+                    })
+                    .collect();
+                (location, years)
+            })
+            .collect();
+
+        let life_table_path = origin.join("life_table.csv");
+        let mut qx_by_age: BTreeMap<(String, i32), [BTreeMap<u32, f64>; 2]> = BTreeMap::new();
+        for (line, row) in csv_rows(life_table, &["Location", "Time", "Sex", "AgeGrpStart", "qx"])
+            .map_err(|(line, message)| DemographyError::Parse { path: life_table_path.clone(), line, message })?
+        {
+            let parse_error = |message: String| DemographyError::Parse { path: life_table_path.clone(), line, message };
+            let sex = match row["Sex"].to_ascii_lowercase().as_str() {
+                "male" => 0,
+                "female" => 1,
+                _ => continue, // "Total" (both sexes)
+            };
+            let qx = count(&row, "qx").map_err(parse_error)?;
+            if qx > 1.0 {
+                return Err(parse_error(format!("qx {} is more than 1", qx)));
+            }
+            let key = (location_name(row["Location"]), number(&row, "Time").map_err(parse_error)?);
+            qx_by_age.entry(key).or_default()[sex].insert(number(&row, "AgeGrpStart").map_err(parse_error)?, qx);
+        }
+        let mut life_tables: BTreeMap<String, Vec<LifeTable>> = BTreeMap::new();
+        for ((location, year), by_sex) in qx_by_age {
+            let invalid = |message: String| DemographyError::Invalid { message };
+            for ages in &by_sex {
+                if ages.is_empty() || !ages.keys().copied().eq(0..ages.len() as u32) {
+                    return Err(invalid(format!(
+                        "the {} life table of {} needs male and female rows for every single year of age from 0",
+                        year, location
+                    )));
+                }
+            }
+            let qx = by_sex.map(|ages| ages.into_values().collect());
+            life_tables.entry(location).or_default().push(LifeTable { year, qx });
+        }
+        Ok(Demography { pyramids, indicators: by_location, life_tables })
+    }
+
+    /// Checks that every region of `catalog` has data (pyramids, indicators and life tables) of its own or of its
+    /// `defaults_from` region, with people in
+    /// its pyramids, and that regions without indicators of their own are sized by population_share values.
+    pub fn check(&self, catalog: &Catalog) -> Result<(), DemographyError> {
+        let invalid = |message: String| DemographyError::Invalid { message };
+        for region in &catalog.regions {
+            let from = region.defaults_from.as_deref().unwrap_or("");
+            for (table, own, inherited) in [
+                ("population", self.pyramids.contains_key(&region.name), self.pyramids.contains_key(from)),
+                ("indicators", self.indicators.contains_key(&region.name), self.indicators.contains_key(from)),
+                ("life_table", self.life_tables.contains_key(&region.name), self.life_tables.contains_key(from)),
+            ] {
+                if !own && !inherited {
+                    return Err(invalid(format!(
+                        "region \"{}\" has no {} rows, of its own or of a defaults_from region",
+                        region.name, table
+                    )));
+                }
+            }
+            if region.population_share.is_none() && !self.indicators.contains_key(&region.name) {
+                return Err(invalid(format!(
+                    "region \"{}\" has no indicators rows of its own to size it by, so the regions need \
+                     population_share values",
+                    region.name
+                )));
+            }
+        }
+        for (location, years) in &self.pyramids {
+            for pyramid in years {
+                if pyramid.groups.iter().map(|g| g.male + g.female).sum::<f64>() <= 0.0 {
+                    return Err(invalid(format!("the {} population of {} has nobody in it", pyramid.year, location)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The pyramid of the location of `region` (a catalog region name, or its `defaults_from`) for `year`: the
+    /// latest one not after it, or the earliest one there is.
+    pub fn pyramid(&self, catalog: &Catalog, region: &str, year: i32) -> &Pyramid {
+        let years = self.location(catalog, region, &self.pyramids);
+        years.iter().rev().find(|p| p.year <= year).unwrap_or(&years[0])
+    }
+
+    /// The indicators of the location of `region` at `year` (fractional), interpolated linearly between the years
+    /// given and held at the first and last of them outside those.
+    pub fn indicators(&self, catalog: &Catalog, region: &str, year: f64) -> Indicators {
+        let rows = self.location(catalog, region, &self.indicators);
+        let after = rows.iter().position(|i| i.year as f64 > year);
+        match after {
+            Some(0) => rows[0],
+            None => rows[rows.len() - 1],
+            Some(k) => {
+                let (a, b) = (rows[k - 1], rows[k]);
+                let w = (year - a.year as f64) / (b.year - a.year) as f64;
+                let lerp = |x: f64, y: f64| x + w * (y - x);
+                Indicators {
+                    year: year.floor() as i32,
+                    population: lerp(a.population, b.population),
+                    crude_birth_rate: lerp(a.crude_birth_rate, b.crude_birth_rate),
+                    sex_ratio_at_birth: lerp(a.sex_ratio_at_birth, b.sex_ratio_at_birth),
+                }
+            }
+        }
+    }
+
+    /// The daily probability of death of the location of `region` at `year` (fractional), by sex (males first) and
+    /// single year of age, from qx interpolated linearly between the life tables' years (and held at the first and
+    /// last of them outside those).  A year's probability `qx` becomes `1 - (1 - qx)^(1/365)` a day.  The last age is
+    /// open-ended, so where its qx is 1 (dying at some age beyond it) it takes the qx of the age before it.
+    pub fn daily_death_probabilities(&self, catalog: &Catalog, region: &str, year: f64) -> [Vec<f64>; 2] {
+        let tables = self.location(catalog, region, &self.life_tables);
+        let after = tables.iter().position(|t| t.year as f64 > year);
+        let (a, b, w) = match after {
+            Some(0) => (&tables[0], &tables[0], 0.0),
+            None => (&tables[tables.len() - 1], &tables[tables.len() - 1], 0.0),
+            Some(k) => {
+                let (a, b) = (&tables[k - 1], &tables[k]);
+                (a, b, (year - a.year as f64) / (b.year - a.year) as f64)
+            }
+        };
+        [0, 1].map(|sex| {
+            let ages = a.qx[sex].len().min(b.qx[sex].len());
+            let mut qx: Vec<f64> = (0..ages).map(|age| a.qx[sex][age] + w * (b.qx[sex][age] - a.qx[sex][age])).collect();
+            if ages > 1 && qx[ages - 1] >= 1.0 {
+                qx[ages - 1] = qx[ages - 2];
+            }
+            qx.into_iter().map(|q| 1.0 - (1.0 - q).powf(1.0 / 365.0)).collect()
+        })
+    }
+
+    /// The share of the population of each catalog region in `year`: its population_share if the catalog gives one,
+    /// otherwise its total population in the indicators.
+    pub fn region_shares(&self, catalog: &Catalog, year: f64) -> Vec<f64> {
+        catalog
+            .regions
+            .iter()
+            .map(|r| r.population_share.unwrap_or_else(|| self.indicators(catalog, &r.name, year).population))
+            .collect()
+    }
+
+    // The rows of `region`, or of its defaults_from region (which `check` makes sure one of them has).
+    fn location<'a, T>(&self, catalog: &Catalog, region: &str, table: &'a BTreeMap<String, Vec<T>>) -> &'a Vec<T> {
+        table
+            .get(region)
+            .or_else(|| {
+                let spec = catalog.regions.iter().find(|r| r.name == region)?;
+                table.get(spec.defaults_from.as_deref()?)
+            })
+            .unwrap_or_else(|| panic!("no demographic data for region \"{}\" (see Demography::check)", region))
+    }
+}
+
+// A WPP location as a region name: lower case, with runs of anything but letters and digits as one underscore.
+fn location_name(location: &str) -> String {
+    let mut name = String::new();
+    for c in location.trim().chars() {
+        if c.is_alphanumeric() {
+            name.extend(c.to_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+// The fields of a CSV row by column name.
+type Row<'a> = HashMap<&'a str, &'a str>;
+
+// The rows of a CSV text with a header row, as fields by column name, with their line numbers.  Blank lines and lines
+// starting with '#' are skipped, fields may be double-quoted (WPP location names contain commas), and columns other
+// than `required` are ignored.
+fn csv_rows<'a>(text: &'a str, required: &[&str]) -> Result<Vec<(usize, Row<'a>)>, (usize, String)> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let (header_line, header) = lines.next().ok_or((1, "the file has no header row".to_string()))?;
+    let header = split_fields(header);
+    for name in required {
+        if !header.contains(name) {
+            return Err((header_line, format!("the \"{}\" column is missing", name)));
+        }
+    }
+    lines
+        .map(|(line_number, line)| {
+            let fields = split_fields(line);
+            if fields.len() != header.len() {
+                return Err((line_number, format!("{} fields where the header has {}", fields.len(), header.len())));
+            }
+            let row = header
+                .iter()
+                .copied()
+                .zip(fields)
+                .filter(|(name, _)| required.contains(name))
+                .collect();
+            Ok((line_number, row))
+        })
+        .collect()
+}
+
+// The comma-separated fields of a line, trimmed and without their enclosing double quotes.
+fn split_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&line[start..]);
+    fields.into_iter().map(|f| f.trim().trim_matches('"')).collect()
+}
+
+fn number<T: std::str::FromStr>(row: &Row, column: &str) -> Result<T, String> {
+    row[column].parse().map_err(|_| format!("{} \"{}\" is not a whole number", column, row[column]))
+}
+
+// A non-negative number of a column.
+fn count(row: &Row, column: &str) -> Result<f64, String> {
+    match row[column].parse::<f64>() {
+        Ok(value) if value >= 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("{} \"{}\" is not a non-negative number", column, row[column])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POPULATION: &str = "Location,Time,AgeGrpStart,AgeGrpSpan,PopMale,PopFemale\n";
+    const INDICATORS: &str = "Location,Time,TPopulation1Jan,CBR,SRB\n";
+
+    // Complete life tables of "Testland, Republic of" for 2000 and 2010, in the WPP columns (ages 0, 1 and 2+).
+    const LIFE_TABLE: &str = "\
+SortOrder,LocID,Location,Variant,Time,MidPeriod,Sex,SexID,AgeGrp,AgeGrpStart,AgeGrpSpan,mx,qx,px,lx,ex
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Male,1,0,0,1,0.052,0.05,0.95,100000,60.1
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Male,1,1,1,1,0.010,0.01,0.99,95000,62.0
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Male,1,2+,2,-1,0.200,1.00,0.00,94050,61.2
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Female,2,0,0,1,0.041,0.04,0.96,100000,64.3
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Female,2,1,1,1,0.008,0.008,0.992,96000,66.0
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Female,2,2+,2,-1,0.180,1.00,0.00,95232,65.5
+1,999,\"Testland, Republic of\",Medium,2000,2000.5,Total,3,0,0,1,0.047,0.045,0.955,100000,62.2
+1,999,\"Testland, Republic of\",Medium,2010,2010.5,Male,1,0,0,1,0.031,0.03,0.97,100000,64.0
+1,999,\"Testland, Republic of\",Medium,2010,2010.5,Male,1,1,1,1,0.006,0.006,0.994,97000,65.0
+1,999,\"Testland, Republic of\",Medium,2010,2010.5,Male,1,2+,2,-1,0.150,1.00,0.00,96418,64.4
+1,999,\"Testland, Republic of\",Medium,2010,2010.5,Female,2,0,0,1,0.020,0.02,0.98,100000,68.0
+1,999,\"Testland, Republic of\",Medium,2010,2010.5,Female,2,1,1,1,0.004,0.004,0.996,98000,69.0
+1,999,\"Testland, Republic of\",Medium,2010,2010.5,Female,2,2+,2,-1,0.120,1.00,0.00,97608,68.6
+";
+
+    fn testland() -> Demography {
+        Demography::from_contents(POPULATION, INDICATORS, LIFE_TABLE, Path::new("test")).unwrap()
+    }
+
+    fn daily(qx: f64) -> f64 {
+        1.0 - (1.0 - qx).powf(1.0 / 365.0)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn reads_a_wpp_life_table() {
+        let demography = testland();
+        let tables = &demography.life_tables["testland_republic_of"];
+        assert_eq!(tables.iter().map(|t| t.year).collect::<Vec<_>>(), [2000, 2010]);
+        assert_eq!(tables[0].qx, [vec![0.05, 0.01, 1.0], vec![0.04, 0.008, 1.0]]);
+        assert_eq!(tables[1].qx, [vec![0.03, 0.006, 1.0], vec![0.02, 0.004, 1.0]]);
+    }
+
+    #[test]
+    fn a_years_qx_is_spread_over_its_days() {
+        let catalog = Catalog::default_catalog();
+        let [male, female] = testland().daily_death_probabilities(&catalog, "testland_republic_of", 2000.0);
+        assert!(close(male[0], daily(0.05)));
+        assert!(close(female[1], daily(0.008)));
+        // surviving every day of the year is surviving the year
+        assert!(close((1.0 - male[0]).powi(365), 0.95));
+        // the open-ended last age takes the qx of the age before it
+        assert!(close(male[2], daily(0.01)));
+    }
+
+    #[test]
+    fn qx_is_interpolated_between_the_years_and_held_outside_them() {
+        let demography = testland();
+        let catalog = Catalog::default_catalog();
+        let at = |year: f64| demography.daily_death_probabilities(&catalog, "testland_republic_of", year);
+        let midway = at(2005.0);
+        assert!(close(midway[0][0], daily(0.04)));
+        assert!(close(midway[1][0], daily(0.03)));
+        let quarter = at(2002.5);
+        assert!(close(quarter[0][1], daily(0.01 + 0.25 * (0.006 - 0.01))));
+        assert_eq!(at(2010.0), at(2030.0));
+        assert_eq!(at(2000.0), at(1950.0));
+    }
+}
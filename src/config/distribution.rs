@@ -0,0 +1,367 @@
+// src/config/distribution.rs
+//
+// Probability distributions over the value of a numeric parameter, written as a table in TOML or JSON:
+//
+//     { distribution = "uniform", low = 0.0005, high = 0.002 }
+//     { distribution = "log_uniform", low = 1e-6, high = 0.01 }
+//     { distribution = "beta", alpha = 2, beta = 8 }                  # on [0, 1], for probabilities
+//     { distribution = "gamma", shape = 2, scale = 0.005 }            # on [0, infinity), mean shape * scale
+//     { distribution = "empirical", file = "posterior.csv" }          # the column named after the parameter
+//     { distribution = "empirical", file = "posterior.csv", column = "escherichia coli_acquisition_prob_baseline" }
+//
+// An empirical distribution is a sample of values, e.g. the posterior sample written by a fit (calibration/abc.rs):
+// a CSV file with a header row, one column per parameter and, optionally, a "weight" column.  A row is drawn (by
+// weight) and its value taken; parameters drawn from the same file for the same run share the row, so a posterior's
+// correlations are kept (see `ParameterSet::draw_distributions`).  A relative file name is taken from the directory
+// of the file that names it.  The file is read when the distribution table is, so a set's samples are those of the
+// files as they were when its parameter files were applied.
+//
+// Used for the priors of a fit (calibration/priors.rs), the ranges of a sensitivity analysis
+// (analysis/sensitivity.rs) and parameters declared as distributions in parameter files (loader.rs).  Besides drawing
+// values, a distribution gives its density on a "working scale" (the log of the value for log_uniform, the value
+// itself otherwise), on which ABC-SMC moves particles, so that a parameter spanning orders of magnitude is explored
+// evenly.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_distr::{Beta, Distribution, Gamma};
+use serde_json::{Map, Value};
+
+/// The distribution of a parameter value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterDistribution {
+    Uniform { low: f64, high: f64 },
+    LogUniform { low: f64, high: f64 },
+    Beta { alpha: f64, beta: f64 },
+    Gamma { shape: f64, scale: f64 },
+    Empirical { sample: Arc<EmpiricalSample>, column: usize },
+}
+
+/// Values read from a CSV file, for empirical distributions (see the top of this file).
+#[derive(Debug, PartialEq)]
+pub struct EmpiricalSample {
+    pub path: PathBuf,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+    weights: Vec<f64>, // [row] cumulative, ending at 1
+}
+
+impl EmpiricalSample {
+    /// Reads a sample file.
+    pub fn load(path: &Path) -> Result<Arc<Self>, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Ok(Arc::new(EmpiricalSample::parse(path, &contents)?))
+    }
+
+    fn parse(path: &Path, contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        let header = lines.next().ok_or_else(|| format!("{} is empty", path.display()))?;
+        let columns: Vec<String> = header.split(',').map(|name| name.trim().trim_matches('"').to_string()).collect();
+        let mut rows = Vec::new();
+        for (number, line) in lines.enumerate() {
+            let row = line
+                .split(',')
+                .map(|field| field.trim().parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| format!("row {} of {} has a field that is not a number", number + 1, path.display()))?;
+            if row.len() != columns.len() {
+                return Err(format!("row {} of {} has {} fields, the header {}", number + 1, path.display(), row.len(), columns.len()));
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(format!("{} has no rows", path.display()));
+        }
+        let mut weights: Vec<f64> = match columns.iter().position(|name| name == "weight") {
+            Some(w) => rows.iter().map(|row| row[w]).collect(),
+            None => vec![1.0; rows.len()],
+        };
+        if weights.iter().any(|&w| w.is_nan() || w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+            return Err(format!("the weights in {} must be non-negative and not all zero", path.display()));
+        }
+        let total: f64 = weights.iter().sum();
+        let mut cumulative = 0.0;
+        for weight in &mut weights {
+            cumulative += *weight / total;
+            *weight = cumulative;
+        }
+        Ok(EmpiricalSample { path: path.to_path_buf(), columns, rows, weights })
+    }
+
+    /// Draws a row, by weight.
+    pub fn draw_row<R: Rng>(&self, rng: &mut R) -> usize {
+        let pick: f64 = rng.gen();
+        self.weights.iter().position(|&cumulative| pick < cumulative).unwrap_or(self.rows.len() - 1)
+    }
+}
+
+impl ParameterDistribution {
+    /// Reads a distribution table for the parameter `key`, e.g. { distribution = "uniform", low = 0, high = 1 };
+    /// `origin` is the file it is in, for empirical samples named relative to it.
+    pub fn from_table(spec: &Map<String, Value>, key: &str, origin: &Path) -> Result<Self, String> {
+        let name = spec
+            .get("distribution")
+            .and_then(Value::as_str)
+            .ok_or("a distribution table needs distribution = \"uniform\", \"log_uniform\", \"beta\", \"gamma\" or \"empirical\"")?;
+        let fields: &[&str] = match name {
+            "uniform" | "log_uniform" => &["low", "high"],
+            "beta" => &["alpha", "beta"],
+            "gamma" => &["shape", "scale"],
+            "empirical" => &["file", "column"],
+            _ => return Err(format!("unknown distribution \"{}\" (uniform, log_uniform, beta, gamma or empirical)", name)),
+        };
+        for field in spec.keys() {
+            if field != "distribution" && !fields.contains(&field.as_str()) {
+                return Err(format!("unknown field \"{}\" in a {} distribution", field, name));
+            }
+        }
+        let number = |field: &str| {
+            spec.get(field)
+                .and_then(Value::as_f64)
+                .ok_or_else(|| format!("a {} distribution needs a number \"{}\"", name, field))
+        };
+        let positive = |field: &str| {
+            let value = number(field)?;
+            if value > 0.0 && value.is_finite() {
+                Ok(value)
+            } else {
+                Err(format!("\"{}\" of a {} distribution must be above 0", field, name))
+            }
+        };
+
+        match name {
+            "uniform" | "log_uniform" => {
+                let (low, high) = (number("low")?, number("high")?);
+                if low >= high || low.is_nan() || high.is_nan() {
+                    return Err(format!("low ({}) must be below high ({})", low, high));
+                }
+                match name {
+                    "uniform" => Ok(ParameterDistribution::Uniform { low, high }),
+                    _ if low > 0.0 => Ok(ParameterDistribution::LogUniform { low, high }),
+                    _ => Err("a log_uniform distribution needs low > 0".to_string()),
+                }
+            }
+            "beta" => Ok(ParameterDistribution::Beta { alpha: positive("alpha")?, beta: positive("beta")? }),
+            "gamma" => Ok(ParameterDistribution::Gamma { shape: positive("shape")?, scale: positive("scale")? }),
+            _ => {
+                let file = spec.get("file").and_then(Value::as_str).ok_or("an empirical distribution needs a \"file\"")?;
+                let column_name = match spec.get("column") {
+                    Some(column) => column.as_str().ok_or("\"column\" must be a column name")?,
+                    None => key,
+                };
+                let path = origin.parent().unwrap_or(Path::new("")).join(file);
+                let path = path.canonicalize().unwrap_or(path);
+                let sample = EmpiricalSample::load(&path)?;
+                let column = sample
+                    .columns
+                    .iter()
+                    .position(|name| name == column_name)
+                    .ok_or_else(|| format!("{} has no column \"{}\"", path.display(), column_name))?;
+                Ok(ParameterDistribution::Empirical { sample, column })
+            }
+        }
+    }
+
+    /// The distribution as a TOML inline table, as read by `from_table`.
+    pub fn to_toml(&self) -> String {
+        match self {
+            ParameterDistribution::Uniform { low, high } => {
+                format!("{{ distribution = \"uniform\", low = {:?}, high = {:?} }}", low, high)
+            }
+            ParameterDistribution::LogUniform { low, high } => {
+                format!("{{ distribution = \"log_uniform\", low = {:?}, high = {:?} }}", low, high)
+            }
+            ParameterDistribution::Beta { alpha, beta } => {
+                format!("{{ distribution = \"beta\", alpha = {:?}, beta = {:?} }}", alpha, beta)
+            }
+            ParameterDistribution::Gamma { shape, scale } => {
+                format!("{{ distribution = \"gamma\", shape = {:?}, scale = {:?} }}", shape, scale)
+            }
+            ParameterDistribution::Empirical { sample, column } => format!(
+                "{{ distribution = \"empirical\", file = {:?}, column = {:?} }}",
+                sample.path.display().to_string(),
+                sample.columns[*column]
+            ),
+        }
+    }
+
+    /// The smallest and largest values the distribution can take.
+    pub fn bounds(&self) -> (f64, f64) {
+        match self {
+            ParameterDistribution::Uniform { low, high } | ParameterDistribution::LogUniform { low, high } => (*low, *high),
+            ParameterDistribution::Beta { .. } => (0.0, 1.0),
+            ParameterDistribution::Gamma { .. } => (0.0, f64::INFINITY),
+            ParameterDistribution::Empirical { sample, column } => {
+                let values = sample.rows.iter().map(|row| row[*column]);
+                (values.clone().fold(f64::INFINITY, f64::min), values.fold(f64::NEG_INFINITY, f64::max))
+            }
+        }
+    }
+
+    /// Draws a value.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        match self {
+            ParameterDistribution::Uniform { low, high } => rng.gen_range(*low..*high),
+            ParameterDistribution::LogUniform { low, high } => rng.gen_range(low.ln()..high.ln()).exp(),
+            ParameterDistribution::Beta { alpha, beta } => {
+                Beta::new(*alpha, *beta).expect("alpha and beta are checked to be above 0").sample(rng)
+            }
+            ParameterDistribution::Gamma { shape, scale } => {
+                Gamma::new(*shape, *scale).expect("shape and scale are checked to be above 0").sample(rng)
+            }
+            ParameterDistribution::Empirical { sample, column } => sample.rows[sample.draw_row(rng)][*column],
+        }
+    }
+
+    /// The value at quantile `u` (0 to 1) of the distribution, for the uniform and log-uniform distributions.
+    pub fn from_unit(&self, u: f64) -> Option<f64> {
+        match *self {
+            ParameterDistribution::Uniform { low, high } => Some(low + u * (high - low)),
+            ParameterDistribution::LogUniform { low, high } => Some((low.ln() + u * (high.ln() - low.ln())).exp()),
+            _ => None,
+        }
+    }
+
+    /// A value on the working scale.
+    pub fn to_working(&self, value: f64) -> f64 {
+        match self {
+            ParameterDistribution::LogUniform { .. } => value.ln(),
+            _ => value,
+        }
+    }
+
+    /// The value for a point on the working scale.
+    pub fn from_working(&self, working: f64) -> f64 {
+        match self {
+            ParameterDistribution::LogUniform { .. } => working.exp(),
+            _ => working,
+        }
+    }
+
+    /// The density at a point on the working scale (0 outside the bounds); None for an empirical distribution,
+    /// which has no density.
+    pub fn working_density(&self, working: f64) -> Option<f64> {
+        let (low, high) = match *self {
+            ParameterDistribution::Uniform { low, high } => (low, high),
+            ParameterDistribution::LogUniform { low, high } => (low.ln(), high.ln()),
+            ParameterDistribution::Beta { alpha, beta } => {
+                if !(0.0..=1.0).contains(&working) {
+                    return Some(0.0);
+                }
+                let ln_b = ln_gamma(alpha) + ln_gamma(beta) - ln_gamma(alpha + beta);
+                return Some(((alpha - 1.0) * working.ln() + (beta - 1.0) * (1.0 - working).ln() - ln_b).exp());
+            }
+            ParameterDistribution::Gamma { shape, scale } => {
+                if working < 0.0 {
+                    return Some(0.0);
+                }
+                return Some(((shape - 1.0) * working.ln() - working / scale - ln_gamma(shape) - shape * scale.ln()).exp());
+            }
+            ParameterDistribution::Empirical { .. } => return None,
+        };
+        if (low..=high).contains(&working) {
+            Some(1.0 / (high - low))
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+// ln Γ(x) for x > 0 (Lanczos approximation, g = 7)
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..].iter().enumerate().fold(COEFFICIENTS[0], |sum, (i, &c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::catalog::Catalog;
+    use crate::config::loader::{ParameterFileFormat, ParameterSet};
+    use rand::SeedableRng;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn ln_gamma_matches_known_values() {
+        assert!(close(ln_gamma(1.0), 0.0));
+        assert!(close(ln_gamma(2.0), 0.0));
+        assert!(close(ln_gamma(5.0), 24.0f64.ln()));
+        assert!(close(ln_gamma(0.5), std::f64::consts::PI.sqrt().ln()));
+        assert!(close(ln_gamma(0.25), 1.288_022_524_698_077_5)); // by reflection
+        assert!(close(ln_gamma(100.0), 359.134_205_369_575_4));
+    }
+
+    #[test]
+    fn beta_and_gamma_densities() {
+        // Beta(2, 8): B(2, 8) = 1/72
+        let beta = ParameterDistribution::Beta { alpha: 2.0, beta: 8.0 };
+        assert!(close(beta.working_density(0.1).unwrap(), 72.0 * 0.1 * 0.9f64.powi(7)));
+        assert_eq!(beta.working_density(1.5), Some(0.0));
+        // Gamma(shape 2, scale 0.5) at 1: x e^(-x / scale) / scale^2
+        let gamma = ParameterDistribution::Gamma { shape: 2.0, scale: 0.5 };
+        assert!(close(gamma.working_density(1.0).unwrap(), 4.0 * (-2.0f64).exp()));
+        assert_eq!(gamma.working_density(-1.0), Some(0.0));
+    }
+
+    #[test]
+    fn empirical_csv_parsing() {
+        let path = Path::new("posterior.csv");
+        let sample = EmpiricalSample::parse(path, "# a fit\n\"x\", y ,weight\n1,10,1\n\n2,20,3\n").unwrap();
+        assert_eq!(sample.columns, ["x", "y", "weight"]);
+        assert_eq!(sample.rows, [vec![1.0, 10.0, 1.0], vec![2.0, 20.0, 3.0]]);
+        assert_eq!(sample.weights, [0.25, 1.0]);
+
+        assert!(EmpiricalSample::parse(path, "x,y\n1\n").unwrap_err().contains("1 fields"));
+        assert!(EmpiricalSample::parse(path, "x,y\n1,a\n").unwrap_err().contains("not a number"));
+        assert!(EmpiricalSample::parse(path, "x,y\n").unwrap_err().contains("no rows"));
+        assert!(EmpiricalSample::parse(path, "x,weight\n1,-1\n2,2\n").unwrap_err().contains("non-negative"));
+    }
+
+    #[test]
+    fn keys_of_one_empirical_sample_are_drawn_from_the_same_row() {
+        let dir = std::env::temp_dir().join(format!("amr_empirical_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("posterior.csv"), "x,y\n1,10\n2,20\n3,30\n4,40\n").unwrap();
+        let mut set = ParameterSet::defaults(&Catalog::default_catalog());
+        let contents = r#"
+            x = { distribution = "empirical", file = "posterior.csv" }
+            y = { distribution = "empirical", file = "posterior.csv" }
+        "#;
+        let applied = set.apply_str(&Catalog::default_catalog(), contents, ParameterFileFormat::Toml, &dir.join("params.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        applied.unwrap();
+
+        let mut rows_seen = Vec::new();
+        for seed in 0..20 {
+            let drawn = set.draw_distributions(&mut rand::rngs::StdRng::seed_from_u64(seed));
+            let (x, y) = (drawn.get("x").unwrap(), drawn.get("y").unwrap());
+            assert_eq!(y, 10.0 * x);
+            rows_seen.push(x);
+        }
+        rows_seen.dedup();
+        assert!(rows_seen.len() > 1);
+    }
+}
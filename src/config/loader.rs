@@ -0,0 +1,462 @@
+// src/config/loader.rs
+//
+// Loading of model parameters from external files.
+//
+// The compiled-in defaults in config.rs (`default_parameters` and `default_string_parameters`, built over the
+// bacteria, drugs and regions of a catalog) are the defaults.  A `ParameterSet` starts from a copy of those defaults and then has one or more override files layered on top, in order, so a
+// later file wins over an earlier one.  Override files are flat tables of "parameter key = value":
+//
+//     # overrides.toml
+//     "drug_base_initiation_rate_per_day" = 0.0002
+//     "escherichia coli_acquisition_prob_baseline" = 0.002
+//     "escherichia coli_age_risk_template" = "urogenital"
+//
+// or the same in json:
+//
+//     { "drug_base_initiation_rate_per_day": 0.0002, "escherichia coli_age_risk_template": "urogenital" }
+//
+// A drug class or subclass can be named where a drug name would go, as "drug_class_{class}_" or
+// "drug_subclass_{subclass}_" in place of "drug_{drug}_" (class and subclass names as in the catalog):
+//
+//     "drug_class_carbapenem_initial_level" = 12.0
+//     "africa_drug_subclass_cephalosporin_3rd_gen_availability" = 0.5
+//     "drug_meropenem_initial_level" = 15.0          # per-drug keys override class and subclass keys
+//
+// Numeric values go to the numeric parameters and string values to the string parameters.  A table value defines
+// a calendar-time schedule for a numeric parameter (see schedule.rs), or, with a "distribution" field, declares it
+// uncertain (see distribution.rs):
+//
+//     "resistance_emergence_rate_per_day_baseline" = { distribution = "log_uniform", low = 1e-6, high = 0.01 }
+//     "escherichia coli_acquisition_prob_baseline" = { distribution = "empirical", file = "posterior.csv" }
+//
+// Each simulation draws its own value of every such parameter from its seed when it is built (so replicates draw
+// different values), and its parameter set then holds the values drawn.  The merged set is handed to the simulation
+// (see simulation/builder.rs), which resolves it into the tables read by the rules (see tables.rs).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use rand::Rng;
+
+use crate::config::catalog::{Catalog, DrugGroup};
+use crate::config::distribution::ParameterDistribution;
+use crate::config::schedule::{CalendarDate, Interpolation, Schedule};
+use crate::config::schema::ParameterSchema;
+use crate::config::{default_parameters, default_string_parameters};
+
+/// The file formats accepted for parameter override files, chosen from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterFileFormat {
+    Toml,
+    Json,
+}
+
+impl ParameterFileFormat {
+    /// Picks the format from the file extension (".toml" or ".json").
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()) {
+            Some(ext) if ext == "toml" => Some(ParameterFileFormat::Toml),
+            Some(ext) if ext == "json" => Some(ParameterFileFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Errors raised while reading a parameter override file.
+#[derive(Debug)]
+pub enum ParameterFileError {
+    Io { path: PathBuf, source: std::io::Error },
+    UnsupportedFormat { path: PathBuf },
+    Parse { path: PathBuf, message: String },
+    InvalidValue { path: PathBuf, key: String, message: String },
+}
+
+impl fmt::Display for ParameterFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterFileError::Io { path, source } => {
+                write!(f, "could not read parameter file {}: {}", path.display(), source)
+            }
+            ParameterFileError::UnsupportedFormat { path } => {
+                write!(f, "parameter file {} must have a .toml or .json extension", path.display())
+            }
+            ParameterFileError::Parse { path, message } => {
+                write!(f, "could not parse parameter file {}: {}", path.display(), message)
+            }
+            ParameterFileError::InvalidValue { path, key, message } => {
+                write!(f, "invalid value for \"{}\" in parameter file {}: {}", key, path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParameterFileError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// A complete, merged set of model parameters: the compiled-in defaults with any override files applied on top.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterSet {
+    values: HashMap<String, f64>,
+    strings: HashMap<String, String>,
+    schedules: HashMap<String, Schedule>, // calendar-time-varying values, which take precedence over `values`
+    #[serde(skip)]
+    distributions: HashMap<String, ParameterDistribution>, // uncertain values, drawn by `draw_distributions`
+    sources: Vec<PathBuf>, // override files applied so far, in the order they were applied
+}
+
+impl ParameterSet {
+    /// A parameter set holding just the compiled-in defaults from config.rs for the bacteria, drugs and regions of
+    /// `catalog`.  Defaults for bacteria, drugs or regions that are not in the catalog are left out.
+    pub fn defaults(catalog: &Catalog) -> Self {
+        let schema = ParameterSchema::for_catalog(catalog);
+        let keep = |key: &String| schema.knows(key);
+        ParameterSet {
+            values: default_parameters(catalog).into_iter().filter(|(k, _)| keep(k)).collect(),
+            strings: default_string_parameters(catalog).into_iter().filter(|(k, _)| keep(k)).collect(),
+            schedules: HashMap::new(),
+            distributions: HashMap::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// The defaults with each of the given files applied in turn (later files override earlier ones).
+    pub fn from_files<P: AsRef<Path>>(catalog: &Catalog, paths: &[P]) -> Result<Self, ParameterFileError> {
+        let mut set = ParameterSet::defaults(catalog);
+        for path in paths {
+            set.apply_file(catalog, path.as_ref())?;
+        }
+        Ok(set)
+    }
+
+    /// Reads one override file and layers its values on top of the current set.  Drug class and subclass keys are
+    /// expanded over the drugs of `catalog`.
+    pub fn apply_file(&mut self, catalog: &Catalog, path: &Path) -> Result<(), ParameterFileError> {
+        let format = ParameterFileFormat::from_path(path)
+            .ok_or_else(|| ParameterFileError::UnsupportedFormat { path: path.to_path_buf() })?;
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| ParameterFileError::Io { path: path.to_path_buf(), source })?;
+        self.apply_str(catalog, &contents, format, path)?;
+        self.sources.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Layers override values given as file contents on top of the current set.
+    /// `origin` is only used to label errors.
+    pub fn apply_str(
+        &mut self,
+        catalog: &Catalog,
+        contents: &str,
+        format: ParameterFileFormat,
+        origin: &Path,
+    ) -> Result<(), ParameterFileError> {
+        let parsed: Value = match format {
+            ParameterFileFormat::Toml => toml::from_str(contents)
+                .map_err(|e| ParameterFileError::Parse { path: origin.to_path_buf(), message: e.to_string() })?,
+            ParameterFileFormat::Json => serde_json::from_str(contents)
+                .map_err(|e| ParameterFileError::Parse { path: origin.to_path_buf(), message: e.to_string() })?,
+        };
+
+        let table = match parsed {
+            Value::Object(table) => table,
+            _ => {
+                return Err(ParameterFileError::Parse {
+                    path: origin.to_path_buf(),
+                    message: "expected a table of parameter keys and values at the top level".to_string(),
+                })
+            }
+        };
+
+        // keys naming a drug class or subclass stand for the same key for each of its drugs; they are applied first,
+        // classes before subclasses, so that within one file a subclass overrides its class and a drug its subclass
+        let mut entries: Vec<(u8, String, Value)> = Vec::new();
+        for (key, value) in table {
+            match catalog.expand_group_key(&key) {
+                Some((group, keys)) => {
+                    let rank = match group {
+                        DrugGroup::Class(_) => 0,
+                        DrugGroup::Subclass(_) => 1,
+                    };
+                    entries.extend(keys.into_iter().map(|k| (rank, k, value.clone())));
+                }
+                None => entries.push((2, key, value)),
+            }
+        }
+        entries.sort_by_key(|(rank, _, _)| *rank);
+
+        for (_, key, value) in entries {
+            match value {
+                Value::Number(number) => {
+                    let number = number.as_f64().ok_or_else(|| ParameterFileError::InvalidValue {
+                        path: origin.to_path_buf(),
+                        key: key.clone(),
+                        message: "number is out of range for f64".to_string(),
+                    })?;
+                    self.schedules.remove(&key);
+                    self.distributions.remove(&key);
+                    self.values.insert(key, number);
+                }
+                Value::String(text) => {
+                    self.strings.insert(key, text);
+                }
+                Value::Object(spec) if spec.contains_key("distribution") => {
+                    let distribution = ParameterDistribution::from_table(&spec, &key, origin).map_err(|message| {
+                        ParameterFileError::InvalidValue { path: origin.to_path_buf(), key: key.clone(), message }
+                    })?;
+                    self.schedules.remove(&key);
+                    self.distributions.insert(key, distribution);
+                }
+                Value::Object(spec) => {
+                    let schedule = parse_schedule(&spec).map_err(|message| ParameterFileError::InvalidValue {
+                        path: origin.to_path_buf(),
+                        key: key.clone(),
+                        message,
+                    })?;
+                    self.distributions.remove(&key);
+                    self.schedules.insert(key, schedule);
+                }
+                other => {
+                    return Err(ParameterFileError::InvalidValue {
+                        path: origin.to_path_buf(),
+                        key,
+                        message: format!("expected a number, a string, a schedule or a distribution table, found {}", other),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets a numeric parameter to a constant value, replacing any schedule or distribution for it.
+    pub fn set_value(&mut self, key: &str, value: f64) {
+        self.schedules.remove(key);
+        self.distributions.remove(key);
+        self.values.insert(key.to_string(), value);
+    }
+
+    /// Sets a string parameter.
+    pub fn set_string(&mut self, key: &str, value: &str) {
+        self.strings.insert(key.to_string(), value.to_string());
+    }
+
+    /// Whether any parameter follows a calendar-time schedule.
+    pub fn has_schedules(&self) -> bool {
+        !self.schedules.is_empty()
+    }
+
+    /// The values of the scheduled parameters on the given day number, in key order; the values in force change
+    /// only on the days this does.
+    pub fn scheduled_values_at(&self, day_number: i64) -> Vec<f64> {
+        let mut keys: Vec<&String> = self.schedules.keys().collect();
+        keys.sort();
+        keys.into_iter().map(|key| self.schedules[key].value_at(day_number)).collect()
+    }
+
+    /// Looks up a numeric parameter, ignoring any schedule (i.e. its constant value).
+    pub fn get(&self, key: &str) -> Option<f64> {
+        self.values.get(key).copied()
+    }
+
+    /// Looks up a numeric parameter as in force on the given day number (see `CalendarDate::day_number`).
+    pub fn get_at(&self, key: &str, day_number: i64) -> Option<f64> {
+        if !self.schedules.is_empty() {
+            if let Some(schedule) = self.schedules.get(key) {
+                return Some(schedule.value_at(day_number));
+            }
+        }
+        self.values.get(key).copied()
+    }
+
+    /// Looks up a string parameter.
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(|s| s.as_str())
+    }
+
+    /// All numeric parameters in the set.
+    pub fn values(&self) -> &HashMap<String, f64> {
+        &self.values
+    }
+
+    /// All string parameters in the set.
+    pub fn strings(&self) -> &HashMap<String, String> {
+        &self.strings
+    }
+
+    /// All calendar-time schedules in the set.
+    pub fn schedules(&self) -> &HashMap<String, Schedule> {
+        &self.schedules
+    }
+
+    /// The parameters declared as distributions.
+    pub fn distributions(&self) -> &HashMap<String, ParameterDistribution> {
+        &self.distributions
+    }
+
+    /// A copy of the set with every distribution replaced by a value drawn from it, in key order.  Parameters
+    /// taken from the same empirical sample get their values from the same row of it.
+    pub fn draw_distributions<R: Rng>(&self, rng: &mut R) -> ParameterSet {
+        let mut drawn = self.clone();
+        let mut keys: Vec<&String> = self.distributions.keys().collect();
+        keys.sort();
+        let mut rows: HashMap<PathBuf, usize> = HashMap::new(); // the row drawn from each empirical sample
+        for key in keys {
+            let value = match &self.distributions[key] {
+                ParameterDistribution::Empirical { sample, column } => {
+                    let row = *rows.entry(sample.path.clone()).or_insert_with(|| sample.draw_row(rng));
+                    sample.rows[row][*column]
+                }
+                distribution => distribution.sample(rng),
+            };
+            drawn.set_value(key, value);
+        }
+        drawn
+    }
+
+    /// A copy of the set with every schedule replaced by its value on the given day number.
+    pub fn resolved_at(&self, day_number: i64) -> ParameterSet {
+        let mut resolved = self.clone();
+        for (key, schedule) in resolved.schedules.drain() {
+            resolved.values.insert(key, schedule.value_at(day_number));
+        }
+        resolved
+    }
+
+    /// Writes the whole set as a TOML parameter file, keys in sorted order.  Applying the file to the defaults
+    /// (or to any set) gives back this set's values, strings, schedules and distributions.
+    pub fn write_toml<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let mut strings: Vec<(&String, &String)> = self.strings.iter().collect();
+        strings.sort();
+        for (key, text) in strings {
+            writeln!(out, "{} = {}", toml_string(key), toml_string(text))?;
+        }
+        let mut keys: Vec<&String> = self.values.keys().chain(self.schedules.keys()).chain(self.distributions.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            if let Some(distribution) = self.distributions.get(key.as_str()) {
+                writeln!(out, "{} = {}", toml_string(key), distribution.to_toml())?;
+                continue;
+            }
+            match self.schedules.get(key.as_str()) {
+                Some(schedule) => {
+                    let interpolation = match schedule.interpolation {
+                        Interpolation::Linear => "linear",
+                        Interpolation::Step => "step",
+                    };
+                    let points: Vec<String> = schedule
+                        .points()
+                        .map(|(date, value)| format!("[\"{}\", {}]", date, toml_float(value)))
+                        .collect();
+                    writeln!(
+                        out,
+                        "{} = {{ interpolation = \"{}\", points = [{}] }}",
+                        toml_string(key),
+                        interpolation,
+                        points.join(", ")
+                    )?;
+                }
+                None => writeln!(out, "{} = {}", toml_string(key), toml_float(self.values[key.as_str()]))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The override files applied to the defaults, in order.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+}
+
+// A TOML basic string.
+fn toml_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// A TOML float (always with a decimal point or exponent, so it reads back as a float).
+fn toml_float(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+// Reads a schedule table: { interpolation = "linear" | "step", points = [[date, value], ...] }.
+fn parse_schedule(spec: &Map<String, Value>) -> Result<Schedule, String> {
+    if let Some(field) = spec.keys().find(|k| *k != "interpolation" && *k != "points") {
+        return Err(format!("unexpected field \"{}\" in schedule (expected \"interpolation\" and \"points\")", field));
+    }
+    let interpolation = match spec.get("interpolation") {
+        None => Interpolation::Linear,
+        Some(Value::String(name)) if name == "linear" => Interpolation::Linear,
+        Some(Value::String(name)) if name == "step" => Interpolation::Step,
+        Some(other) => return Err(format!("interpolation must be \"linear\" or \"step\", found {}", other)),
+    };
+    let points = match spec.get("points") {
+        Some(Value::Array(points)) => points,
+        _ => return Err("a schedule needs a \"points\" array of [date, value] pairs".to_string()),
+    };
+    let mut parsed = Vec::with_capacity(points.len());
+    for point in points {
+        let (date, value) = match point.as_array().map(|p| p.as_slice()) {
+            Some([date, value]) => (date, value),
+            _ => return Err(format!("schedule point {} is not a [date, value] pair", point)),
+        };
+        let date = parse_date(date).ok_or_else(|| format!("schedule point {} does not start with a YYYY-MM-DD date", point))?;
+        let value = value.as_f64().ok_or_else(|| format!("schedule point {} does not have a numeric value", point))?;
+        parsed.push((date, value));
+    }
+    Schedule::new(interpolation, parsed)
+}
+
+// Dates may be quoted strings, or bare TOML dates (which arrive as a single-entry table holding the date text).
+fn parse_date(value: &Value) -> Option<CalendarDate> {
+    match value {
+        Value::String(text) => CalendarDate::parse(text),
+        Value::Object(wrapped) if wrapped.len() == 1 => wrapped.values().next()?.as_str().and_then(CalendarDate::parse),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_drug_key_overrides_its_subclass_which_overrides_its_class_in_any_order() {
+        let catalog = Catalog::default_catalog();
+        // a subclass with two drugs, in a class that also has a drug outside it
+        let (drug, sibling, cousin) = catalog
+            .drugs
+            .iter()
+            .find_map(|d| {
+                let sibling = catalog.drugs.iter().find(|o| o.name != d.name && o.subclass == d.subclass)?;
+                let cousin = catalog.drugs.iter().find(|o| o.class == d.class && o.subclass != d.subclass)?;
+                Some((d, sibling, cousin))
+            })
+            .expect("the default catalog has a class with more than one subclass");
+        let lines = [
+            format!("drug_{}_half_life_days = 3.0", drug.name),
+            format!("{}half_life_days = 2.0", DrugGroup::Subclass(drug.subclass).key_prefix()),
+            format!("{}half_life_days = 1.0", DrugGroup::Class(drug.class).key_prefix()),
+        ];
+
+        for contents in [lines.join("\n"), lines.iter().rev().cloned().collect::<Vec<_>>().join("\n")] {
+            let mut set = ParameterSet::defaults(&catalog);
+            set.apply_str(&catalog, &contents, ParameterFileFormat::Toml, Path::new("test.toml")).unwrap();
+            let half_life = |name: &str| set.get(&format!("drug_{}_half_life_days", name));
+            assert_eq!(half_life(&drug.name), Some(3.0));
+            assert_eq!(half_life(&sibling.name), Some(2.0));
+            assert_eq!(half_life(&cousin.name), Some(1.0));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+// src/config/schedule.rs
+//
+// Calendar dates and calendar-time-varying parameter values.
+//
+// Any numeric parameter can be given a schedule instead of a single value, as a table in a parameter file:
+//
+//     ["drug_base_initiation_rate_per_day"]
+//     interpolation = "linear"                      # or "step"
+//     points = [["1942-01-01", 0.0], ["1960-01-01", 0.0001], ["2020-01-01", 0.0003]]
+//
+// With "linear" the value is interpolated between points; with "step" each value holds from its date until the
+// next point.  Before the first point the first value applies and after the last point the last value applies.
+//
+// The simulation maps time_step t to the calendar day `simulation_start_date + t` and resolves the parameters for
+// that day (see `ParameterTables::resolve` in tables.rs), so the rules read the value in force on the simulated day.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A date in the proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CalendarDate {
+    pub year: i32,
+    pub month: u32, // 1-12
+    pub day: u32,   // 1-31
+}
+
+impl CalendarDate {
+    /// Builds a date, returning `None` if the month or day is out of range.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(CalendarDate { year, month, day })
+    }
+
+    /// Parses an ISO "YYYY-MM-DD" date.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        CalendarDate::from_ymd(year, month, day)
+    }
+
+    /// Days since 1970-01-01 (negative for earlier dates).
+    pub fn day_number(&self) -> i64 {
+        // days-from-civil, after H. Hinnant
+        let y = if self.month <= 2 { self.year as i64 - 1 } else { self.year as i64 };
+        let era = (if y >= 0 { y } else { y - 399 }) / 400;
+        let year_of_era = y - era * 400;
+        let m = self.month as i64;
+        let day_of_year = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// The date for a day number as returned by `day_number`.
+    pub fn from_day_number(day_number: i64) -> Self {
+        let z = day_number + 719468;
+        let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+        let day_of_era = z - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        CalendarDate { year, month, day }
+    }
+
+    /// The date `days` days after this one.
+    pub fn add_days(&self, days: i64) -> Self {
+        CalendarDate::from_day_number(self.day_number() + days)
+    }
+
+    /// The year with the part of it gone by on this date, e.g. about 2000.5 on 2000-07-02.
+    pub fn fractional_year(&self) -> f64 {
+        let new_year = CalendarDate { year: self.year, month: 1, day: 1 };
+        let next_new_year = CalendarDate { year: self.year + 1, month: 1, day: 1 };
+        let days_in_year = (next_new_year.day_number() - new_year.day_number()) as f64;
+        self.year as f64 + (self.day_number() - new_year.day_number()) as f64 / days_in_year
+    }
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// How a schedule moves between its points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    Linear,
+    Step,
+}
+
+/// A parameter value that varies over calendar time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub interpolation: Interpolation,
+    points: Vec<(i64, f64)>, // (day number, value), sorted by day, at least one point
+}
+
+impl Schedule {
+    /// Builds a schedule from (date, value) points, which may be given in any order.
+    /// Returns an error message if there are no points or two points share a date.
+    pub fn new(interpolation: Interpolation, points: Vec<(CalendarDate, f64)>) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("a schedule needs at least one point".to_string());
+        }
+        let mut points: Vec<(i64, f64)> = points.into_iter().map(|(date, value)| (date.day_number(), value)).collect();
+        points.sort_by_key(|&(day, _)| day);
+        if let Some(pair) = points.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("two points are given for {}", CalendarDate::from_day_number(pair[0].0)));
+        }
+        Ok(Schedule { interpolation, points })
+    }
+
+    /// The value in force on the given day number.
+    pub fn value_at(&self, day_number: i64) -> f64 {
+        // index of the first point after the day
+        let next = self.points.partition_point(|&(day, _)| day <= day_number);
+        if next == 0 {
+            return self.points[0].1;
+        }
+        let (day_before, value_before) = self.points[next - 1];
+        if next == self.points.len() || self.interpolation == Interpolation::Step {
+            return value_before;
+        }
+        let (day_after, value_after) = self.points[next];
+        let fraction = (day_number - day_before) as f64 / (day_after - day_before) as f64;
+        value_before + fraction * (value_after - value_before)
+    }
+
+    /// The (date, value) points of the schedule, in date order.
+    pub fn points(&self) -> impl Iterator<Item = (CalendarDate, f64)> + '_ {
+        self.points.iter().map(|&(day, value)| (CalendarDate::from_day_number(day), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> CalendarDate {
+        CalendarDate::parse(text).unwrap()
+    }
+
+    #[test]
+    fn day_numbers_count_from_1970() {
+        assert_eq!(date("1970-01-01").day_number(), 0);
+        assert_eq!(date("1969-12-31").day_number(), -1);
+        assert_eq!(date("2000-03-01").day_number() - date("2000-02-28").day_number(), 2); // 2000 is a leap year
+        assert_eq!(date("1900-03-01").day_number() - date("1900-02-28").day_number(), 1); // 1900 is not
+        assert_eq!(date("2024-03-01").day_number() - date("2024-02-28").day_number(), 2);
+        assert_eq!(date("2023-03-01").day_number() - date("2023-02-28").day_number(), 1);
+    }
+
+    #[test]
+    fn day_numbers_round_trip_across_leap_years() {
+        let first = date("1896-01-01").day_number();
+        let last = date("2104-12-31").day_number();
+        let mut expected = date("1896-01-01");
+        for day_number in first..=last {
+            let round_trip = CalendarDate::from_day_number(day_number);
+            assert_eq!(round_trip, expected);
+            assert_eq!(round_trip.day_number(), day_number);
+            // the next date, counted by hand
+            expected = if expected.day < days_in_month(expected.year, expected.month) {
+                CalendarDate { day: expected.day + 1, ..expected }
+            } else if expected.month < 12 {
+                CalendarDate { month: expected.month + 1, day: 1, ..expected }
+            } else {
+                CalendarDate { year: expected.year + 1, month: 1, day: 1 }
+            };
+        }
+        assert_eq!(CalendarDate::from_day_number(date("1900-02-28").day_number() + 1), date("1900-03-01"));
+        assert_eq!(CalendarDate::from_day_number(date("2000-02-28").day_number() + 1), date("2000-02-29"));
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        assert!(CalendarDate::parse("1900-02-29").is_none());
+        assert!(CalendarDate::parse("2000-02-29").is_some());
+        assert!(CalendarDate::parse("2001-13-01").is_none());
+        assert!(CalendarDate::parse("2001-04-31").is_none());
+        assert!(CalendarDate::parse("2001-04").is_none());
+    }
+
+    fn schedule(interpolation: Interpolation) -> Schedule {
+        // given out of order on purpose
+        Schedule::new(interpolation, vec![(date("2000-01-11"), 3.0), (date("2000-01-01"), 1.0)]).unwrap()
+    }
+
+    #[test]
+    fn linear_schedule_values() {
+        let schedule = schedule(Interpolation::Linear);
+        assert_eq!(schedule.value_at(date("1999-06-01").day_number()), 1.0); // before the first point
+        assert_eq!(schedule.value_at(date("2000-01-01").day_number()), 1.0); // at a point
+        assert!((schedule.value_at(date("2000-01-06").day_number()) - 2.0).abs() < 1e-12); // half way
+        assert_eq!(schedule.value_at(date("2000-01-11").day_number()), 3.0);
+        assert_eq!(schedule.value_at(date("2010-01-01").day_number()), 3.0); // after the last point
+    }
+
+    #[test]
+    fn step_schedule_values() {
+        let schedule = schedule(Interpolation::Step);
+        assert_eq!(schedule.value_at(date("1999-06-01").day_number()), 1.0);
+        assert_eq!(schedule.value_at(date("2000-01-01").day_number()), 1.0);
+        assert_eq!(schedule.value_at(date("2000-01-10").day_number()), 1.0);
+        assert_eq!(schedule.value_at(date("2000-01-11").day_number()), 3.0);
+        assert_eq!(schedule.value_at(date("2010-01-01").day_number()), 3.0);
+    }
+
+    #[test]
+    fn schedules_need_distinct_points() {
+        assert!(Schedule::new(Interpolation::Linear, Vec::new()).is_err());
+        let same_day = vec![(date("2000-01-01"), 1.0), (date("2000-01-01"), 2.0)];
+        assert!(Schedule::new(Interpolation::Step, same_day).is_err());
+        let single = Schedule::new(Interpolation::Linear, vec![(date("2000-01-01"), 5.0)]).unwrap();
+        assert_eq!(single.value_at(0), 5.0);
+        assert_eq!(single.value_at(date("2100-01-01").day_number()), 5.0);
+    }
+}
@@ -0,0 +1,636 @@
+// src/config/schema.rs
+//
+// Registry of every parameter key the model knows about, with its type, allowed range and default.
+//
+// Keys are declared as families with placeholders that are expanded over the bacteria, drug and region lists:
+//
+//     {bacteria}     each bacteria of the catalog, as used in "{bacteria}_acquisition_prob_baseline"
+//     {bacteria_key} the same name with spaces replaced by underscores (used in the region risk multipliers)
+//     {drug}         each drug of the catalog
+//     {region}       each region of the catalog ("africa", "europe", ...); there are no "home" keys, since someone
+//                    at home is in the region they live in
+//     {sex}          "male" and "female"
+//     {syndrome}     the infectious syndrome ids 1 to 10
+//
+// Required keys must be present in the parameter set; optional keys are per-bacteria or per-drug overrides that
+// the rules fall back from when they are absent.  `ParameterSchema::validate` checks a `ParameterSet` against the
+// registry and reports unknown keys (typically typos in an override file), missing required keys, values of the
+// wrong type and values outside their allowed range (including every point of a calendar-time schedule and the
+// finite ends of a distribution's support), all together as one `ParameterValidationError`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::catalog::{Catalog, MAX_SYNDROME_ID};
+use crate::config::loader::ParameterSet;
+use crate::config::schedule::CalendarDate;
+use crate::config::{default_parameters, default_string_parameters, AGE_RISK_TEMPLATES};
+
+const INF: f64 = f64::INFINITY;
+const NEG_INF: f64 = f64::NEG_INFINITY;
+
+/// The type of value a parameter takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Number,
+    AgeRiskTemplate, // a string naming one of the entries of AGE_RISK_TEMPLATES
+    Date,            // a "YYYY-MM-DD" string
+}
+
+/// The compiled-in default for a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamDefault {
+    Number(f64),
+    Text(String),
+}
+
+/// Specification of one concrete parameter key.
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub key: String,
+    pub kind: ParamKind,
+    pub min: f64, // inclusive bounds, only used for ParamKind::Number
+    pub max: f64,
+    pub required: bool,
+    pub default: Option<ParamDefault>,
+    pub fallback: Option<&'static str>, // what the rules use when an optional key is absent
+}
+
+// A family of keys sharing a template, before expansion over the bacteria / drug / region lists.
+struct Family {
+    template: &'static str,
+    kind: ParamKind,
+    min: f64,
+    max: f64,
+    required: bool,
+    fallback: Option<&'static str>,
+}
+
+const fn required(template: &'static str, min: f64, max: f64) -> Family {
+    Family { template, kind: ParamKind::Number, min, max, required: true, fallback: None }
+}
+
+const fn optional(template: &'static str, min: f64, max: f64, fallback: &'static str) -> Family {
+    Family { template, kind: ParamKind::Number, min, max, required: false, fallback: Some(fallback) }
+}
+
+const FAMILIES: &[Family] = &[
+    // --- per bacteria ---
+    required("{bacteria}_acquisition_prob_baseline", 0.0, 1.0),
+    required("{bacteria}_initial_infection_level", 0.0, INF),
+    required("{bacteria}_environmental_acquisition_proportion", 0.0, 1.0),
+    required("{bacteria}_hospital_acquired_multiplier", 0.0, INF),
+    required("{bacteria}_adult_contact_acq_rate_ratio_per_unit", 0.0, INF),
+    required("{bacteria}_child_contact_acq_rate_ratio_per_unit", 0.0, INF),
+    required("{bacteria}_oral_exposure_acq_rate_ratio_per_unit", 0.0, INF),
+    required("{bacteria}_sexual_contact_acq_rate_ratio_per_unit", 0.0, INF),
+    required("{bacteria}_mosquito_exposure_acq_rate_ratio_per_unit", 0.0, INF),
+    required("{bacteria}_vaccine_efficacy", 0.0, 1.0),
+    required("{bacteria}_base_bacteria_level_change", NEG_INF, INF),
+    required("{bacteria}_max_level", 0.0, INF),
+    required("{bacteria}_immunity_effect_on_level_change", 0.0, INF),
+    required("{bacteria}_immunity_base_response", 0.0, INF),
+    required("{bacteria}_immunity_increase_per_unit_higher_bacteria_level", 0.0, INF),
+    required("{bacteria}_immunity_increase_per_infection_day", 0.0, INF),
+    required("{bacteria}_immunity_age_modifier", 0.0, INF),
+    required("{bacteria}_immunity_immunodeficiency_modifier", 0.0, INF),
+    required("{bacteria}_max_immune_response", 0.0, INF),
+    required("{bacteria}_age_effect_scaling", 0.0, INF),
+    Family {
+        template: "{bacteria}_age_risk_template",
+        kind: ParamKind::AgeRiskTemplate,
+        min: NEG_INF,
+        max: INF,
+        required: true,
+        fallback: None,
+    },
+    optional("{bacteria}_sepsis_baseline_risk_per_day", 0.0, 1.0, "default_sepsis_baseline_risk_per_day"),
+    optional("{bacteria}_sepsis_level_multiplier", 0.0, INF, "default_sepsis_level_multiplier"),
+    optional("{bacteria}_sepsis_duration_multiplier", 0.0, INF, "default_sepsis_duration_multiplier"),
+    optional("{bacteria}_microbiome_acquisition_multiplier", 0.0, INF, "default_microbiome_acquisition_multiplier"),
+    optional("{bacteria}_microbiome_clearance_probability_per_day", 0.0, 1.0, "default_microbiome_clearance_probability_per_day"),
+    optional("{bacteria}_microbiome_infection_acquisition_multiplier", 0.0, INF, "default_microbiome_infection_acquisition_multiplier"),
+
+    // --- per drug ---
+    required("drug_{drug}_half_life_days", 0.0, INF),
+    required("drug_{drug}_initial_level", 0.0, INF),
+    required("drug_{drug}_double_dose_multiplier", 0.0, INF),
+    required("drug_{drug}_spectrum_breadth", 1.0, 5.0),
+    optional("drug_{drug}_toxicity_per_unit_level_per_day", 0.0, INF, "default_drug_toxicity_per_unit_level_per_day"),
+    optional("drug_{drug}_adverse_event_death_risk", 0.0, 1.0, "0.0"),
+    optional("drug_{drug}_restricted_to_identified_infection", 0.0, 1.0, "0.0"),
+
+    // --- per drug / bacteria combination ---
+    required("drug_{drug}_for_bacteria_{bacteria}_initiation_multiplier", 0.0, INF),
+    required("drug_{drug}_for_bacteria_{bacteria}_potency_when_no_r", 0.0, INF),
+    required("drug_{drug}_for_bacteria_{bacteria}_resistance_emergence_rate_per_day_baseline", 0.0, 1.0),
+
+    // --- per region ---
+    required("{region}_travel_multiplier", 0.0, INF),
+    required("{region}_life_table_adjustment", 0.0, INF),
+    required("{region}_sepsis_mortality_multiplier", 0.0, INF),
+    required("{region}_mosquito_exposure_multiplier", 0.0, INF),
+    required("{region}_drug_{drug}_availability", 0.0, 1.0),
+    optional("{region}_{bacteria_key}_infection_risk_multiplier", 0.0, INF, "{region}_infection_risk_multiplier_default"),
+    optional("{region}_infection_risk_multiplier_default", 0.0, INF, "1.0"),
+
+    // --- calendar time ---
+    Family {
+        template: "simulation_start_date",
+        kind: ParamKind::Date,
+        min: NEG_INF,
+        max: INF,
+        required: true,
+        fallback: None,
+    },
+
+    // --- per sex and syndrome ---
+    required("{sex}_life_table_adjustment", 0.0, INF),
+    optional("syndrome_{syndrome}_initiation_multiplier", 0.0, INF, "no syndrome-specific multiplier"),
+
+    // --- drug use ---
+    required("drug_base_initiation_rate_per_day", 0.0, 1.0),
+    required("drug_infection_present_multiplier", 0.0, INF),
+    required("drug_test_identified_multiplier", 0.0, INF),
+    required("drug_decay_per_day", 0.0, INF), // legacy, superseded by the drug-specific half-lives
+    required("already_on_drug_initiation_multiplier", 0.0, INF),
+    required("double_dose_probability_if_identified_infection", 0.0, 1.0),
+    required("random_drug_cessation_probability", 0.0, 1.0),
+    required("empiric_therapy_broad_spectrum_bonus", 0.0, INF),
+    required("targeted_therapy_narrow_spectrum_bonus", 0.0, INF),
+    required("targeted_therapy_broad_spectrum_penalty", 0.0, INF),
+    required("targeted_therapy_ineffective_drug_penalty", 0.0, INF),
+    required("default_drug_toxicity_per_unit_level_per_day", 0.0, INF),
+
+    // --- resistance ---
+    required("environmental_majority_r_level_for_new_acquisition", 0.0, 1.0),
+    required("hospital_majority_r_level_for_new_acquisition", 0.0, 1.0),
+    required("max_resistance_level", 0.0, 1.0),
+    required("majority_r_evolution_rate_per_day_when_drug_present", 0.0, 1.0),
+    required("any_r_increase_rate_per_day_when_drug_present", 0.0, 1.0),
+    required("resistance_emergence_rate_per_day_baseline", 0.0, 1.0),
+    required("microbiome_resistance_emergence_rate_per_day_baseline", 0.0, 1.0),
+    required("resistance_emergence_bacteria_level_multiplier", 0.0, INF),
+    required("any_r_emergence_level_on_first_emergence", 0.0, 1.0),
+    required("microbiome_resistance_transfer_probability_per_day", 0.0, 1.0),
+
+    // --- immunity ---
+    required("immune_decay_rate_per_day", 0.0, INF),
+
+    // --- testing ---
+    required("test_delay_days", 0.0, INF),
+    required("test_rate_per_day", 0.0, 1.0),
+    required("prob_test_r_done", 0.0, 1.0),
+    required("test_r_error_probability", 0.0, 1.0),
+    required("test_r_error_value", 0.0, 1.0),
+
+    // --- hospitalisation and travel ---
+    required("hospitalization_baseline_rate_per_day", 0.0, 1.0),
+    required("hospitalization_age_multiplier_per_day", 0.0, 1.0),
+    required("hospitalization_recovery_rate_per_day", 0.0, 1.0),
+    required("hospitalization_max_days", 0.0, INF),
+    required("travel_probability_per_day", 0.0, 1.0),
+
+    // --- sepsis ---
+    required("default_sepsis_baseline_risk_per_day", 0.0, 1.0),
+    required("default_sepsis_level_multiplier", 0.0, INF),
+    required("default_sepsis_duration_multiplier", 0.0, INF),
+    required("high_sepsis_risk_multiplier", 0.0, INF),
+    required("moderate_sepsis_risk_multiplier", 0.0, INF),
+    required("low_sepsis_risk_multiplier", 0.0, INF),
+
+    // --- mortality ---
+    required("background_mortality_infection_fraction_infant", 0.0, 1.0),
+    required("background_mortality_infection_fraction_child", 0.0, 1.0),
+    required("background_mortality_infection_fraction_adult", 0.0, 1.0),
+    required("background_mortality_infection_fraction_elderly", 0.0, 1.0),
+    required("immunosuppressed_mortality_multiplier", 0.0, INF),
+    required("hospital_mortality_multiplier", 0.0, INF),
+    required("base_sepsis_death_risk_per_day", 0.0, 1.0),
+    required("sepsis_age_mortality_multiplier_infant", 0.0, INF),
+    required("sepsis_age_mortality_multiplier_child", 0.0, INF),
+    required("sepsis_age_mortality_multiplier_adult", 0.0, INF),
+    required("sepsis_age_mortality_multiplier_elderly", 0.0, INF),
+    required("sepsis_immunosuppressed_multiplier", 0.0, INF),
+    required("immunosuppression_onset_rate_per_day", 0.0, 1.0),
+    required("immunosuppression_recovery_rate_per_day", 0.0, 1.0),
+
+    // --- microbiome ---
+    required("default_microbiome_acquisition_multiplier", 0.0, INF),
+    required("default_microbiome_clearance_probability_per_day", 0.0, 1.0),
+    required("default_microbiome_infection_acquisition_multiplier", 0.0, INF),
+
+    // --- contact and exposure levels ---
+    required("contact_level_daily_fluctuation_range", 0.0, INF),
+    required("min_contact_level", 0.0, INF),
+    required("max_contact_level", 0.0, INF),
+    required("sexual_contact_baseline", 0.0, INF),
+    required("sexual_contact_age_peak_days", 0.0, INF),
+    required("sexual_contact_age_rise_exponent", 0.0, INF),
+    required("sexual_contact_age_decline_rate", 0.0, 1.0),
+    required("sexual_contact_hospital_multiplier", 0.0, INF),
+    required("airborne_contact_adult_baseline", 0.0, INF),
+    required("airborne_contact_adult_age_breakpoint_days", 0.0, INF),
+    required("airborne_contact_adult_child_multiplier", 0.0, INF),
+    required("airborne_contact_in_hospital_multiplier", 0.0, INF),
+    required("airborne_contact_child_baseline", 0.0, INF),
+    required("airborne_contact_child_age_breakpoint_days", 0.0, INF),
+    required("airborne_contact_child_child_multiplier", 0.0, INF),
+    required("airborne_contact_child_adult_multiplier", 0.0, INF),
+    required("oral_exposure_baseline", 0.0, INF),
+    required("oral_exposure_child_age_breakpoint_days", 0.0, INF),
+    required("oral_exposure_child_multiplier", 0.0, INF),
+    required("oral_exposure_in_hospital_multiplier", 0.0, INF),
+    required("mosquito_exposure_baseline", 0.0, INF),
+    required("mosquito_exposure_in_hospital_multiplier", 0.0, INF),
+];
+
+/// One problem found when validating a parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterIssue {
+    UnknownKey { key: String, suggestion: Option<String> },
+    MissingKey { key: String },
+    WrongType { key: String, expected: &'static str },
+    OutOfRange { key: String, value: f64, min: f64, max: f64 },
+    InvalidChoice { key: String, value: String, allowed: Vec<String> },
+}
+
+impl fmt::Display for ParameterIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterIssue::UnknownKey { key, suggestion: Some(suggestion) } => {
+                write!(f, "unknown key \"{}\" (did you mean \"{}\"?)", key, suggestion)
+            }
+            ParameterIssue::UnknownKey { key, suggestion: None } => write!(f, "unknown key \"{}\"", key),
+            ParameterIssue::MissingKey { key } => write!(f, "missing required key \"{}\"", key),
+            ParameterIssue::WrongType { key, expected } => write!(f, "\"{}\" must be {}", key, expected),
+            ParameterIssue::OutOfRange { key, value, min, max } => {
+                write!(f, "\"{}\" = {} is outside the allowed range [{}, {}]", key, value, min, max)
+            }
+            ParameterIssue::InvalidChoice { key, value, allowed } => {
+                write!(f, "\"{}\" = \"{}\" is not one of: {}", key, value, allowed.join(", "))
+            }
+        }
+    }
+}
+
+/// All the problems found in a parameter set, reported together.
+#[derive(Debug, Clone)]
+pub struct ParameterValidationError {
+    pub issues: Vec<ParameterIssue>,
+}
+
+impl fmt::Display for ParameterValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} problem(s) found in the model parameters:", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParameterValidationError {}
+
+/// The registry of all known parameter keys.
+#[derive(Debug, Clone)]
+pub struct ParameterSchema {
+    specs: HashMap<String, ParamSpec>,
+    keys: Vec<String>, // in declaration order, for listing
+}
+
+impl ParameterSchema {
+    /// The registry for the bacteria, drugs and regions of `catalog`, with their defaults.
+    pub fn for_catalog(catalog: &Catalog) -> Self {
+        let (numbers, strings) = (default_parameters(catalog), default_string_parameters(catalog));
+        let (bacteria, drugs, regions) = (catalog.bacteria_names(), catalog.drug_names(), catalog.region_names());
+        ParameterSchema::for_lists(&bacteria, &drugs, &regions, &numbers, &strings)
+    }
+
+    // The registry expanded over the given bacteria, drug and region names, with the given numeric and string
+    // defaults.
+    fn for_lists(
+        bacteria: &[&str],
+        drugs: &[&str],
+        regions: &[&str],
+        numbers: &HashMap<String, f64>,
+        strings: &HashMap<String, String>,
+    ) -> Self {
+        let bacteria_keys: Vec<String> = bacteria.iter().map(|b| b.replace(' ', "_")).collect();
+        let syndromes: Vec<String> = (1..=MAX_SYNDROME_ID).map(|s| s.to_string()).collect();
+
+        let mut schema = ParameterSchema { specs: HashMap::new(), keys: Vec::new() };
+        for family in FAMILIES {
+            let mut expanded = vec![family.template.to_string()];
+            expand(&mut expanded, "{bacteria}", "{bacteria_key}", bacteria, &bacteria_keys);
+            expand_one(&mut expanded, "{drug}", drugs);
+            expand_one(&mut expanded, "{region}", regions);
+            expand_one(&mut expanded, "{sex}", &["male", "female"]);
+            let syndrome_refs: Vec<&str> = syndromes.iter().map(|s| s.as_str()).collect();
+            expand_one(&mut expanded, "{syndrome}", &syndrome_refs);
+
+            for key in expanded {
+                let default = match family.kind {
+                    ParamKind::Number => numbers.get(&key).map(|&v| ParamDefault::Number(v)),
+                    ParamKind::AgeRiskTemplate | ParamKind::Date => {
+                        strings.get(&key).map(|v| ParamDefault::Text(v.clone()))
+                    }
+                };
+                if !schema.specs.contains_key(&key) {
+                    schema.keys.push(key.clone());
+                }
+                schema.specs.insert(key.clone(), ParamSpec {
+                    key,
+                    kind: family.kind,
+                    min: family.min,
+                    max: family.max,
+                    required: family.required,
+                    default,
+                    fallback: family.fallback,
+                });
+            }
+        }
+        schema
+    }
+
+    /// Whether `key` is a known parameter key.
+    pub fn knows(&self, key: &str) -> bool {
+        self.specs.contains_key(key)
+    }
+
+    /// All known keys, in declaration order.
+    pub fn iter(&self) -> impl Iterator<Item = &ParamSpec> {
+        self.keys.iter().map(move |k| &self.specs[k])
+    }
+
+    /// Writes one tab-separated line per known key: key, type, range, required/optional and default.
+    pub fn write_listing<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "key\ttype\tmin\tmax\trequired\tdefault")?;
+        for spec in self.iter() {
+            let kind = match spec.kind {
+                ParamKind::Number => "number",
+                ParamKind::AgeRiskTemplate => "age_risk_template",
+                ParamKind::Date => "date",
+            };
+            let default = match (&spec.default, spec.fallback) {
+                (Some(ParamDefault::Number(v)), _) => v.to_string(),
+                (Some(ParamDefault::Text(v)), _) => v.clone(),
+                (None, Some(fallback)) => format!("(falls back to {})", fallback),
+                (None, None) => String::new(),
+            };
+            let required = if spec.required { "required" } else { "optional" };
+            writeln!(out, "{}\t{}\t{}\t{}\t{}\t{}", spec.key, kind, spec.min, spec.max, required, default)?;
+        }
+        Ok(())
+    }
+
+    /// Checks every key and value in `set`, reporting all problems at once.
+    pub fn validate(&self, set: &ParameterSet) -> Result<(), ParameterValidationError> {
+        let mut issues = Vec::new();
+
+        let mut numeric_keys: Vec<&String> = set.values().keys().collect();
+        numeric_keys.sort();
+        for key in numeric_keys {
+            let value = set.values()[key];
+            match self.specs.get(key) {
+                None => issues.push(self.unknown(key)),
+                Some(spec) if spec.kind != ParamKind::Number => {
+                    issues.push(ParameterIssue::WrongType { key: key.clone(), expected: "a string" })
+                }
+                Some(spec) => {
+                    if value.is_nan() || value < spec.min || value > spec.max {
+                        issues.push(ParameterIssue::OutOfRange { key: key.clone(), value, min: spec.min, max: spec.max });
+                    }
+                }
+            }
+        }
+
+        let mut allowed_templates: Vec<String> = AGE_RISK_TEMPLATES.keys().map(|k| k.to_string()).collect();
+        allowed_templates.sort();
+        let mut string_keys: Vec<&String> = set.strings().keys().collect();
+        string_keys.sort();
+        for key in string_keys {
+            let value = &set.strings()[key];
+            match self.specs.get(key) {
+                None => issues.push(self.unknown(key)),
+                Some(spec) if spec.kind == ParamKind::Number => {
+                    issues.push(ParameterIssue::WrongType { key: key.clone(), expected: "a number" })
+                }
+                Some(spec) if spec.kind == ParamKind::Date => {
+                    if CalendarDate::parse(value).is_none() {
+                        issues.push(ParameterIssue::WrongType { key: key.clone(), expected: "a YYYY-MM-DD date" });
+                    }
+                }
+                Some(_) => {
+                    if !allowed_templates.contains(value) {
+                        issues.push(ParameterIssue::InvalidChoice {
+                            key: key.clone(),
+                            value: value.clone(),
+                            allowed: allowed_templates.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut scheduled_keys: Vec<&String> = set.schedules().keys().collect();
+        scheduled_keys.sort();
+        for key in scheduled_keys {
+            match self.specs.get(key) {
+                None => issues.push(self.unknown(key)),
+                Some(spec) if spec.kind != ParamKind::Number => {
+                    issues.push(ParameterIssue::WrongType { key: key.clone(), expected: "a string, not a schedule" })
+                }
+                Some(spec) => {
+                    for (date, value) in set.schedules()[key].points() {
+                        if value.is_nan() || value < spec.min || value > spec.max {
+                            issues.push(ParameterIssue::OutOfRange {
+                                key: format!("{} (at {})", key, date),
+                                value,
+                                min: spec.min,
+                                max: spec.max,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut distributed_keys: Vec<&String> = set.distributions().keys().collect();
+        distributed_keys.sort();
+        for key in distributed_keys {
+            match self.specs.get(key) {
+                None => issues.push(self.unknown(key)),
+                Some(spec) if spec.kind != ParamKind::Number => {
+                    issues.push(ParameterIssue::WrongType { key: key.clone(), expected: "a string, not a distribution" })
+                }
+                Some(spec) => {
+                    // the support has to lie within the range where it ends; an unbounded tail (a gamma's) is not
+                    // held against a finite maximum
+                    let (low, high) = set.distributions()[key].bounds();
+                    for value in [low, high].into_iter().filter(|value| !value.is_infinite()) {
+                        if value.is_nan() || value < spec.min || value > spec.max {
+                            issues.push(ParameterIssue::OutOfRange {
+                                key: format!("{} (distribution)", key),
+                                value,
+                                min: spec.min,
+                                max: spec.max,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for key in &self.keys {
+            let spec = &self.specs[key];
+            let present = match spec.kind {
+                ParamKind::Number => {
+                    set.get(key).is_some() || set.schedules().contains_key(key) || set.distributions().contains_key(key)
+                }
+                ParamKind::AgeRiskTemplate | ParamKind::Date => set.get_string(key).is_some(),
+            };
+            if spec.required && !present {
+                issues.push(ParameterIssue::MissingKey { key: key.clone() });
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ParameterValidationError { issues })
+        }
+    }
+
+    // An unknown-key issue, with the closest known key as a suggestion if it is a plausible typo.
+    fn unknown(&self, key: &str) -> ParameterIssue {
+        let suggestion = self
+            .keys
+            .iter()
+            .map(|known| (edit_distance(key, known), known))
+            .filter(|(distance, _)| *distance <= 3)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, known)| known.clone());
+        ParameterIssue::UnknownKey { key: key.to_string(), suggestion }
+    }
+}
+
+// Replaces `placeholder` (and its underscored twin) in every template with each of the given names.
+fn expand(templates: &mut Vec<String>, placeholder: &str, key_placeholder: &str, names: &[&str], keys: &[String]) {
+    if !templates.iter().any(|t| t.contains(placeholder) || t.contains(key_placeholder)) {
+        return;
+    }
+    let mut out = Vec::with_capacity(templates.len() * names.len());
+    for template in templates.iter() {
+        for (name, key) in names.iter().zip(keys) {
+            out.push(template.replace(placeholder, name).replace(key_placeholder, key));
+        }
+    }
+    *templates = out;
+}
+
+fn expand_one(templates: &mut Vec<String>, placeholder: &str, names: &[&str]) {
+    if !templates.iter().any(|t| t.contains(placeholder)) {
+        return;
+    }
+    let mut out = Vec::with_capacity(templates.len() * names.len());
+    for template in templates.iter() {
+        for name in names {
+            out.push(template.replace(placeholder, name));
+        }
+    }
+    *templates = out;
+}
+
+// Levenshtein distance, used to suggest the intended key for a typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::ParameterFileFormat;
+    use std::path::Path;
+
+    // The issues of the default set with `changes` (a TOML parameter file) applied.
+    fn issues_with(changes: &str) -> Vec<ParameterIssue> {
+        let catalog = Catalog::default_catalog();
+        let mut set = ParameterSet::defaults(&catalog);
+        set.apply_str(&catalog, changes, ParameterFileFormat::Toml, Path::new("test.toml")).unwrap();
+        ParameterSchema::for_catalog(&catalog).validate(&set).err().map_or(Vec::new(), |e| e.issues)
+    }
+
+    #[test]
+    fn the_defaults_are_valid() {
+        assert_eq!(issues_with(""), []);
+    }
+
+    #[test]
+    fn an_unknown_key_comes_with_its_likely_spelling() {
+        assert_eq!(
+            issues_with("drug_base_initiation_rate_per_dya = 0.001"),
+            [ParameterIssue::UnknownKey {
+                key: "drug_base_initiation_rate_per_dya".to_string(),
+                suggestion: Some("drug_base_initiation_rate_per_day".to_string()),
+            }]
+        );
+        assert_eq!(
+            issues_with("no_such_parameter_at_all = 1"),
+            [ParameterIssue::UnknownKey { key: "no_such_parameter_at_all".to_string(), suggestion: None }]
+        );
+    }
+
+    #[test]
+    fn a_value_of_the_wrong_type_is_reported() {
+        assert_eq!(
+            issues_with("simulation_start_date = 2000"),
+            [ParameterIssue::WrongType { key: "simulation_start_date".to_string(), expected: "a string" }]
+        );
+        assert_eq!(
+            issues_with("simulation_start_date = \"the first of january\""),
+            [ParameterIssue::WrongType { key: "simulation_start_date".to_string(), expected: "a YYYY-MM-DD date" }]
+        );
+    }
+
+    #[test]
+    fn a_value_outside_its_range_is_reported() {
+        assert_eq!(
+            issues_with("drug_base_initiation_rate_per_day = 1.5"),
+            [ParameterIssue::OutOfRange {
+                key: "drug_base_initiation_rate_per_day".to_string(),
+                value: 1.5,
+                min: 0.0,
+                max: 1.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn a_distribution_is_checked_by_the_finite_ends_of_its_support() {
+        // a gamma's unbounded tail is not held against the maximum of 1
+        let gamma = "drug_base_initiation_rate_per_day = { distribution = \"gamma\", shape = 2, scale = 0.001 }";
+        assert_eq!(issues_with(gamma), []);
+        assert_eq!(
+            issues_with("drug_base_initiation_rate_per_day = { distribution = \"uniform\", low = -0.5, high = 0.5 }"),
+            [ParameterIssue::OutOfRange {
+                key: "drug_base_initiation_rate_per_day (distribution)".to_string(),
+                value: -0.5,
+                min: 0.0,
+                max: 1.0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,500 @@
+// src/config/tables.rs
+//
+// Parameter values resolved into index-based tables for the per-individual update in rules/mod.rs.
+//
+// Looking parameters up by string key means building a `format!` key and hashing it, which inside the
+// bacteria x drug loops of `apply_rules` happens thousands of times per individual per day.  `ParameterTables`
+// does all of those lookups once, in the simulation's own parameter set (so several simulations with different
+// parameters can run in one process) and with the same fallbacks the rules used, and stores the results as typed
+// scalars, per-bacteria and per-drug vectors (indexed like the bacteria and drugs of the catalog), dense
+// [bacteria][drug] tables and per-region tables (indexed by `Region::index`, over the regions of the catalog: "home"
+// is looked up as the region lived in, see `Region::resolve`).
+//
+// The tables hold the values in force on one calendar day (see schedule.rs), so the simulation resolves
+// them once per run, or again on the days a calendar-time schedule moves to a new value.  The background death risks
+// come from the life tables of the day's calendar year (see demography.rs) and are brought to each new year's tables
+// with `set_background_mortality_year`.
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+use crate::config::catalog::{Catalog, MAX_SYNDROME_ID};
+use crate::config::demography::Demography;
+use crate::config::loader::ParameterSet;
+use crate::config::schedule::CalendarDate;
+use crate::config::{
+    age_risk_group, get_age_infection_multipliers, get_bacteria_sepsis_risk_multiplier, get_cross_resistance_groups,
+    get_drug_availability,
+};
+use crate::simulation::population::{Region, Sex};
+
+
+/// All parameters read by `apply_rules`, resolved for the current calendar day.
+#[derive(Debug, Clone)]
+pub struct ParameterTables {
+    // --- contact and exposure levels ---
+    pub contact_level_daily_fluctuation_range: f64,
+    pub min_contact_level: f64,
+    pub max_contact_level: f64,
+    pub sexual_contact_baseline: f64,
+    pub sexual_contact_age_peak_days: f64,
+    pub sexual_contact_age_rise_exponent: f64,
+    pub sexual_contact_age_decline_rate: f64,
+    pub sexual_contact_hospital_multiplier: f64,
+    pub airborne_contact_adult_baseline: f64,
+    pub airborne_contact_adult_age_breakpoint_days: f64,
+    pub airborne_contact_adult_child_multiplier: f64,
+    pub airborne_contact_in_hospital_multiplier: f64,
+    pub airborne_contact_child_baseline: f64,
+    pub airborne_contact_child_age_breakpoint_days: f64,
+    pub airborne_contact_child_child_multiplier: f64,
+    pub airborne_contact_child_adult_multiplier: f64,
+    pub oral_exposure_baseline: f64,
+    pub oral_exposure_child_age_breakpoint_days: f64,
+    pub oral_exposure_child_multiplier: f64,
+    pub oral_exposure_in_hospital_multiplier: f64,
+    pub mosquito_exposure_baseline: f64,
+    pub mosquito_exposure_in_hospital_multiplier: f64,
+    pub region_mosquito_exposure_multiplier: Vec<f64>, // [region]
+
+    // --- immunosuppression and hospitalisation ---
+    pub immunosuppression_onset_rate_per_day: f64,
+    pub immunosuppression_recovery_rate_per_day: f64,
+    pub hospitalization_baseline_rate_per_day: f64,
+    pub hospitalization_age_multiplier_per_day: f64,
+    pub hospitalization_recovery_rate_per_day: f64,
+    pub hospitalization_max_days: f64,
+
+    // --- travel ---
+    pub travel_probability_per_day: f64,
+    pub region_travel_multiplier: Vec<f64>, // [region]
+    travel_destinations: Vec<Option<WeightedIndex<f64>>>, // [region lived in], from the catalog; None: nowhere to go
+
+    // --- sepsis ---
+    pub sepsis_baseline_risk_per_day: Vec<f64>, // [bacteria]
+    pub sepsis_level_multiplier: Vec<f64>,      // [bacteria]
+    pub sepsis_duration_multiplier: Vec<f64>,   // [bacteria]
+    pub sepsis_risk_multiplier: Vec<f64>,       // [bacteria], from the bacteria's sepsis risk category
+
+    // --- infectious syndromes ---
+    syndromes: Vec<(Vec<u32>, WeightedIndex<f64>)>, // [bacteria], the syndrome ids of a new infection and their weights
+
+    // --- drug initiation and stopping ---
+    pub drug_base_initiation_rate_per_day: f64,
+    pub drug_infection_present_multiplier: f64,
+    pub already_on_drug_initiation_multiplier: f64,
+    pub drug_test_identified_multiplier: f64,
+    pub double_dose_probability_if_identified_infection: f64,
+    pub random_drug_cessation_probability: f64,
+    pub targeted_therapy_narrow_spectrum_bonus: f64,
+    pub targeted_therapy_broad_spectrum_penalty: f64,
+    pub targeted_therapy_ineffective_drug_penalty: f64,
+    pub empiric_therapy_broad_spectrum_bonus: f64,
+    syndrome_initiation_multiplier: Vec<Option<f64>>, // [syndrome id], None where no multiplier is set
+    drug_availability: Vec<Vec<f64>>,                 // [region][drug]
+    pub drug_restricted_to_identified_infection: Vec<bool>, // [drug], stewardship: only started once a bacteria is identified
+
+    // --- drug levels and toxicity ---
+    pub drug_initial_level: Vec<f64>,                 // [drug]
+    pub drug_daily_decay_factor: Vec<f64>,            // [drug], exp(-ln(2) / half-life in days)
+    pub drug_spectrum_breadth: Vec<f64>,              // [drug]
+    pub drug_double_dose_multiplier: Vec<f64>,        // [drug]
+    pub drug_toxicity_per_unit_level_per_day: Vec<f64>, // [drug]
+    pub drug_adverse_event_death_risk: Vec<f64>,      // [drug]
+
+    // --- drug x bacteria ---
+    pub potency_when_no_r: Vec<Vec<Option<f64>>>, // [bacteria][drug], None where not set (each use has its own fallback)
+    pub initiation_multiplier: Vec<Vec<Option<f64>>>, // [bacteria][drug], None where not set
+    pub resistance_emergence_rate_per_day_baseline: Vec<Vec<f64>>, // [bacteria][drug]
+
+    // --- mortality ---
+    pub background_mortality_year: i32, // calendar year of the life tables below
+    pub background_death_risk_by_age: Vec<[Vec<f64>; 2]>, // [region][sex][age in years], per day
+    pub background_mortality_infection_fraction_infant: f64,
+    pub background_mortality_infection_fraction_child: f64,
+    pub background_mortality_infection_fraction_adult: f64,
+    pub background_mortality_infection_fraction_elderly: f64,
+    pub region_life_table_adjustment: Vec<f64>, // [region], 1.0 = the life table as is
+    pub sex_life_table_adjustment: [f64; 2],    // [sex], likewise
+    pub immunosuppressed_mortality_multiplier: f64,
+    pub hospital_mortality_multiplier: f64,
+    pub base_sepsis_death_risk_per_day: f64,
+    pub sepsis_age_mortality_multiplier_infant: f64,
+    pub sepsis_age_mortality_multiplier_child: f64,
+    pub sepsis_age_mortality_multiplier_adult: f64,
+    pub sepsis_age_mortality_multiplier_elderly: f64,
+    pub region_sepsis_mortality_multiplier: Vec<f64>, // [region]
+    pub sepsis_immunosuppressed_multiplier: f64,
+
+    // --- acquisition and carriage, per bacteria ---
+    pub acquisition_prob_baseline: Vec<f64>,
+    pub sexual_contact_acq_rate_ratio_per_unit: Vec<f64>,
+    pub adult_contact_acq_rate_ratio_per_unit: Vec<f64>,
+    pub child_contact_acq_rate_ratio_per_unit: Vec<f64>,
+    pub oral_exposure_acq_rate_ratio_per_unit: Vec<f64>,
+    pub mosquito_exposure_acq_rate_ratio_per_unit: Vec<f64>,
+    pub vaccine_efficacy: Vec<f64>,
+    pub microbiome_infection_acquisition_multiplier: Vec<f64>,
+    pub hospital_acquired_multiplier: Vec<f64>,
+    age_infection_multipliers: Vec<[f64; 6]>, // [bacteria][age group]
+    pub region_infection_risk_multiplier: Vec<Vec<f64>>, // [region][bacteria]
+    pub microbiome_acquisition_multiplier: Vec<f64>,
+    pub microbiome_clearance_probability_per_day: Vec<f64>,
+    pub initial_infection_level: Vec<f64>,
+    pub environmental_acquisition_proportion: Vec<f64>,
+
+    // --- resistance ---
+    pub microbiome_resistance_transfer_probability_per_day: f64,
+    pub microbiome_resistance_emergence_rate_per_day_baseline: f64,
+    pub environmental_majority_r_level_for_new_acquisition: f64,
+    pub hospital_majority_r_level_for_new_acquisition: f64,
+    pub max_resistance_level: f64,
+    pub majority_r_evolution_rate_per_day_when_drug_present: f64,
+    pub any_r_increase_rate_per_day_when_drug_present: f64,
+    pub any_r_emergence_level_on_first_emergence: f64,
+    pub resistance_emergence_bacteria_level_multiplier: f64,
+    pub cross_resistance_groups: Vec<Vec<Vec<usize>>>, // [bacteria] -> groups of drug indices
+
+    // --- testing ---
+    pub test_delay_days: i32,
+    pub test_rate_per_day: f64,
+    pub prob_test_r_done: f64,
+    pub test_r_error_probability: f64,
+    pub test_r_error_value: f64,
+
+    // --- bacteria level and immunity, per bacteria ---
+    pub max_level: Vec<f64>,
+    pub base_bacteria_level_change: Vec<f64>,
+    pub immunity_effect_on_level_change: Vec<f64>,
+    pub immunity_base_response: Vec<f64>,
+    pub immunity_increase_per_infection_day: Vec<f64>,
+    pub immunity_increase_per_unit_higher_bacteria_level: Vec<f64>,
+    pub immunity_age_modifier: Vec<f64>,
+    pub immunity_immunodeficiency_modifier: Vec<f64>,
+    pub max_immune_response: Vec<f64>,
+    pub immune_decay_rate_per_day: f64,
+}
+
+impl ParameterTables {
+    /// Resolves every parameter from `set` as of day number `day` (see `CalendarDate::day_number`), over the
+    /// bacteria, drugs and regions of `catalog` and with the life tables of `demography`.
+    /// Panics if a parameter the rules require has no value, as the rules themselves did.
+    pub fn resolve(set: &ParameterSet, catalog: &Catalog, demography: &Demography, day: i64) -> Self {
+        let (bacteria_list, drug_short_names) = (catalog.bacteria_names(), catalog.drug_names());
+        let get_global_param = |key: &str| set.get_at(key, day);
+        let get_bacteria_param = |bacteria: &str, suffix: &str| get_global_param(&format!("{}_{}", bacteria, suffix));
+        let get_drug_param = |drug: &str, suffix: &str| get_global_param(&format!("drug_{}_{}", drug, suffix));
+        let global = |key: &str, fallback: f64| get_global_param(key).unwrap_or(fallback);
+        let required = |key: &str| get_global_param(key).unwrap_or_else(|| panic!("Missing {} in config", key));
+        let per_bacteria = |suffix: &str, fallback: f64| -> Vec<f64> {
+            bacteria_list.iter().map(|b| get_bacteria_param(b, suffix).unwrap_or(fallback)).collect()
+        };
+        // per-bacteria values that fall back to a required "default_..." global parameter
+        let per_bacteria_or_default = |suffix: &str| -> Vec<f64> {
+            bacteria_list
+                .iter()
+                .map(|b| get_bacteria_param(b, suffix).unwrap_or_else(|| required(&format!("default_{}", suffix))))
+                .collect()
+        };
+        let per_drug = |suffix: &str, fallback: f64| -> Vec<f64> {
+            drug_short_names.iter().map(|d| get_drug_param(d, suffix).unwrap_or(fallback)).collect()
+        };
+        let per_region = |suffix: &str| -> Vec<f64> {
+            Region::all(catalog).map(|r| global(&format!("{}_{}", r.name(catalog), suffix), 1.0)).collect()
+        };
+        let per_pair = |suffix: &str| -> Vec<Vec<Option<f64>>> {
+            bacteria_list
+                .iter()
+                .map(|b| {
+                    drug_short_names
+                        .iter()
+                        .map(|d| get_global_param(&format!("drug_{}_for_bacteria_{}_{}", d, b, suffix)))
+                        .collect()
+                })
+                .collect()
+        };
+
+        let cross_resistance_groups = bacteria_list
+            .iter()
+            .map(|&b| match get_cross_resistance_groups().get(b) {
+                Some(groups) => groups
+                    .iter()
+                    .map(|group| {
+                        group.iter().filter_map(|d| drug_short_names.iter().position(|name| name == d)).collect()
+                    })
+                    .collect(),
+                None => Vec::new(),
+            })
+            .collect();
+
+        let year = CalendarDate::from_day_number(day).year;
+        let background_mortality_infection_fraction = ["infant", "child", "adult", "elderly"]
+            .map(|group| global(&format!("background_mortality_infection_fraction_{}", group), 0.0));
+
+        ParameterTables {
+            contact_level_daily_fluctuation_range: global("contact_level_daily_fluctuation_range", 0.5),
+            min_contact_level: global("min_contact_level", 0.0),
+            max_contact_level: global("max_contact_level", 10.0),
+            sexual_contact_baseline: global("sexual_contact_baseline", 5.0),
+            sexual_contact_age_peak_days: global("sexual_contact_age_peak_days", 25.0 * 365.0),
+            sexual_contact_age_rise_exponent: global("sexual_contact_age_rise_exponent", 2.0),
+            sexual_contact_age_decline_rate: global("sexual_contact_age_decline_rate", 0.00005),
+            sexual_contact_hospital_multiplier: global("sexual_contact_hospital_multiplier", 0.0),
+            airborne_contact_adult_baseline: global("airborne_contact_adult_baseline", 5.0),
+            airborne_contact_adult_age_breakpoint_days: global("airborne_contact_adult_age_breakpoint_days", 18.0 * 365.0),
+            airborne_contact_adult_child_multiplier: global("airborne_contact_adult_child_multiplier", 0.2),
+            airborne_contact_in_hospital_multiplier: global("airborne_contact_in_hospital_multiplier", 1.5),
+            airborne_contact_child_baseline: global("airborne_contact_child_baseline", 3.0),
+            airborne_contact_child_age_breakpoint_days: global("airborne_contact_child_age_breakpoint_days", 12.0 * 365.0),
+            airborne_contact_child_child_multiplier: global("airborne_contact_child_child_multiplier", 1.5),
+            airborne_contact_child_adult_multiplier: global("airborne_contact_child_adult_multiplier", 0.5),
+            oral_exposure_baseline: global("oral_exposure_baseline", 2.0),
+            oral_exposure_child_age_breakpoint_days: global("oral_exposure_child_age_breakpoint_days", 5.0 * 365.0),
+            oral_exposure_child_multiplier: global("oral_exposure_child_multiplier", 3.0),
+            oral_exposure_in_hospital_multiplier: global("oral_exposure_in_hospital_multiplier", 0.8),
+            mosquito_exposure_baseline: global("mosquito_exposure_baseline", 1.0),
+            mosquito_exposure_in_hospital_multiplier: global("mosquito_exposure_in_hospital_multiplier", 0.2),
+            region_mosquito_exposure_multiplier: per_region("mosquito_exposure_multiplier"),
+
+            immunosuppression_onset_rate_per_day: global("immunosuppression_onset_rate_per_day", 0.0001),
+            immunosuppression_recovery_rate_per_day: global("immunosuppression_recovery_rate_per_day", 0.0005),
+            hospitalization_baseline_rate_per_day: required("hospitalization_baseline_rate_per_day"),
+            hospitalization_age_multiplier_per_day: required("hospitalization_age_multiplier_per_day"),
+            hospitalization_recovery_rate_per_day: required("hospitalization_recovery_rate_per_day"),
+            hospitalization_max_days: required("hospitalization_max_days"),
+
+            travel_probability_per_day: required("travel_probability_per_day"),
+            region_travel_multiplier: per_region("travel_multiplier"),
+            travel_destinations: Region::all(catalog)
+                .map(|from| WeightedIndex::new(catalog.travel_weights(from.index())).ok())
+                .collect(),
+
+            sepsis_baseline_risk_per_day: per_bacteria_or_default("sepsis_baseline_risk_per_day"),
+            sepsis_level_multiplier: per_bacteria_or_default("sepsis_level_multiplier"),
+            sepsis_duration_multiplier: per_bacteria_or_default("sepsis_duration_multiplier"),
+            sepsis_risk_multiplier: bacteria_list
+                .iter()
+                .map(|b| get_bacteria_sepsis_risk_multiplier(catalog, set, day, b))
+                .collect(),
+
+            syndromes: catalog
+                .bacteria
+                .iter()
+                .map(|b| {
+                    let ids = b.syndromes.iter().map(|&(id, _)| id).collect();
+                    let weights = b.syndromes.iter().map(|&(_, p)| p);
+                    (ids, WeightedIndex::new(weights).expect("the catalog checks the syndrome probabilities"))
+                })
+                .collect(),
+
+            drug_base_initiation_rate_per_day: global("drug_base_initiation_rate_per_day", 0.0001),
+            drug_infection_present_multiplier: global("drug_infection_present_multiplier", 50.0),
+            already_on_drug_initiation_multiplier: global("already_on_drug_initiation_multiplier", 0.0001),
+            drug_test_identified_multiplier: global("drug_test_identified_multiplier", 20.0),
+            double_dose_probability_if_identified_infection: global("double_dose_probability_if_identified_infection", 0.1),
+            random_drug_cessation_probability: global("random_drug_cessation_probability", 0.001),
+            targeted_therapy_narrow_spectrum_bonus: global("targeted_therapy_narrow_spectrum_bonus", 3.0),
+            targeted_therapy_broad_spectrum_penalty: global("targeted_therapy_broad_spectrum_penalty", 0.4),
+            targeted_therapy_ineffective_drug_penalty: global("targeted_therapy_ineffective_drug_penalty", 0.1),
+            empiric_therapy_broad_spectrum_bonus: global("empiric_therapy_broad_spectrum_bonus", 2.0),
+            syndrome_initiation_multiplier: (0..=MAX_SYNDROME_ID as usize)
+                .map(|id| get_global_param(&format!("syndrome_{}_initiation_multiplier", id)))
+                .collect(),
+            drug_availability: Region::all(catalog)
+                .map(|region| {
+                    let region = region.name(catalog);
+                    drug_short_names.iter().map(|d| get_drug_availability(set, day, d, region)).collect()
+                })
+                .collect(),
+
+            drug_initial_level: per_drug("initial_level", 10.0),
+            drug_daily_decay_factor: per_drug("half_life_days", 0.25)
+                .into_iter()
+                .map(|half_life_days| {
+                    let decay_constant = (2.0_f64).ln() / half_life_days; // k = ln(2) / t_half
+                    (-decay_constant).exp() // e^(-k*t) where t=1 day
+                })
+                .collect(),
+            drug_restricted_to_identified_infection: per_drug("restricted_to_identified_infection", 0.0)
+                .into_iter()
+                .map(|restricted| restricted > 0.5)
+                .collect(),
+            drug_spectrum_breadth: per_drug("spectrum_breadth", 3.0),
+            drug_double_dose_multiplier: per_drug("double_dose_multiplier", 2.0),
+            drug_toxicity_per_unit_level_per_day: drug_short_names
+                .iter()
+                .map(|d| {
+                    get_drug_param(d, "toxicity_per_unit_level_per_day")
+                        .unwrap_or_else(|| required("default_drug_toxicity_per_unit_level_per_day"))
+                })
+                .collect(),
+            drug_adverse_event_death_risk: per_drug("adverse_event_death_risk", 0.0),
+
+            potency_when_no_r: per_pair("potency_when_no_r"),
+            initiation_multiplier: per_pair("initiation_multiplier"),
+            resistance_emergence_rate_per_day_baseline: per_pair("resistance_emergence_rate_per_day_baseline")
+                .into_iter()
+                .map(|rates| rates.into_iter().map(|rate| rate.unwrap_or(0.000001)).collect())
+                .collect(),
+
+            background_mortality_year: year,
+            background_death_risk_by_age: background_death_risks(
+                catalog,
+                demography,
+                year,
+                background_mortality_infection_fraction,
+            ),
+            background_mortality_infection_fraction_infant: background_mortality_infection_fraction[0],
+            background_mortality_infection_fraction_child: background_mortality_infection_fraction[1],
+            background_mortality_infection_fraction_adult: background_mortality_infection_fraction[2],
+            background_mortality_infection_fraction_elderly: background_mortality_infection_fraction[3],
+            region_life_table_adjustment: per_region("life_table_adjustment"),
+            sex_life_table_adjustment: [Sex::Male, Sex::Female].map(|sex| global(&format!("{}_life_table_adjustment", sex), 1.0)),
+            immunosuppressed_mortality_multiplier: global("immunosuppressed_mortality_multiplier", 1.0),
+            hospital_mortality_multiplier: global("hospital_mortality_multiplier", 1.0),
+            base_sepsis_death_risk_per_day: required("base_sepsis_death_risk_per_day"),
+            sepsis_age_mortality_multiplier_infant: global("sepsis_age_mortality_multiplier_infant", 3.0),
+            sepsis_age_mortality_multiplier_child: global("sepsis_age_mortality_multiplier_child", 0.5),
+            sepsis_age_mortality_multiplier_adult: global("sepsis_age_mortality_multiplier_adult", 1.0),
+            sepsis_age_mortality_multiplier_elderly: global("sepsis_age_mortality_multiplier_elderly", 2.5),
+            region_sepsis_mortality_multiplier: per_region("sepsis_mortality_multiplier"),
+            sepsis_immunosuppressed_multiplier: global("sepsis_immunosuppressed_multiplier", 3.0),
+
+            acquisition_prob_baseline: per_bacteria("acquisition_prob_baseline", 0.01),
+            sexual_contact_acq_rate_ratio_per_unit: per_bacteria("sexual_contact_acq_rate_ratio_per_unit", 1.0),
+            adult_contact_acq_rate_ratio_per_unit: per_bacteria("adult_contact_acq_rate_ratio_per_unit", 1.0),
+            child_contact_acq_rate_ratio_per_unit: per_bacteria("child_contact_acq_rate_ratio_per_unit", 1.0),
+            oral_exposure_acq_rate_ratio_per_unit: per_bacteria("oral_exposure_acq_rate_ratio_per_unit", 1.0),
+            mosquito_exposure_acq_rate_ratio_per_unit: per_bacteria("mosquito_exposure_acq_rate_ratio_per_unit", 1.0),
+            vaccine_efficacy: per_bacteria("vaccine_efficacy", 0.0),
+            microbiome_infection_acquisition_multiplier: per_bacteria_or_default("microbiome_infection_acquisition_multiplier"),
+            hospital_acquired_multiplier: per_bacteria("hospital_acquired_multiplier", 1.0),
+            age_infection_multipliers: bacteria_list.iter().map(|b| get_age_infection_multipliers(set, day, b)).collect(),
+            region_infection_risk_multiplier: Region::all(catalog)
+                .map(|region| {
+                    let region = region.name(catalog);
+                    bacteria_list
+                        .iter()
+                        .map(|b| {
+                            // the region-bacteria combination if given, else the region default, else 1.0
+                            get_global_param(&format!("{}_{}_infection_risk_multiplier", region, b.replace(' ', "_")))
+                                .unwrap_or_else(|| global(&format!("{}_infection_risk_multiplier_default", region), 1.0))
+                        })
+                        .collect()
+                })
+                .collect(),
+            microbiome_acquisition_multiplier: per_bacteria_or_default("microbiome_acquisition_multiplier"),
+            microbiome_clearance_probability_per_day: per_bacteria_or_default("microbiome_clearance_probability_per_day"),
+            initial_infection_level: per_bacteria("initial_infection_level", 0.01),
+            environmental_acquisition_proportion: per_bacteria("environmental_acquisition_proportion", 0.1),
+
+            microbiome_resistance_transfer_probability_per_day: global("microbiome_resistance_transfer_probability_per_day", 0.05),
+            microbiome_resistance_emergence_rate_per_day_baseline: get_global_param("microbiome_resistance_emergence_rate_per_day_baseline")
+                .or_else(|| get_global_param("resistance_emergence_rate_per_day_baseline"))
+                .unwrap_or(0.000001),
+            environmental_majority_r_level_for_new_acquisition: global("environmental_majority_r_level_for_new_acquisition", 0.0),
+            hospital_majority_r_level_for_new_acquisition: global("hospital_majority_r_level_for_new_acquisition", 0.0),
+            max_resistance_level: global("max_resistance_level", 1.0),
+            majority_r_evolution_rate_per_day_when_drug_present: global("majority_r_evolution_rate_per_day_when_drug_present", 0.0),
+            any_r_increase_rate_per_day_when_drug_present: global("any_r_increase_rate_per_day_when_drug_present", 0.05),
+            any_r_emergence_level_on_first_emergence: global("any_r_emergence_level_on_first_emergence", 0.5),
+            resistance_emergence_bacteria_level_multiplier: global("resistance_emergence_bacteria_level_multiplier", 0.05),
+            cross_resistance_groups,
+
+            test_delay_days: global("test_delay_days", 3.0) as i32,
+            test_rate_per_day: global("test_rate_per_day", 0.15),
+            prob_test_r_done: global("prob_test_r_done", 0.95),
+            test_r_error_probability: global("test_r_error_probability", 0.02),
+            test_r_error_value: global("test_r_error_value", 0.25),
+
+            max_level: per_bacteria("max_level", 100.0),
+            base_bacteria_level_change: per_bacteria("base_bacteria_level_change", 0.0),
+            immunity_effect_on_level_change: per_bacteria("immunity_effect_on_level_change", 0.0),
+            immunity_base_response: per_bacteria("immunity_base_response", 0.0),
+            immunity_increase_per_infection_day: per_bacteria("immunity_increase_per_infection_day", 0.0),
+            immunity_increase_per_unit_higher_bacteria_level: per_bacteria("immunity_increase_per_unit_higher_bacteria_level", 0.0),
+            immunity_age_modifier: per_bacteria("immunity_age_modifier", 1.0),
+            immunity_immunodeficiency_modifier: per_bacteria("immunity_immunodeficiency_modifier", 0.1),
+            max_immune_response: per_bacteria("max_immune_response", 10.0),
+            immune_decay_rate_per_day: global("immune_decay_rate_per_day", 0.02),
+        }
+    }
+
+    /// The initiation multiplier for an infectious syndrome, if one is set.
+    pub fn syndrome_initiation_multiplier(&self, syndrome_id: i32) -> Option<f64> {
+        usize::try_from(syndrome_id).ok().and_then(|id| self.syndrome_initiation_multiplier.get(id).copied().flatten())
+    }
+
+    /// The age-based infection risk multiplier of a bacteria at an age in days.
+    pub fn age_infection_multiplier(&self, b_idx: usize, age_days: i32) -> f64 {
+        self.age_infection_multipliers[b_idx][age_risk_group(age_days)]
+    }
+
+    /// Brings the background death risks to the life tables of `year` in `demography`, when a run enters a new
+    /// calendar year.
+    pub fn set_background_mortality_year(&mut self, catalog: &Catalog, demography: &Demography, year: i32) {
+        let infection_fraction = [
+            self.background_mortality_infection_fraction_infant,
+            self.background_mortality_infection_fraction_child,
+            self.background_mortality_infection_fraction_adult,
+            self.background_mortality_infection_fraction_elderly,
+        ];
+        self.background_death_risk_by_age = background_death_risks(catalog, demography, year, infection_fraction);
+        self.background_mortality_year = year;
+    }
+
+    /// The daily background death risk from the life tables of someone of a sex at birth and age in days (not
+    /// negative) living in `region`.  Ages beyond the last of the life tables take its risk.
+    pub fn background_death_risk_per_day(&self, region: Region, sex_at_birth: Sex, age_days: i32) -> f64 {
+        let by_age = &self.background_death_risk_by_age[region.index()][sex_at_birth as usize];
+        by_age[((age_days.max(0) / 365) as usize).min(by_age.len() - 1)]
+    }
+
+    /// Where someone living in `region_living` goes on a visit, drawn by the travel weights of the catalog; None if
+    /// the region has no travel to anywhere (e.g. the only region).
+    pub fn travel_destination<R: Rng + ?Sized>(&self, region_living: Region, rng: &mut R) -> Option<Region> {
+        let destinations = self.travel_destinations[region_living.index()].as_ref()?;
+        Some(Region::from_index(destinations.sample(rng)))
+    }
+
+    /// The infectious syndrome of a new infection with a bacteria, drawn by the syndrome probabilities of the
+    /// catalog.
+    pub fn draw_syndrome<R: Rng + ?Sized>(&self, b_idx: usize, rng: &mut R) -> u32 {
+        let (ids, weights) = &self.syndromes[b_idx];
+        ids[weights.sample(rng)]
+    }
+
+    /// How available a drug is in a geographic region (for someone at home, the region lived in; see
+    /// `IndividualMut::region_present`).
+    pub fn drug_availability(&self, d_idx: usize, region: Region) -> f64 {
+        self.drug_availability[region.index()][d_idx]
+    }
+}
+
+// The daily background death risks of each region in `year`, by sex and age in years, from the life tables less the
+// share of deaths attributable to infection, which the model simulates itself (by the age groups of the sepsis
+// mortality multipliers: under 1, under 18, under 65 and older).
+fn background_death_risks(
+    catalog: &Catalog,
+    demography: &Demography,
+    year: i32,
+    infection_fraction: [f64; 4],
+) -> Vec<[Vec<f64>; 2]> {
+    Region::all(catalog)
+        .map(|region| {
+            let mut risks = demography.daily_death_probabilities(catalog, region.name(catalog), year as f64);
+            for by_age in risks.iter_mut() {
+                for (age, risk) in by_age.iter_mut().enumerate() {
+                    let group = match age {
+                        0 => 0,
+                        1..=17 => 1,
+                        18..=64 => 2,
+                        _ => 3,
+                    };
+                    *risk *= 1.0 - infection_fraction[group];
+                }
+            }
+            risks
+        })
+        .collect()
+}
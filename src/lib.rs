@@ -0,0 +1,47 @@
+// src/lib.rs
+//
+// The AMR model as a library.  The executable (main.rs) is one user of it; other tools can embed the model and
+// drive it through `Simulation::builder()` (see simulation/builder.rs):
+//
+//     use amr_project::{EndOfRunSummary, Simulation};
+//
+//     let mut simulation = Simulation::builder()
+//         .population_size(10_000)
+//         .days(365)
+//         .seed(1)
+//         .output(EndOfRunSummary::new(std::io::stdout()))
+//         .build()?;
+//     simulation.run()?;
+//
+// config holds the parameters, catalog, demographic data and parameter registry, simulation the population and the
+// time loop, rules the per-individual update applied at each time step, calibration the comparison of runs with
+// observed data, and analysis the study of many runs at once.
+
+pub mod analysis;
+pub mod calibration;
+pub mod config;
+pub mod rules;
+pub mod simulation;
+
+pub use analysis::metrics::{MetricsCollector, OutputMetric};
+pub use analysis::replicates::ReplicateSummary;
+pub use analysis::sensitivity::{SensitivityAnalysis, SensitivityIndex, SensitivityMethod, SensitivityResult};
+pub use calibration::abc::{AbcFit, AbcMethod, AbcProgress, Particle, Posterior};
+pub use calibration::fit::{CalibrationReport, CalibrationWriter, TargetFit};
+pub use calibration::priors::{load_priors, Prior};
+pub use calibration::targets::{load_targets, CalibrationError, Likelihood, Target, TargetKind};
+pub use config::catalog::Catalog;
+pub use config::demography::Demography;
+pub use config::distribution::ParameterDistribution;
+pub use config::loader::ParameterSet;
+pub use config::schedule::CalendarDate;
+pub use config::schema::ParameterSchema;
+pub use simulation::builder::{BuildError, RunLength, SimulationBuilder};
+pub use simulation::events::{Event, EventKind, EventLogWriter, EventRecord};
+pub use simulation::output::OutputSink;
+pub use simulation::population::{Individual, IndividualMut, Population};
+pub use simulation::simulation::Simulation;
+pub use simulation::snapshot::{SnapshotError, SnapshotWriter};
+pub use simulation::summary::EndOfRunSummary;
+pub use simulation::timeseries::{TimeSeriesHandle, TimeSeriesWriter};
+pub use simulation::trace::{CohortTracer, TraceCondition, TraceFilter};
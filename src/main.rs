@@ -1,17 +1,13 @@
 // src/main.rs
 
-mod simulation;
-mod rules;
-mod config;
-
 //
 //
 // run model for the world to get idea of parameter values for rate of first appearance of resistance
 //
 // decide on time zero for mda azithromycin project
 //
-// work on initial age distribution to reflect start year and end year and population growth - decide on start and end year
-// for azithromycin mda project
+// decide on start and end year for azithromycin mda project (the initial age distribution follows the start year's
+// pyramids and births the fertility of the years run, see config/demography.rs)
 //
 // for mda project can base in africa with an "other" region all groued together
 //
@@ -30,299 +26,593 @@ mod config;
 
 
 
-use crate::simulation::simulation::Simulation;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use amr_project::calibration::fit::ReportHandle;
+use amr_project::config::catalog::Catalog;
+use amr_project::config::demography::Demography;
+use amr_project::config::schema::ParameterSchema;
+use amr_project::simulation::rng::random_master_seed;
+use amr_project::simulation::timeseries::DEFAULT_AGE_BANDS;
+use amr_project::{
+    load_priors, load_targets, AbcFit, AbcMethod, CalendarDate, CalibrationWriter, CohortTracer, EndOfRunSummary,
+    EventKind, EventLogWriter, OutputMetric, ParameterSet, ReplicateSummary, RunLength, SensitivityAnalysis,
+    SensitivityMethod, Simulation, SimulationBuilder, SnapshotWriter, TimeSeriesHandle, TimeSeriesWriter, TraceFilter,
+};
+
+/// Agent-based model of bacterial infection, antibiotic use and resistance.
+#[derive(Parser)]
+#[command(name = "executable_amr")]
+struct Cli {
+    /// Bacteria, drug and region catalog to use instead of the compiled-in one (see catalogs/default.toml)
+    #[arg(long, global = true, value_name = "FILE")]
+    catalog: Option<PathBuf>,
+
+    /// Directory of demographic data (population.csv, indicators.csv, life_table.csv in UN WPP columns) to use
+    /// instead of the compiled-in data (see demography/)
+    #[arg(long, global = true, value_name = "DIR")]
+    demography: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
 
-fn main() {
-    // Create and run the simulation
-    let population_size =    1_000_000 ;
-    let time_steps =  10  ;  // Reduced for testing immune response changes
+#[derive(Subcommand)]
+enum Command {
+    /// Run a simulation
+    Run(Box<RunArgs>),
+    /// Fit parameters to calibration targets by approximate Bayesian computation, writing a posterior sample
+    Fit(Box<FitArgs>),
+    /// Run the model over a design of parameter values and report how much each parameter moves chosen metrics
+    Sensitivity(Box<SensitivityArgs>),
+    /// Print the effective parameters (defaults plus parameter files and --set values) as a parameter file
+    Params(ParamsArgs),
+    /// Print every known parameter key with its type, range and default
+    ListParameters,
+    /// Print the bacteria, drug and region catalog in use
+    ListCatalog,
+}
 
-    let mut simulation = Simulation::new(population_size, time_steps);
+/// The parameter settings shared by `run` and `params`.
+#[derive(Args)]
+struct ScenarioArgs {
+    /// Parameter override files (.toml or .json), applied in order on top of the defaults
+    #[arg(value_name = "PARAMETER_FILE")]
+    parameter_files: Vec<PathBuf>,
 
-    let ind0 = &simulation.population.individuals[0];
-    
-    // print variable values at time step 0, before starting to go through the time steps
+    /// Set a numeric parameter after the parameter files (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    values: Vec<(String, f64)>,
 
-    println!("  ");
-    println!("main.rs  variable values at time step 0, before starting to go through the time steps");
-    println!("  ");
+    /// Calendar date of time step 0, YYYY-MM-DD (default: the simulation_start_date parameter)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    start_date: Option<CalendarDate>,
+}
 
-    for (bacteria, &b_idx) in simulation.bacteria_indices.iter() {
-        println!("{}_vaccination_status: {}", bacteria, ind0.vaccination_status[b_idx]);
-    }
+#[derive(Args)]
+struct RunArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+
+    /// Number of individuals alive at the start (those born during the run are added to them)
+    #[arg(long, default_value_t = 1_000_000)]
+    population: usize,
+
+    /// Continue the simulation saved in this snapshot (population, time step, seed and parameters) instead of
+    /// starting a new one; parameter files and --set values are applied on top of its parameters
+    #[arg(long, value_name = "SNAPSHOT", conflicts_with_all = ["population", "start_date"])]
+    resume: Option<PathBuf>,
+
+    /// Save a snapshot of the simulation to this file at the end of the run
+    #[arg(long, value_name = "FILE")]
+    snapshot: Option<PathBuf>,
+
+    /// Also save the snapshot every this many days, overwriting the previous one
+    #[arg(long, value_name = "DAYS", requires = "snapshot")]
+    checkpoint_every: Option<usize>,
+
+    /// Number of days (time steps) to run (after the snapshot's time step with --resume)
+    #[arg(long, default_value_t = 10, conflicts_with = "until")]
+    days: usize,
+
+    /// Run up to and including this date, YYYY-MM-DD, instead of a number of days
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<CalendarDate>,
+
+    /// Remove the dead from the population every this many days, so that long runs with births keep only the
+    /// living (their deaths stay in summary.txt; later snapshots no longer hold them)
+    #[arg(long, value_name = "DAYS")]
+    compact_every: Option<usize>,
+
+    /// Master random seed; the same seed (and settings) repeats a run exactly.  Drawn at random if not given
+    /// (with --resume: the snapshot's seed)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Write the outputs (run.toml, parameters.toml, summary.txt, timeseries.csv and, with --event-log, events.csv,
+    /// with a --trace option, trace.jsonl, with --calibration-targets, calibration.csv) to this directory instead of
+    /// the console
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Days between the rows of timeseries.csv
+    #[arg(long, value_name = "DAYS", default_value_t = 1, requires = "output_dir")]
+    report_every: usize,
+
+    /// Age bands of timeseries.csv, as the lower limit of each band in years
+    #[arg(long, value_name = "YEARS", value_delimiter = ',', default_values_t = DEFAULT_AGE_BANDS, requires = "output_dir")]
+    age_bands: Vec<u32>,
+
+    /// Also write a line list of individual-level events (infections, drug courses, tests, deaths, ...) to
+    /// events.csv in the output directory
+    #[arg(long, requires = "output_dir")]
+    event_log: bool,
+
+    /// Only log events of these kinds, e.g. infection_acquired,died (default: all)
+    #[arg(long, value_name = "KINDS", value_delimiter = ',', value_parser = parse_event_kind, requires = "event_log")]
+    event_types: Vec<EventKind>,
+
+    /// Only log events of the individuals with these ids (default: all)
+    #[arg(long, value_name = "IDS", value_delimiter = ',', requires = "event_log")]
+    event_individuals: Vec<usize>,
+
+    /// Trace the full daily state of the individuals with these ids to trace.jsonl in the output directory
+    #[arg(long, value_name = "IDS", value_delimiter = ',', requires = "output_dir")]
+    trace_ids: Vec<usize>,
+
+    /// Trace a random sample of this many individuals (the same ones for the same seed)
+    #[arg(long, value_name = "N", requires = "output_dir")]
+    trace_sample: Option<usize>,
+
+    /// Trace everyone meeting these conditions, from the day they first do, e.g.
+    /// "infected(klebsiella pneumoniae) and on_drug(meropenem)" (see src/simulation/trace.rs)
+    #[arg(long, value_name = "CONDITIONS", requires = "output_dir")]
+    trace_where: Option<String>,
+
+    /// Compare the run with the observed values in these CSV files (see src/calibration/targets.rs), writing the
+    /// fit to each target to calibration.csv in the output directory
+    #[arg(long, value_name = "FILES", value_delimiter = ',', requires = "output_dir")]
+    calibration_targets: Vec<PathBuf>,
+
+    /// Run this many replicates, each with its own seed derived from --seed, and write the mean, median and 2.5 and
+    /// 97.5 percentiles of every time-series count over them to timeseries_summary.csv in the output directory
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "output_dir", conflicts_with = "snapshot")]
+    replicates: usize,
+
+    /// Also keep the outputs of each replicate, in replicate_001/, replicate_002/, ... in the output directory
+    #[arg(long, requires = "replicates")]
+    keep_replicates: bool,
+
+    /// Number of worker threads (default: one per CPU); results do not depend on it
+    #[arg(long)]
+    threads: Option<usize>,
+}
 
-    println!("background_all_cause_mortality_rate: {:.4}", ind0.background_all_cause_mortality_rate);
-    println!("sexual_contact_level: {:.2}", ind0.sexual_contact_level);
-    println!("airborne_contact_level_with_adults: {:.2}", ind0.airborne_contact_level_with_adults);
-    println!("airborne_contact_level_with_children: {:.2}", ind0.airborne_contact_level_with_children);
-    println!("oral_exposure_level: {:.2}", ind0.oral_exposure_level);
-    println!("mosquito_exposure_level: {:.2}", ind0.mosquito_exposure_level);
-    println!("current_toxicity: {:.2}", ind0.current_toxicity);
-    println!("mortality_risk_current_toxicity: {:.2}", ind0.mortality_risk_current_toxicity);
+#[derive(Args)]
+struct FitArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
 
-    use std::time::Instant;
-    let start = Instant::now();
+    /// The parameters to fit and their prior distributions (.toml or .json; see src/calibration/priors.rs)
+    #[arg(long, value_name = "FILE")]
+    priors: PathBuf,
 
-    simulation.run();
+    /// The observed values to fit to, as CSV files (see src/calibration/targets.rs)
+    #[arg(long, value_name = "FILES", value_delimiter = ',', required = true)]
+    targets: Vec<PathBuf>,
 
-    let duration = start.elapsed();
-    println!("main.rs  final outputs ");
-
-    // --- DEATH REPORTING START ---
-    let mut total_deaths = 0;
-    let mut death_causes_count: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-
-    // New: Track per-bacteria and per-drug resistance counts
-    let mut bacteria_infection_counts: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
-
-    for individual in &simulation.population.individuals {
-        // Death reporting (existing)
-        if let Some(_date_of_death) = individual.date_of_death {
-            total_deaths += 1;
-            if let Some(cause) = &individual.cause_of_death {
-                *death_causes_count.entry(cause.clone()).or_insert(0) += 1;
-            }
-        }
+    /// Number of individuals alive at the start of each model run
+    #[arg(long, default_value_t = 2_000)]
+    population: usize,
 
-        // Per-bacteria and per-drug resistance reporting
-        for (bacteria, &b_idx) in simulation.bacteria_indices.iter() {
-            if individual.level[b_idx] > 0.001 {
-                // Count as infected with this bacteria
-                *bacteria_infection_counts.entry(bacteria).or_insert(0) += 1;
-            }
-        }
+    /// Number of days each model run covers
+    #[arg(long, default_value_t = 365, conflicts_with = "until")]
+    days: usize,
+
+    /// Run each model up to and including this date, YYYY-MM-DD, instead of a number of days
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<CalendarDate>,
+
+    /// rejection (draw, run and keep the closest) or smc (sequential Monte Carlo)
+    #[arg(long, value_enum, default_value_t = FitMethod::Smc)]
+    method: FitMethod,
+
+    /// Size of the posterior sample
+    #[arg(long, default_value_t = 100)]
+    particles: usize,
+
+    /// For rejection, the number of parameter sets drawn from the priors
+    #[arg(long, default_value_t = 1_000)]
+    draws: usize,
+
+    /// For smc, the number of generations
+    #[arg(long, default_value_t = 5)]
+    generations: usize,
+
+    /// For smc, the quantile of each generation's distances that becomes the next tolerance
+    #[arg(long, default_value_t = 0.5)]
+    quantile: f64,
+
+    /// Largest distance (total deviance) to accept: for rejection, instead of keeping the closest; for smc, where
+    /// to stop
+    #[arg(long)]
+    tolerance: Option<f64>,
+
+    /// For smc, the most model runs a generation may take before the fit stops
+    #[arg(long, default_value_t = 10_000)]
+    max_runs_per_generation: usize,
+
+    /// Seed of the fit; the same seed (and settings) repeats it exactly.  Drawn at random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Where to write the posterior sample (CSV: weight, distance, seed and one column per parameter)
+    #[arg(long, value_name = "FILE", default_value = "posterior.csv")]
+    output: PathBuf,
+
+    /// Number of worker threads (default: one per CPU); results do not depend on it
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FitMethod {
+    Rejection,
+    Smc,
+}
+
+#[derive(Args)]
+struct SensitivityArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+
+    /// The parameters to vary and their ranges, as uniform or log_uniform distributions in a priors file (.toml or
+    /// .json; see src/calibration/priors.rs)
+    #[arg(long, value_name = "FILE")]
+    ranges: PathBuf,
+
+    /// An output metric to report on, e.g. deaths_sepsis_related or "any_r(escherichia coli, amoxicillin)"; repeat
+    /// for more (see src/analysis/metrics.rs)
+    #[arg(long = "metric", value_name = "METRIC", required = true)]
+    metrics: Vec<String>,
+
+    /// lhs (Latin hypercube, rank correlations), morris (elementary effects) or sobol (Saltelli design, Sobol
+    /// indices)
+    #[arg(long, value_enum, default_value_t = DesignMethod::Morris)]
+    method: DesignMethod,
+
+    /// Points of a Latin hypercube, trajectories of a Morris design, or base samples of a Sobol design
+    #[arg(long, default_value_t = 10)]
+    samples: usize,
+
+    /// For morris, the number of grid levels
+    #[arg(long, default_value_t = 4)]
+    levels: usize,
+
+    /// Number of individuals alive at the start of each model run
+    #[arg(long, default_value_t = 2_000)]
+    population: usize,
+
+    /// Number of days each model run covers
+    #[arg(long, default_value_t = 365, conflicts_with = "until")]
+    days: usize,
+
+    /// Run each model up to and including this date, YYYY-MM-DD, instead of a number of days
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<CalendarDate>,
+
+    /// Seed of the analysis; the same seed (and settings) repeats it exactly.  Drawn at random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Directory to write design.csv (every run's parameters and metrics) and sensitivity.csv (the statistics) to
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    output_dir: PathBuf,
+
+    /// Number of worker threads (default: one per CPU); results do not depend on it
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DesignMethod {
+    Lhs,
+    Morris,
+    Sobol,
+}
+
+#[derive(Args)]
+struct ParamsArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+
+    /// Print the values in force on this date, YYYY-MM-DD, in place of calendar-time schedules
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    at: Option<CalendarDate>,
+}
+
+fn parse_date(text: &str) -> Result<CalendarDate, String> {
+    CalendarDate::parse(text).ok_or_else(|| format!("\"{}\" is not a YYYY-MM-DD date", text))
+}
+
+fn parse_event_kind(text: &str) -> Result<EventKind, String> {
+    EventKind::from_name(text).ok_or_else(|| {
+        let names: Vec<&str> = EventKind::ALL.iter().map(|kind| kind.name()).collect();
+        format!("\"{}\" is not an event kind (one of {})", text, names.join(", "))
+    })
+}
+
+fn parse_key_value(text: &str) -> Result<(String, f64), String> {
+    let (key, value) = text.split_once('=').ok_or_else(|| format!("\"{}\" is not KEY=VALUE", text))?;
+    let value = value.trim().parse().map_err(|_| format!("\"{}\" is not a number", value.trim()))?;
+    Ok((key.trim().to_string(), value))
+}
+
+// Prints an error and exits with status 1.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
+
+// The catalog and demographic data every simulation of the command is built with.
+struct ModelData {
+    catalog: Arc<Catalog>,
+    demography: Arc<Demography>,
+}
+
+impl ModelData {
+    // Gives a new simulation's builder the catalog and demographic data.
+    fn apply(&self, builder: SimulationBuilder) -> SimulationBuilder {
+        builder.catalog(self.catalog.clone()).demography(self.demography.clone())
     }
+}
 
-    println!("total deaths during simulation: {}", total_deaths);
-    println!("breakdown by cause of death:");
-    for (cause, count) in death_causes_count {
-        println!("{}: {}", cause, count);
-    }
-
-
-
-/*
-
-
-    // New: Print bacteria and resistance summary
-    println!("\n--- Bacteria infection and resistance summary ---");
-    for (bacteria, &count) in &bacteria_infection_counts {
-        println!("{}: {} infected", bacteria, count);
-        for (drug, _) in simulation.drug_indices.iter() {
-            // Collect the full distribution of any_r for this bacteria/drug pair
-            let mut any_r_values = Vec::new();
-            for individual in &simulation.population.individuals {
-                if let Some(&b_idx) = simulation.bacteria_indices.get(bacteria) {
-                    if individual.level[b_idx] > 0.001 {
-                        if let Some(&d_idx) = simulation.drug_indices.get(drug) {
-                            let any_r = individual.resistances[b_idx][d_idx].any_r;
-                            any_r_values.push(any_r);
-                        }
-                    }
-                }
-            }
-            // Print summary statistics for the distribution
-            if !any_r_values.is_empty() {
-                let n = any_r_values.len() as f64;
-                let mut count_0 = 0;
-                let mut count_001_025 = 0;
-                let mut count_0251_05 = 0;
-                let mut count_0501_075 = 0;
-                let mut count_0751_1 = 0;
-                for &val in &any_r_values {
-                    if val == 0.0 {
-                        count_0 += 1;
-                    } else if val > 0.0 && val <= 0.25 {
-                        count_001_025 += 1;
-                    } else if val > 0.25 && val <= 0.5 {
-                        count_0251_05 += 1;
-                    } else if val > 0.5 && val <= 0.75 {
-                        count_0501_075 += 1;
-                    } else if val > 0.75 && val <= 1.0 {
-                        count_0751_1 += 1;
-                    }
-                }
-                println!(
-                    "    {}: n = {}, prop 0.00 = {:.3}, prop 0.25 = {:.3}, prop 0.5 = {:.3}, prop 0.75 = {:.3}, prop 1.00 = {:.3}",
-                    drug,
-                    n as usize,
-                    count_0 as f64 / n,
-                    count_001_025 as f64 / n,
-                    count_0251_05 as f64 / n,
-                    count_0501_075 as f64 / n,
-                    count_0751_1 as f64 / n
-                );
-            } else {
-                println!("    {}: n = 0", drug);
-            }
+fn main() {
+    let cli = Cli::parse();
+
+    let catalog = match &cli.catalog {
+        Some(path) => {
+            let catalog = Catalog::from_file(path).unwrap_or_else(|e| fail(e));
+            eprintln!("main.rs  using catalog {}", path.display());
+            catalog
+        }
+        None => Catalog::default_catalog(),
+    };
+    let demography = match &cli.demography {
+        Some(path) => {
+            let demography = Demography::from_dir(path).unwrap_or_else(|e| fail(e));
+            eprintln!("main.rs  using demographic data from {}", path.display());
+            demography
         }
+        None => Demography::default_demography(),
+    };
+    let data = ModelData { catalog: Arc::new(catalog), demography: Arc::new(demography) };
+
+    match cli.command {
+        Command::Run(args) => run(*args, &data, cli.catalog, cli.demography),
+        Command::Fit(args) => fit(*args, &data),
+        Command::Sensitivity(args) => sensitivity(*args, &data),
+        Command::Params(args) => params(args, &data),
+        Command::ListParameters => ParameterSchema::for_catalog(&data.catalog)
+            .write_listing(&mut std::io::stdout().lock())
+            .expect("failed to write parameter listing"),
+        Command::ListCatalog => data
+            .catalog
+            .write_listing(&mut std::io::stdout().lock())
+            .expect("failed to write catalog listing"),
     }
-    // --- end death and resistance reporting ---
-
-*/
+}
 
+// A builder with the parameter settings applied; the parameter files are applied in order and the result
+// validated on build, so unknown keys, missing keys or out-of-range values stop the run rather than silently using
+// fallbacks.
+fn scenario_builder(scenario: &ScenarioArgs) -> SimulationBuilder {
+    let mut builder = Simulation::builder();
+    for path in &scenario.parameter_files {
+        builder = builder.parameter_file(path);
+    }
+    for (key, value) in &scenario.values {
+        builder = builder.parameter(key, *value);
+    }
+    if let Some(start_date) = scenario.start_date {
+        builder = builder.start_date(start_date);
+    }
+    builder
+}
 
+// Writes the effective parameters to stdout.
+fn params(args: ParamsArgs, data: &ModelData) {
+    let parameters = data.apply(scenario_builder(&args.scenario)).resolve_parameters().unwrap_or_else(|e| fail(e));
+    let parameters = match args.at {
+        Some(date) => parameters.resolved_at(date.day_number()),
+        None => parameters,
+    };
+    parameters.write_toml(&mut std::io::stdout().lock()).expect("failed to write parameters");
+}
 
-/*
+// Fits the priors' parameters to the targets and writes the posterior sample.
+fn fit(args: FitArgs, data: &ModelData) {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap_or_else(|e| fail(format!("could not start {} threads: {}", threads, e)));
+    }
+    let parameters = data.apply(scenario_builder(&args.scenario)).resolve_parameters().unwrap_or_else(|e| fail(e));
+    let priors = load_priors(&data.catalog, &args.priors).unwrap_or_else(|e| fail(e));
+    let targets = load_targets(&data.catalog, &args.targets).unwrap_or_else(|e| fail(e));
+    let seed = args.seed.unwrap_or_else(random_master_seed);
+    println!(
+        "main.rs  fitting {} parameters to {} targets, seed {} (rerun with --seed {} to repeat this fit)",
+        priors.len(),
+        targets.len(),
+        seed,
+        seed
+    );
+
+    let method = match args.method {
+        FitMethod::Rejection => AbcMethod::Rejection { draws: args.draws },
+        FitMethod::Smc => AbcMethod::Smc { generations: args.generations, quantile: args.quantile },
+    };
+    let mut abc = AbcFit::new(priors, targets)
+        .parameters(parameters)
+        .catalog(data.catalog.clone())
+        .demography(data.demography.clone())
+        .population_size(args.population)
+        .run_length(args.until.map_or(RunLength::Days(args.days), RunLength::Until))
+        .particles(args.particles)
+        .method(method)
+        .max_runs_per_generation(args.max_runs_per_generation)
+        .seed(seed)
+        .progress(|progress| println!("main.rs  {}", progress));
+    if let Some(tolerance) = args.tolerance {
+        abc = abc.tolerance(tolerance);
+    }
+    let posterior = abc.run().unwrap_or_else(|e| fail(e));
+
+    let mut out = File::create(&args.output)
+        .map(BufWriter::new)
+        .unwrap_or_else(|e| fail(format!("could not create {}: {}", args.output.display(), e)));
+    posterior.write_csv(&mut out).unwrap_or_else(|e| fail(format!("could not write {}: {}", args.output.display(), e)));
+    println!(
+        "main.rs  {} parameter sets within distance {:.4} after {} runs written to {}",
+        posterior.particles.len(),
+        posterior.tolerance,
+        posterior.runs,
+        args.output.display()
+    );
+}
 
-    // Example: Plot distribution of any_r for one random bacteria-drug pair using plotters
-    // (Requires plotters = "0.3" in Cargo.toml)
-    use rand::seq::IteratorRandom;
-    use plotters::prelude::*;
+fn sensitivity(args: SensitivityArgs, data: &ModelData) {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap_or_else(|e| fail(format!("could not start {} threads: {}", threads, e)));
+    }
+    let parameters = data.apply(scenario_builder(&args.scenario)).resolve_parameters().unwrap_or_else(|e| fail(e));
+    let ranges = load_priors(&data.catalog, &args.ranges).unwrap_or_else(|e| fail(e));
+    let metrics: Vec<OutputMetric> = args
+        .metrics
+        .iter()
+        .map(|text| OutputMetric::parse(&data.catalog, text).unwrap_or_else(|e| fail(format!("--metric \"{}\": {}", text, e))))
+        .collect();
+    let seed = args.seed.unwrap_or_else(random_master_seed);
+    println!(
+        "main.rs  sensitivity of {} metrics to {} parameters, seed {} (rerun with --seed {} to repeat this analysis)",
+        metrics.len(),
+        ranges.len(),
+        seed,
+        seed
+    );
+
+    let method = match args.method {
+        DesignMethod::Lhs => SensitivityMethod::LatinHypercube { samples: args.samples },
+        DesignMethod::Morris => SensitivityMethod::Morris { trajectories: args.samples, levels: args.levels },
+        DesignMethod::Sobol => SensitivityMethod::Sobol { samples: args.samples },
+    };
+    let result = SensitivityAnalysis::new(ranges, metrics)
+        .parameters(parameters)
+        .catalog(data.catalog.clone())
+        .demography(data.demography.clone())
+        .population_size(args.population)
+        .run_length(args.until.map_or(RunLength::Days(args.days), RunLength::Until))
+        .method(method)
+        .seed(seed)
+        .run()
+        .unwrap_or_else(|e| fail(e));
+    println!("main.rs  {} runs of {} parameters, run seed {}", result.runs(), result.keys.len(), result.seed);
+
+    fs::create_dir_all(&args.output_dir)
+        .unwrap_or_else(|e| fail(format!("could not create {}: {}", args.output_dir.display(), e)));
+    let design_path = args.output_dir.join("design.csv");
+    let indices_path = args.output_dir.join("sensitivity.csv");
+    let create = |path: &PathBuf| {
+        File::create(path).map(BufWriter::new).unwrap_or_else(|e| fail(format!("could not create {}: {}", path.display(), e)))
+    };
+    result
+        .write_design_csv(&mut create(&design_path))
+        .unwrap_or_else(|e| fail(format!("could not write {}: {}", design_path.display(), e)));
+    result
+        .write_indices_csv(&mut create(&indices_path))
+        .unwrap_or_else(|e| fail(format!("could not write {}: {}", indices_path.display(), e)));
+    println!(
+        "main.rs  {} runs written to {}, statistics to {}",
+        result.runs(),
+        design_path.display(),
+        indices_path.display()
+    );
+}
 
-    // Pick a random bacteria-drug pair with at least one infected individual and at least one any_r > 0
-    let mut rng = rand::thread_rng();
-    let mut example_pair: Option<(&str, &str)> = None;
-    let mut example_any_r_values = Vec::new();
+fn run(args: RunArgs, data: &ModelData, catalog_file: Option<PathBuf>, demography_dir: Option<PathBuf>) {
+    if args.age_bands.first() != Some(&0) || !args.age_bands.windows(2).all(|pair| pair[0] < pair[1]) {
+        fail("the age bands must start at 0 and increase");
+    }
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap_or_else(|e| fail(format!("could not start {} threads: {}", threads, e)));
+    }
 
-    let pairs: Vec<(&str, &str)> = simulation.bacteria_indices.keys()
-        .flat_map(|&bacteria| simulation.drug_indices.keys().map(move |&drug| (bacteria, drug)))
-        .collect();
+    if args.replicates == 0 {
+        fail("--replicates must be at least 1");
+    }
+    if args.replicates > 1 {
+        run_replicates(&args, data, catalog_file.as_ref(), demography_dir.as_ref());
+        return;
+    }
 
-    // --- DEBUG: Print how many pairs have any_r > 0 ---
-    let mut found_pairs = 0;
-    for &(bacteria, drug) in &pairs {
-        let mut values = Vec::new();
-        if let (Some(&b_idx), Some(&d_idx)) = (simulation.bacteria_indices.get(bacteria), simulation.drug_indices.get(drug)) {
-            for individual in &simulation.population.individuals {
-                if individual.level[b_idx] > 0.001 {
-                    let any_r = individual.resistances[b_idx][d_idx].any_r;
-                    values.push(any_r);
-                }
-            }
-            if values.iter().any(|&v| v > 0.0) {
-                found_pairs += 1;
-            }
+    let mut builder = run_builder(&args, data, args.seed);
+    let mut calibration_report = None;
+    builder = match &args.output_dir {
+        Some(dir) => {
+            let (builder, report) = add_outputs(builder, &args, data, dir, None);
+            calibration_report = report;
+            builder
         }
-    }
-    println!("Number of bacteria/drug pairs with any any_r > 0: {}", found_pairs);
-
-    for &(bacteria, drug) in pairs.iter().choose_multiple(&mut rng, pairs.len()) {
-        let mut values = Vec::new();
-        if let (Some(&b_idx), Some(&d_idx)) = (simulation.bacteria_indices.get(bacteria), simulation.drug_indices.get(drug)) {
-            for individual in &simulation.population.individuals {
-                if individual.level[b_idx] > 0.001 {
-                    let any_r = individual.resistances[b_idx][d_idx].any_r;
-                    values.push(any_r);
-                }
-            }
-            // Only use this pair if there is at least one value > 0
-            if values.iter().any(|&v| v > 0.0) {
-                example_pair = Some((bacteria, drug));
-                example_any_r_values = values;
-                break;
-            }
+        None => builder.output(EndOfRunSummary::new(std::io::stdout())),
+    };
+    if let Some(path) = &args.snapshot {
+        let mut writer = SnapshotWriter::new(path);
+        if let Some(days) = args.checkpoint_every {
+            writer = writer.every(days);
         }
+        builder = builder.output(writer);
     }
+    let mut simulation = builder.build().unwrap_or_else(|e| fail(e));
+    println!("main.rs  random seed {} (rerun with --seed {} to repeat this run)", simulation.seed, simulation.seed);
 
-    if let Some((bacteria, drug)) = example_pair {
-        println!("\n--- Example histogram for any_r distribution: {} / {} ---", bacteria, drug);
-
-        // Bin edges: [0, 0.25], (0.25, 0.5], (0.5, 0.75], (0.75, 1.0]
-        let mut bins = [0; 5];
-        for &val in &example_any_r_values {
-            if val == 0.0 {
-                bins[0] += 1;
-            } else if val > 0.0 && val <= 0.25 {
-                bins[1] += 1;
-            } else if val > 0.25 && val <= 0.5 {
-                bins[2] += 1;
-            } else if val > 0.5 && val <= 0.75 {
-                bins[3] += 1;
-            } else if val > 0.75 && val <= 1.0 {
-                bins[4] += 1;
-            }
-        }
-        println!("Bin counts: {:?}", bins);
-
-        // Always plot the histogram, even if only one bin is nonzero
-        let root = BitMapBackend::new("any_r_histogram.png", (640, 480)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let max_count = *bins.iter().max().unwrap_or(&1);
-
-        // Set a minimum y-axis height for better visibility of small bars
-        let y_axis_max = if max_count < 10 { 10 } else { max_count + 2 };
-
-        // Use f64 for both axes in build_cartesian_2d and Rectangle coordinates
-        let mut chart = ChartBuilder::on(&root)
-            .caption(format!("any_r distribution for {} / {}", bacteria, drug), ("sans-serif", 30))
-            .margin(40)
-            .x_label_area_size(40)
-            .y_label_area_size(40)
-            .build_cartesian_2d(
-                0f64..5f64,
-                0f64..(y_axis_max as f64),
-            )
-            .unwrap();
-
-        chart
-            .configure_mesh()
-            .x_desc("any_r bin")
-            .y_desc("Count")
-            .disable_x_mesh() // Keep y-axis grid lines, but hide x-axis ones
-            .disable_x_axis() // Hide the default x-axis line and labels
-            .draw()
-            .unwrap();
-
-        // Draw all bins with equal width, centered on each bin midpoint
-        use plotters::style::RGBColor;
-        let bar_color = RGBColor(220, 50, 47); // Solarized red for visibility
-
-        chart.draw_series(
-            bins.iter().enumerate().map(|(i, &count)| {
-                // Center the bar on the midpoint (i+0.5)
-                let x0 = i as f64 + 0.05;
-                let x1 = i as f64 + 0.95;
-                Rectangle::new(
-                    [(x0, 0.0), (x1, count as f64)],
-                    if count > 0 { bar_color.filled() } else { WHITE.filled() },
-                )
-            }),
-        ).unwrap();
-
-        // Draw count labels above each bar for clarity, always at the center of the bar
-        chart.draw_series(
-            bins.iter().enumerate().map(|(i, &count)| {
-                let x = i as f64 + 0.5;
-                if count > 0 {
-                    Text::new(
-                        format!("{}", count),
-                        (x, count as f64 + 0.5),
-                        ("sans-serif", 15).into_font().color(&BLACK),
-                    )
-                } else {
-                    Text::new(
-                        String::new(),
-                        (x, 0.0),
-                        ("sans-serif", 15).into_font().color(&BLACK),
-                    )
-                }
-            }),
-        ).unwrap();
-
-        // Manually draw the x-axis labels centered under each bar
-        let labels = ["0", "0.25", "0.5", "0.75", "1"];
-        chart.draw_series(
-            labels.iter().enumerate().map(|(i, &label)| {
-                let x = i as f64 + 0.5;
-                Text::new(
-                    label.to_string(),
-                    (x, -0.05 * y_axis_max as f64), // Position below the x-axis
-                    ("sans-serif", 15).into_font().color(&BLACK),
-                )
-            })
-        ).unwrap();
-
-        println!("Histogram saved to any_r_histogram.png");
-    } else {
-        println!("No bacteria/drug pair found with any nonzero any_r values.");
-    }
-
-
-
-
-*/
+    // record how the run was set up next to its outputs, so it can be repeated
+    if let Some(dir) = &args.output_dir {
+        write_run_record(dir, &args, catalog_file.as_ref(), demography_dir.as_ref(), &simulation, &simulation.parameters, None)
+            .unwrap_or_else(|e| fail(format!("could not write to {}: {}", dir.display(), e)));
+    }
 
+    use std::time::Instant;
+    let start = Instant::now();
+
+    // (the end-of-run summary is written by the EndOfRunSummary output as the run finishes)
+    if let Err(e) = simulation.run() {
+        fail(format!("failed to write outputs: {}", e));
+    }
+
+    let duration = start.elapsed();
+
+    if let Some(report) = calibration_report.and_then(|handle| handle.lock().unwrap().take()) {
+        println!(
+            "main.rs  calibration: {} of {} targets in the run, log-likelihood {:.3}, deviance {:.3}",
+            report.covered().count(),
+            report.fits.len(),
+            report.total_log_likelihood(),
+            report.total_deviance()
+        );
+    }
 
     println!("\n--- simulation ended ---");
     println!("\n--- total simulation time: {:.3?} seconds", duration);
@@ -331,4 +621,212 @@ fn main() {
 
 }
 
+// The builder for a run (or a replicate): scenario, population or snapshot, seed and run length.
+fn run_builder(args: &RunArgs, data: &ModelData, seed: Option<u64>) -> SimulationBuilder {
+    let mut builder = scenario_builder(&args.scenario);
+    builder = match &args.resume {
+        Some(path) => {
+            let snapshot = Simulation::load_snapshot(path, data.catalog.clone(), data.demography.clone())
+                .unwrap_or_else(|e| fail(e));
+            println!("main.rs  resuming from {} at time step {}", path.display(), snapshot.current_time_step);
+            builder.resume(snapshot)
+        }
+        None => data.apply(builder).population_size(args.population).seed(seed.unwrap_or_else(random_master_seed)),
+    };
+    if let (Some(seed), Some(_)) = (seed, &args.resume) {
+        builder = builder.seed(seed);
+    }
+    if let Some(days) = args.compact_every {
+        builder = builder.compact_dead_every(days);
+    }
+    match args.until {
+        Some(end_date) => builder.until(end_date),
+        None => builder.days(args.days),
+    }
+}
+
+// Adds the outputs written to `dir` (summary, time series and the optional event log, trace and calibration),
+// keeping the time-series counts in `kept` if given.  Returns the calibration report handle if there is one.
+fn add_outputs(
+    mut builder: SimulationBuilder,
+    args: &RunArgs,
+    data: &ModelData,
+    dir: &std::path::Path,
+    kept: Option<TimeSeriesHandle>,
+) -> (SimulationBuilder, Option<ReportHandle>) {
+    fs::create_dir_all(dir).unwrap_or_else(|e| fail(format!("could not create {}: {}", dir.display(), e)));
+    let summary_path = dir.join("summary.txt");
+    let summary = File::create(&summary_path)
+        .unwrap_or_else(|e| fail(format!("could not create {}: {}", summary_path.display(), e)));
+    let timeseries_path = dir.join("timeseries.csv");
+    let timeseries = File::create(&timeseries_path)
+        .unwrap_or_else(|e| fail(format!("could not create {}: {}", timeseries_path.display(), e)));
+    let mut timeseries = TimeSeriesWriter::new(BufWriter::new(timeseries))
+        .every(args.report_every)
+        .age_bands(args.age_bands.clone());
+    if let Some(kept) = kept {
+        timeseries = timeseries.keep_counts(kept);
+    }
+    builder = builder.output(EndOfRunSummary::new(BufWriter::new(summary))).output(timeseries);
+    if args.event_log {
+        let events_path = dir.join("events.csv");
+        let events = File::create(&events_path)
+            .unwrap_or_else(|e| fail(format!("could not create {}: {}", events_path.display(), e)));
+        let mut event_log = EventLogWriter::new(BufWriter::new(events));
+        if !args.event_types.is_empty() {
+            event_log = event_log.kinds(args.event_types.iter().copied());
+        }
+        if !args.event_individuals.is_empty() {
+            event_log = event_log.individuals(args.event_individuals.iter().copied());
+        }
+        builder = builder.output(event_log);
+    }
+    if !args.trace_ids.is_empty() || args.trace_sample.is_some() || args.trace_where.is_some() {
+        let trace_path = dir.join("trace.jsonl");
+        let trace = File::create(&trace_path)
+            .unwrap_or_else(|e| fail(format!("could not create {}: {}", trace_path.display(), e)));
+        let mut tracer = CohortTracer::new(BufWriter::new(trace)).ids(args.trace_ids.iter().copied());
+        if let Some(size) = args.trace_sample {
+            tracer = tracer.sample(size);
+        }
+        if let Some(conditions) = &args.trace_where {
+            let filter = TraceFilter::parse(&data.catalog, conditions)
+                .unwrap_or_else(|e| fail(format!("invalid --trace-where \"{}\": {}", conditions, e)));
+            tracer = tracer.filter(filter);
+        }
+        builder = builder.output(tracer);
+    }
+    let mut calibration_report = None;
+    if !args.calibration_targets.is_empty() {
+        let targets = load_targets(&data.catalog, &args.calibration_targets).unwrap_or_else(|e| fail(e));
+        let calibration_path = dir.join("calibration.csv");
+        let calibration = File::create(&calibration_path)
+            .unwrap_or_else(|e| fail(format!("could not create {}: {}", calibration_path.display(), e)));
+        let writer = CalibrationWriter::new(BufWriter::new(calibration), targets);
+        calibration_report = Some(writer.report_handle());
+        builder = builder.output(writer);
+    }
+    (builder, calibration_report)
+}
+
+// Runs the replicates of `run --replicates N` one after another, then writes the summary of their time series,
+// the values each drew for the parameters declared as distributions (replicate_parameters.csv), run.toml (with
+// every replicate's seed) and parameters.toml to the output directory.
+fn run_replicates(args: &RunArgs, data: &ModelData, catalog_file: Option<&PathBuf>, demography_dir: Option<&PathBuf>) {
+    let dir = args.output_dir.as_ref().expect("--replicates requires --output-dir");
+    fs::create_dir_all(dir).unwrap_or_else(|e| fail(format!("could not create {}: {}", dir.display(), e)));
+    let master_seed = args.seed.unwrap_or_else(random_master_seed);
+    println!(
+        "main.rs  {} replicates from seed {} (rerun with --seed {} to repeat them)",
+        args.replicates, master_seed, master_seed
+    );
+
+    let parameters = run_builder(args, data, Some(master_seed)).resolve_parameters().unwrap_or_else(|e| fail(e));
+    let mut uncertain: Vec<String> = parameters.distributions().keys().cloned().collect();
+    uncertain.sort();
+    if !uncertain.is_empty() {
+        println!("main.rs  each replicate draws its own values of {} parameters declared as distributions", uncertain.len());
+    }
+
+    let start = std::time::Instant::now();
+    let mut summary = ReplicateSummary::new(args.age_bands.clone()).parameters(uncertain.clone());
+    let mut last = None;
+    for replicate in 0..args.replicates {
+        let counts = TimeSeriesHandle::default();
+        let mut builder = run_builder(args, data, Some(master_seed)).replicate(replicate).quiet(true);
+        let replicate_dir = dir.join(format!("replicate_{:03}", replicate + 1));
+        builder = if args.keep_replicates {
+            add_outputs(builder, args, data, &replicate_dir, Some(counts.clone())).0
+        } else {
+            builder.output(
+                TimeSeriesWriter::new(std::io::sink())
+                    .every(args.report_every)
+                    .age_bands(args.age_bands.clone())
+                    .keep_counts(counts.clone()),
+            )
+        };
+        let mut simulation = builder.build().unwrap_or_else(|e| fail(e));
+        if args.keep_replicates {
+            write_run_record(&replicate_dir, args, catalog_file, demography_dir, &simulation, &simulation.parameters, None)
+                .unwrap_or_else(|e| fail(format!("could not write to {}: {}", replicate_dir.display(), e)));
+        }
+        if let Err(e) = simulation.run() {
+            fail(format!("failed to write outputs: {}", e));
+        }
+        summary.add(&simulation, std::mem::take(&mut *counts.lock().unwrap()));
+        println!("main.rs  replicate {} of {} done (seed {})", replicate + 1, args.replicates, simulation.seed);
+        last = Some(simulation);
+    }
+
+    let summary_path = dir.join("timeseries_summary.csv");
+    let mut out = File::create(&summary_path)
+        .map(BufWriter::new)
+        .unwrap_or_else(|e| fail(format!("could not create {}: {}", summary_path.display(), e)));
+    summary.write_csv(&mut out).unwrap_or_else(|e| fail(format!("could not write {}: {}", summary_path.display(), e)));
+    if !uncertain.is_empty() {
+        let parameters_path = dir.join("replicate_parameters.csv");
+        let mut out = File::create(&parameters_path)
+            .map(BufWriter::new)
+            .unwrap_or_else(|e| fail(format!("could not create {}: {}", parameters_path.display(), e)));
+        summary
+            .write_parameters_csv(&mut out)
+            .unwrap_or_else(|e| fail(format!("could not write {}: {}", parameters_path.display(), e)));
+    }
+    let simulation = last.expect("at least one replicate has run");
+    write_run_record(dir, args, catalog_file, demography_dir, &simulation, &parameters, Some((master_seed, summary.seeds())))
+        .unwrap_or_else(|e| fail(format!("could not write to {}: {}", dir.display(), e)));
+    println!("main.rs  summary of {} replicates written to {}", summary.replicates(), summary_path.display());
+    println!("\n--- total simulation time: {:.3?} seconds", start.elapsed());
+}
+
+// Writes run.toml (the settings of the run) and parameters.toml (the full effective parameter set, `parameter_set`) to
+// `dir`.
+// `executable_amr run --seed <seed> --population <n> --days <d> <dir>/parameters.toml` repeats the run (for a
+// resumed run, `run --resume <snapshot> --until <end date> <dir>/parameters.toml`).  For a set of replicates,
+// `replicates` has the master seed and the seed of each replicate, and `--replicates <n>` repeats them.
+fn write_run_record(
+    dir: &std::path::Path,
+    args: &RunArgs,
+    catalog_file: Option<&PathBuf>,
+    demography_dir: Option<&PathBuf>,
+    simulation: &Simulation,
+    parameter_set: &ParameterSet,
+    replicates: Option<(u64, &[u64])>,
+) -> std::io::Result<()> {
+    let mut run = BufWriter::new(File::create(dir.join("run.toml"))?);
+    match replicates {
+        Some((master_seed, seeds)) => {
+            writeln!(run, "seed = {}", master_seed)?;
+            writeln!(run, "replicates = {}", seeds.len())?;
+            let seeds: Vec<String> = seeds.iter().map(|seed| seed.to_string()).collect();
+            writeln!(run, "replicate_seeds = [{}]", seeds.join(", "))?;
+        }
+        None => writeln!(run, "seed = {}", simulation.seed)?,
+    }
+    if args.resume.is_none() {
+        writeln!(run, "population = {}", args.population)?;
+    }
+    writeln!(run, "days = {}", simulation.time_steps)?;
+    writeln!(run, "start_date = \"{}\"", simulation.start_date)?;
+    writeln!(run, "end_date = \"{}\"", simulation.date_at(simulation.time_steps).add_days(-1))?;
+    if let Some(days) = args.compact_every {
+        writeln!(run, "compact_every = {}", days)?;
+    }
+    if let Some(path) = &args.resume {
+        writeln!(run, "resumed_from = {:?}", path.display().to_string())?;
+        writeln!(run, "resumed_at_time_step = {}", simulation.current_time_step)?;
+    }
+    if let Some(path) = catalog_file {
+        writeln!(run, "catalog = {:?}", path.display().to_string())?;
+    }
+    if let Some(path) = demography_dir {
+        writeln!(run, "demography = {:?}", path.display().to_string())?;
+    }
+    let files: Vec<String> = args.scenario.parameter_files.iter().map(|p| format!("{:?}", p.display().to_string())).collect();
+    writeln!(run, "parameter_files = [{}]", files.join(", "))?;
+    run.flush()?;
 
+    let mut parameters = BufWriter::new(File::create(dir.join("parameters.toml"))?);
+    parameter_set.write_toml(&mut parameters)?;
+    parameters.flush()
+}
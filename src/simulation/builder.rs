@@ -0,0 +1,385 @@
+// src/simulation/builder.rs
+//
+// Building a `Simulation` from code, for tools that embed the model rather than running the executable:
+//
+//     let mut simulation = Simulation::builder()
+//         .population_size(100_000)
+//         .start_date(CalendarDate::parse("2000-01-01").unwrap())
+//         .days(365)
+//         .seed(42)
+//         .parameter_file("parameters/example_overrides.toml")
+//         .parameter("drug_base_initiation_rate_per_day", 0.0002)
+//         .output(EndOfRunSummary::new(std::io::stdout()))
+//         .build()?;
+//     simulation.run()?;
+//
+// The parameter set starts from the compiled-in defaults (or a given `ParameterSet`), then has the parameter files
+// applied in order, then the individual values; the start date, if given, replaces "simulation_start_date".  The
+// result is validated against the registry in schema.rs before the population is created.  The bacteria, drugs and
+// regions are those of the compiled-in catalog unless another is given with `catalog`, and likewise the demographic
+// data (`demography`), which must cover every region of the catalog; the defaults and registry of the parameters are
+// built over the catalog.  Each simulation owns
+// its parameter set and holds its catalog and demographic data, so several can be built and run in one process.
+//
+// A builder can also continue a simulation restored from a snapshot (see snapshot.rs) instead of creating a new
+// population, e.g. to branch several scenarios from one warm-up run:
+//
+//     let warm = Simulation::load_snapshot("warm_up_2000.snap", catalog, demography)?;
+//     let mut scenario = Simulation::builder()
+//         .resume(warm)
+//         .parameter_file("parameters/intervention.toml")
+//         .until(CalendarDate::parse("2030-12-31").unwrap())
+//         .build()?;
+//
+// The parameter files and values are then applied on top of the snapshot's parameter set, the start date, population,
+// catalog and demographic data are those of the snapshot, `days` counts from the snapshot's time step, and a seed, if given, replaces
+// the snapshot's one for the time steps still to run.
+//
+// Parameters declared as distributions in the parameter files (see loader.rs) are drawn when the simulation is
+// built, from its seed, and the simulation's parameter set holds the values drawn.
+//
+// For replicates of a scenario, give every builder the same seed and a different `replicate` number; each run then
+// gets its own seed derived from the shared one (see rng::replicate_seed), and analysis/replicates.rs summarises
+// their time series.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::catalog::Catalog;
+use crate::config::demography::{Demography, DemographyError};
+use crate::config::loader::{ParameterFileError, ParameterSet};
+use crate::config::schedule::CalendarDate;
+use crate::config::schema::{ParameterSchema, ParameterValidationError};
+use crate::config::simulation_start_date;
+use crate::simulation::output::OutputSink;
+use crate::simulation::rng::{individual_rng, random_master_seed, replicate_seed, RngStream};
+use crate::simulation::simulation::Simulation;
+
+/// How long a simulation runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunLength {
+    /// A number of days (time steps).
+    Days(usize),
+    /// Up to and including a calendar date.
+    Until(CalendarDate),
+}
+
+/// Errors raised while building a simulation.
+#[derive(Debug)]
+pub enum BuildError {
+    ParameterFile(ParameterFileError),
+    InvalidParameters(ParameterValidationError),
+    InvalidDemography(DemographyError),
+    InvalidSetting(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::ParameterFile(e) => write!(f, "{}", e),
+            BuildError::InvalidParameters(e) => write!(f, "{}", e),
+            BuildError::InvalidDemography(e) => write!(f, "{}", e),
+            BuildError::InvalidSetting(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::ParameterFile(e) => Some(e),
+            BuildError::InvalidParameters(e) => Some(e),
+            BuildError::InvalidDemography(e) => Some(e),
+            BuildError::InvalidSetting(_) => None,
+        }
+    }
+}
+
+impl From<ParameterFileError> for BuildError {
+    fn from(e: ParameterFileError) -> Self {
+        BuildError::ParameterFile(e)
+    }
+}
+
+impl From<ParameterValidationError> for BuildError {
+    fn from(e: ParameterValidationError) -> Self {
+        BuildError::InvalidParameters(e)
+    }
+}
+
+impl From<DemographyError> for BuildError {
+    fn from(e: DemographyError) -> Self {
+        BuildError::InvalidDemography(e)
+    }
+}
+
+/// Builder for a `Simulation`; see the top of this file.
+pub struct SimulationBuilder {
+    population_size: usize,
+    start_date: Option<CalendarDate>,
+    run_length: RunLength,
+    seed: Option<u64>,
+    replicate: Option<usize>,
+    catalog: Option<Arc<Catalog>>,
+    demography: Option<Arc<Demography>>,
+    base_parameters: Option<ParameterSet>,
+    parameter_files: Vec<PathBuf>,
+    parameter_values: Vec<(String, f64)>,
+    outputs: Vec<Box<dyn OutputSink>>,
+    resume: Option<Simulation>,
+    quiet: bool,
+    compact_dead_every: usize,
+}
+
+impl Default for SimulationBuilder {
+    fn default() -> Self {
+        SimulationBuilder::new()
+    }
+}
+
+impl SimulationBuilder {
+    /// A builder for 100,000 individuals over 10 days, with default parameters and a random seed.
+    pub fn new() -> Self {
+        SimulationBuilder {
+            population_size: 100_000,
+            start_date: None,
+            run_length: RunLength::Days(10),
+            seed: None,
+            replicate: None,
+            catalog: None,
+            demography: None,
+            base_parameters: None,
+            parameter_files: Vec::new(),
+            parameter_values: Vec::new(),
+            outputs: Vec::new(),
+            resume: None,
+            quiet: false,
+            compact_dead_every: 0,
+        }
+    }
+
+    /// Number of individuals alive at the start (those born during the run are added to them).
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    /// Calendar date of time step 0 (default: the "simulation_start_date" parameter).
+    pub fn start_date(mut self, start_date: CalendarDate) -> Self {
+        self.start_date = Some(start_date);
+        self
+    }
+
+    /// Run for a number of days.
+    pub fn days(mut self, days: usize) -> Self {
+        self.run_length = RunLength::Days(days);
+        self
+    }
+
+    /// Run up to and including a calendar date.
+    pub fn until(mut self, end_date: CalendarDate) -> Self {
+        self.run_length = RunLength::Until(end_date);
+        self
+    }
+
+    /// Run length as days or an end date.
+    pub fn run_length(mut self, run_length: RunLength) -> Self {
+        self.run_length = run_length;
+        self
+    }
+
+    /// Master random seed (default: drawn from the operating system; see `Simulation::seed` for the one used).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The bacteria, drugs and regions of the simulation (default: the compiled-in catalog).
+    pub fn catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    /// The pyramids, birth rates and life tables of the simulation (default: the compiled-in data).
+    pub fn demography(mut self, demography: Arc<Demography>) -> Self {
+        self.demography = Some(demography);
+        self
+    }
+
+    /// Start from this parameter set instead of the compiled-in defaults.
+    pub fn parameters(mut self, parameters: ParameterSet) -> Self {
+        self.base_parameters = Some(parameters);
+        self
+    }
+
+    /// Apply a parameter override file (.toml or .json); files are applied in the order given.
+    pub fn parameter_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.parameter_files.push(path.into());
+        self
+    }
+
+    /// Set one numeric parameter, after the parameter files.
+    pub fn parameter(mut self, key: &str, value: f64) -> Self {
+        self.parameter_values.push((key.to_string(), value));
+        self
+    }
+
+    /// Add an output sink.
+    pub fn output<O: OutputSink + 'static>(mut self, output: O) -> Self {
+        self.outputs.push(Box::new(output));
+        self
+    }
+
+    /// Continue this restored simulation rather than creating a new one (see the top of this file).
+    pub fn resume(mut self, simulation: Simulation) -> Self {
+        self.resume = Some(simulation);
+        self
+    }
+
+    /// Makes this run replicate number `replicate` of the scenario: its seed is derived from the master seed (which
+    /// must be given, or come from the resumed snapshot) so that each replicate differs.
+    pub fn replicate(mut self, replicate: usize) -> Self {
+        self.replicate = Some(replicate);
+        self
+    }
+
+    /// Leaves out the progress messages (the start of the run) on the console.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Removes the rows of the dead from the population every `time_steps` time steps (0, the default: never), so
+    /// that long runs with births do not keep everyone who ever died (see `Population::compact`).
+    pub fn compact_dead_every(mut self, time_steps: usize) -> Self {
+        self.compact_dead_every = time_steps;
+        self
+    }
+
+    // The catalog of the simulation: the one given, else the resumed simulation's, else the compiled-in one.
+    fn chosen_catalog(&self) -> Arc<Catalog> {
+        match (&self.catalog, &self.resume) {
+            (Some(catalog), _) => catalog.clone(),
+            (None, Some(simulation)) => simulation.catalog.clone(),
+            (None, None) => Arc::new(Catalog::default_catalog()),
+        }
+    }
+
+    /// The merged and validated parameter set this builder would give the simulation.
+    pub fn resolve_parameters(&self) -> Result<ParameterSet, BuildError> {
+        let catalog = self.chosen_catalog();
+        let mut parameters = match (&self.base_parameters, &self.resume) {
+            (Some(parameters), _) => parameters.clone(),
+            (None, Some(simulation)) => simulation.parameters.clone(),
+            (None, None) => ParameterSet::defaults(&catalog),
+        };
+        for path in &self.parameter_files {
+            parameters.apply_file(&catalog, path)?;
+        }
+        for (key, value) in &self.parameter_values {
+            parameters.set_value(key, *value);
+        }
+        if let Some(start_date) = self.start_date {
+            parameters.set_string("simulation_start_date", &start_date.to_string());
+        }
+        if let Some(simulation) = &self.resume {
+            // the time steps already run are counted from the snapshot's start date
+            parameters.set_string("simulation_start_date", &simulation.start_date.to_string());
+        }
+        ParameterSchema::for_catalog(&catalog).validate(&parameters)?;
+        Ok(parameters)
+    }
+
+    /// Validates the settings and parameters and creates the simulation (and its population), or readies the
+    /// resumed one.
+    pub fn build(mut self) -> Result<Simulation, BuildError> {
+        if self.resume.is_some() && self.start_date.is_some() {
+            return Err(BuildError::InvalidSetting("a resumed simulation keeps the start date of its snapshot".to_string()));
+        }
+        if self.resume.is_some() && (self.catalog.is_some() || self.demography.is_some()) {
+            return Err(BuildError::InvalidSetting(
+                "a resumed simulation keeps the catalog and demographic data it was restored with".to_string(),
+            ));
+        }
+        if self.replicate.is_some() && self.seed.is_none() && self.resume.is_none() {
+            return Err(BuildError::InvalidSetting("replicates need a master seed to derive their seeds from".to_string()));
+        }
+        if self.resume.is_none() && self.population_size == 0 {
+            return Err(BuildError::InvalidSetting("the population size must be at least 1".to_string()));
+        }
+        let parameters = self.resolve_parameters()?;
+        let start_date = simulation_start_date(&parameters);
+        let already_run = self.resume.as_ref().map_or(0, |simulation| simulation.current_time_step);
+        let time_steps = match self.run_length {
+            RunLength::Days(days) => already_run + days,
+            RunLength::Until(end_date) => {
+                let days = end_date.day_number() - start_date.day_number() + 1;
+                if days < 1 {
+                    return Err(BuildError::InvalidSetting(format!(
+                        "the end date {} is before the start date {}",
+                        end_date, start_date
+                    )));
+                }
+                if (days as usize) < already_run {
+                    return Err(BuildError::InvalidSetting(format!(
+                        "the end date {} is before {}, the last day the resumed simulation has already run",
+                        end_date,
+                        start_date.add_days(already_run as i64 - 1)
+                    )));
+                }
+                days as usize
+            }
+        };
+
+        // the seed of this run, and the values of the parameters declared as distributions drawn from it
+        let mut seed = match (self.seed, &self.resume) {
+            (Some(seed), _) => seed,
+            (None, Some(simulation)) => simulation.seed,
+            (None, None) => random_master_seed(),
+        };
+        if let Some(replicate) = self.replicate {
+            seed = replicate_seed(seed, replicate);
+        }
+        let parameters = parameters.draw_distributions(&mut individual_rng(seed, RngStream::Parameters, 0, 0));
+
+        let mut simulation = match self.resume.take() {
+            Some(mut simulation) => {
+                simulation.time_steps = time_steps;
+                simulation.seed = seed;
+                simulation.set_parameters(parameters);
+                simulation
+            }
+            None => {
+                let catalog = self.chosen_catalog();
+                let demography = self.demography.take().unwrap_or_else(|| Arc::new(Demography::default_demography()));
+                demography.check(&catalog)?;
+                Simulation::with_parameters(self.population_size, time_steps, seed, parameters, catalog, demography)
+            }
+        };
+        simulation.quiet = self.quiet;
+        simulation.compact_dead_every = self.compact_dead_every;
+        for output in self.outputs {
+            simulation.add_output(output);
+        }
+        Ok(simulation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demographic_data_missing_a_region_is_a_build_error() {
+        let mut catalog = Catalog::default_catalog();
+        catalog.regions[0].name = "atlantis".to_string();
+        catalog.regions[0].defaults_from = None;
+        let result = Simulation::builder().catalog(Arc::new(catalog)).population_size(10).seed(1).quiet(true).build();
+        match result {
+            Err(BuildError::InvalidDemography(e)) => assert!(e.to_string().contains("\"atlantis\""), "{}", e),
+            Err(e) => panic!("expected a demography error, got: {}", e),
+            Ok(_) => panic!("expected a demography error"),
+        }
+    }
+}
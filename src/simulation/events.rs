@@ -0,0 +1,366 @@
+// src/simulation/events.rs
+//
+// Individual-level events: what happened to whom and when (an infection acquired, a drug course started, a death,
+// ...), for calibration against line-list data and for following individuals while debugging.
+//
+// apply_rules records the events of one individual for one time step in an `EventBuffer`; the simulation collects
+// them, in individual order, and hands them to every output sink that asks for events (`OutputSink::wants_events`).
+// When no sink does, the buffers discard everything and nothing is collected.  `EventLogWriter` is such a sink: it
+// writes a line list as CSV, optionally restricted to some kinds of event and to a subset of individuals:
+//
+//     date,time_step,individual,event,bacteria,drug,value,detail
+//     1942-01-03,2,417,infection_acquired,escherichia coli,,8,community
+//     1942-01-05,4,417,drug_started,,amoxicillin,10,
+//
+// value and detail depend on the event (see `Event`).
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::config::catalog::Catalog;
+use crate::simulation::output::{csv_field, OutputSink};
+use crate::simulation::population::Region;
+use crate::simulation::simulation::Simulation;
+
+/// Where an infection came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfectionSource {
+    Environment,
+    Hospital,
+    Community,
+}
+
+impl fmt::Display for InfectionSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            InfectionSource::Environment => "environment",
+            InfectionSource::Hospital => "hospital",
+            InfectionSource::Community => "community",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Where resistance emerged: in the infecting bacteria or in the microbiome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResistanceSite {
+    Infection,
+    Microbiome,
+}
+
+impl fmt::Display for ResistanceSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ResistanceSite::Infection => "infection",
+            ResistanceSite::Microbiome => "microbiome",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Something that happened to an individual.  Bacteria and drugs are indices into the bacteria and drugs of the
+/// catalog.  In the line list, `value` is the number given in brackets and `detail` the text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// [syndrome id] infected, from the given source
+    InfectionAcquired { bacteria: usize, source: InfectionSource, syndrome: u32 },
+    /// the bacteria newly present in the microbiome
+    MicrobiomeColonised { bacteria: usize },
+    /// [any_r or microbiome_r level] de novo resistance to the drug, at the given site
+    ResistanceEmerged { bacteria: usize, drug: usize, site: ResistanceSite, level: f64 },
+    /// [initial drug level] a course started; 10 is a standard dose, more a double dose
+    DrugStarted { drug: usize, dose: f64 },
+    /// [drug level at stopping] a course stopped; the level decays from there
+    DrugStopped { drug: usize, level: f64 },
+    /// the infection identified by a test
+    InfectionIdentified { bacteria: usize },
+    /// [test_r] a resistance test result for one drug (one event per drug tested)
+    ResistanceTestResult { bacteria: usize, drug: usize, test_r: f64 },
+    /// sepsis with the bacteria began
+    SepsisOnset { bacteria: usize },
+    /// admitted to hospital
+    Hospitalised,
+    /// discharged from hospital
+    Discharged,
+    /// left home for a visit to the given region
+    TravelStarted { to: Region },
+    /// came home from a visit
+    TravelEnded,
+    /// died of the given cause
+    Died { cause: String },
+}
+
+/// The kinds of event, for filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    InfectionAcquired,
+    MicrobiomeColonised,
+    ResistanceEmerged,
+    DrugStarted,
+    DrugStopped,
+    InfectionIdentified,
+    ResistanceTestResult,
+    SepsisOnset,
+    Hospitalised,
+    Discharged,
+    TravelStarted,
+    TravelEnded,
+    Died,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 13] = [
+        EventKind::InfectionAcquired,
+        EventKind::MicrobiomeColonised,
+        EventKind::ResistanceEmerged,
+        EventKind::DrugStarted,
+        EventKind::DrugStopped,
+        EventKind::InfectionIdentified,
+        EventKind::ResistanceTestResult,
+        EventKind::SepsisOnset,
+        EventKind::Hospitalised,
+        EventKind::Discharged,
+        EventKind::TravelStarted,
+        EventKind::TravelEnded,
+        EventKind::Died,
+    ];
+
+    /// The name used in the line list (and to select kinds on the command line).
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::InfectionAcquired => "infection_acquired",
+            EventKind::MicrobiomeColonised => "microbiome_colonised",
+            EventKind::ResistanceEmerged => "resistance_emerged",
+            EventKind::DrugStarted => "drug_started",
+            EventKind::DrugStopped => "drug_stopped",
+            EventKind::InfectionIdentified => "infection_identified",
+            EventKind::ResistanceTestResult => "test_r_result",
+            EventKind::SepsisOnset => "sepsis_onset",
+            EventKind::Hospitalised => "hospitalised",
+            EventKind::Discharged => "discharged",
+            EventKind::TravelStarted => "travel_started",
+            EventKind::TravelEnded => "travel_ended",
+            EventKind::Died => "died",
+        }
+    }
+
+    /// The kind with the given name.
+    pub fn from_name(name: &str) -> Option<EventKind> {
+        EventKind::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::InfectionAcquired { .. } => EventKind::InfectionAcquired,
+            Event::MicrobiomeColonised { .. } => EventKind::MicrobiomeColonised,
+            Event::ResistanceEmerged { .. } => EventKind::ResistanceEmerged,
+            Event::DrugStarted { .. } => EventKind::DrugStarted,
+            Event::DrugStopped { .. } => EventKind::DrugStopped,
+            Event::InfectionIdentified { .. } => EventKind::InfectionIdentified,
+            Event::ResistanceTestResult { .. } => EventKind::ResistanceTestResult,
+            Event::SepsisOnset { .. } => EventKind::SepsisOnset,
+            Event::Hospitalised => EventKind::Hospitalised,
+            Event::Discharged => EventKind::Discharged,
+            Event::TravelStarted { .. } => EventKind::TravelStarted,
+            Event::TravelEnded => EventKind::TravelEnded,
+            Event::Died { .. } => EventKind::Died,
+        }
+    }
+
+    // (bacteria, drug, value, detail) columns of the line list; region names are those of `catalog`
+    fn columns(&self, catalog: &Catalog) -> (Option<usize>, Option<usize>, Option<f64>, String) {
+        match self {
+            Event::InfectionAcquired { bacteria, source, syndrome } => {
+                (Some(*bacteria), None, Some(*syndrome as f64), source.to_string())
+            }
+            Event::MicrobiomeColonised { bacteria } => (Some(*bacteria), None, None, String::new()),
+            Event::ResistanceEmerged { bacteria, drug, site, level } => {
+                (Some(*bacteria), Some(*drug), Some(*level), site.to_string())
+            }
+            Event::DrugStarted { drug, dose } => (None, Some(*drug), Some(*dose), String::new()),
+            Event::DrugStopped { drug, level } => (None, Some(*drug), Some(*level), String::new()),
+            Event::InfectionIdentified { bacteria } => (Some(*bacteria), None, None, String::new()),
+            Event::ResistanceTestResult { bacteria, drug, test_r } => (Some(*bacteria), Some(*drug), Some(*test_r), String::new()),
+            Event::SepsisOnset { bacteria } => (Some(*bacteria), None, None, String::new()),
+            Event::Hospitalised | Event::Discharged | Event::TravelEnded => (None, None, None, String::new()),
+            Event::TravelStarted { to } => (None, None, None, to.name(catalog).to_string()),
+            Event::Died { cause } => (None, None, None, cause.clone()),
+        }
+    }
+}
+
+/// An event with when it happened and to whom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventRecord {
+    pub time_step: usize,
+    pub individual: usize,
+    pub event: Event,
+}
+
+/// Where apply_rules puts the events of one individual in one time step.  A discarding buffer ignores them, so the
+/// rules cost nothing extra when no output wants events.
+pub struct EventBuffer {
+    recording: bool,
+    events: Vec<Event>,
+}
+
+impl EventBuffer {
+    pub fn recording() -> Self {
+        EventBuffer { recording: true, events: Vec::new() }
+    }
+
+    pub fn discarding() -> Self {
+        EventBuffer { recording: false, events: Vec::new() }
+    }
+
+    pub fn push(&mut self, event: Event) {
+        if self.recording {
+            self.events.push(event);
+        }
+    }
+
+    pub fn into_events(self) -> Vec<Event> {
+        self.events
+    }
+}
+
+/// Output sink writing the events as a CSV line list (see the top of this file).
+pub struct EventLogWriter<W: Write + Send> {
+    out: W,
+    kinds: Option<HashSet<EventKind>>,
+    individuals: Option<HashSet<usize>>,
+}
+
+impl<W: Write + Send> EventLogWriter<W> {
+    /// A line list of every event of every individual.
+    pub fn new(out: W) -> Self {
+        EventLogWriter { out, kinds: None, individuals: None }
+    }
+
+    /// Only write events of these kinds.
+    pub fn kinds<I: IntoIterator<Item = EventKind>>(mut self, kinds: I) -> Self {
+        self.kinds = Some(kinds.into_iter().collect());
+        self
+    }
+
+    /// Only write events of the individuals with these ids.
+    pub fn individuals<I: IntoIterator<Item = usize>>(mut self, ids: I) -> Self {
+        self.individuals = Some(ids.into_iter().collect());
+        self
+    }
+
+    fn selected(&self, record: &EventRecord) -> bool {
+        self.kinds.as_ref().is_none_or(|kinds| kinds.contains(&record.event.kind()))
+            && self.individuals.as_ref().is_none_or(|ids| ids.contains(&record.individual))
+    }
+}
+
+impl<W: Write + Send> OutputSink for EventLogWriter<W> {
+    fn wants_events(&self) -> bool {
+        true
+    }
+
+    fn start(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        writeln!(self.out, "date,time_step,individual,event,bacteria,drug,value,detail")
+    }
+
+    fn events(&mut self, simulation: &Simulation, time_step: usize, events: &[EventRecord]) -> io::Result<()> {
+        let date = simulation.date_at(time_step);
+        let catalog = &simulation.catalog;
+        for record in events {
+            if !self.selected(record) {
+                continue;
+            }
+            let (bacteria, drug, value, detail) = record.event.columns(catalog);
+            writeln!(
+                self.out,
+                "{},{},{},{},{},{},{},{}",
+                date,
+                record.time_step,
+                record.individual,
+                record.event.kind(),
+                csv_field(bacteria.map_or("", |b| catalog.bacteria[b].name.as_str())),
+                csv_field(drug.map_or("", |d| catalog.drugs[d].name.as_str())),
+                value.map_or(String::new(), |v| v.to_string()),
+                csv_field(&detail)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(event: Event) -> (Option<usize>, Option<usize>, Option<f64>, String) {
+        event.columns(&Catalog::default_catalog())
+    }
+
+    #[test]
+    fn each_event_fills_its_own_columns() {
+        assert_eq!(
+            columns(Event::InfectionAcquired { bacteria: 2, source: InfectionSource::Community, syndrome: 8 }),
+            (Some(2), None, Some(8.0), "community".to_string())
+        );
+        assert_eq!(columns(Event::MicrobiomeColonised { bacteria: 5 }), (Some(5), None, None, String::new()));
+        assert_eq!(
+            columns(Event::ResistanceEmerged { bacteria: 1, drug: 3, site: ResistanceSite::Microbiome, level: 0.5 }),
+            (Some(1), Some(3), Some(0.5), "microbiome".to_string())
+        );
+        assert_eq!(columns(Event::DrugStarted { drug: 4, dose: 20.0 }), (None, Some(4), Some(20.0), String::new()));
+        // the level the drug was at when stopped, from which it decays
+        assert_eq!(columns(Event::DrugStopped { drug: 4, level: 3.25 }), (None, Some(4), Some(3.25), String::new()));
+        assert_eq!(columns(Event::InfectionIdentified { bacteria: 0 }), (Some(0), None, None, String::new()));
+        assert_eq!(
+            columns(Event::ResistanceTestResult { bacteria: 0, drug: 7, test_r: 0.25 }),
+            (Some(0), Some(7), Some(0.25), String::new())
+        );
+        assert_eq!(columns(Event::SepsisOnset { bacteria: 9 }), (Some(9), None, None, String::new()));
+        for event in [Event::Hospitalised, Event::Discharged, Event::TravelEnded] {
+            assert_eq!(columns(event), (None, None, None, String::new()));
+        }
+        let africa = Region::from_name(&Catalog::default_catalog(), "africa").unwrap();
+        assert_eq!(columns(Event::TravelStarted { to: africa }), (None, None, None, "africa".to_string()));
+        assert_eq!(
+            columns(Event::Died { cause: "sepsis_death".to_string() }),
+            (None, None, None, "sepsis_death".to_string())
+        );
+    }
+
+    #[test]
+    fn event_kinds_are_found_by_their_names() {
+        for kind in EventKind::ALL {
+            assert_eq!(EventKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(EventKind::from_name("drug_start"), None);
+        assert_eq!(Event::ResistanceTestResult { bacteria: 0, drug: 0, test_r: 0.0 }.kind().name(), "test_r_result");
+    }
+
+    #[test]
+    fn the_log_keeps_only_the_kinds_and_individuals_asked_for() {
+        let record = |individual: usize, event: Event| EventRecord { time_step: 0, individual, event };
+        let everything = EventLogWriter::new(io::sink());
+        assert!(everything.selected(&record(3, Event::Hospitalised)));
+
+        let some = EventLogWriter::new(io::sink()).kinds([EventKind::DrugStarted, EventKind::Died]).individuals([3, 8]);
+        assert!(some.selected(&record(3, Event::Died { cause: "background_mortality".to_string() })));
+        assert!(some.selected(&record(8, Event::DrugStarted { drug: 0, dose: 10.0 })));
+        assert!(!some.selected(&record(4, Event::DrugStarted { drug: 0, dose: 10.0 })));
+        assert!(!some.selected(&record(3, Event::Hospitalised)));
+    }
+}
@@ -1,2 +1,11 @@
+pub mod builder; // SimulationBuilder, for creating a simulation from code
+pub mod events; // individual-level events recorded by the rules, and the line-list output
+pub mod output; // the OutputSink trait through which a running simulation reports results
 pub mod population;
-pub mod simulation; 
+pub mod rng; // per-individual, per-time-step random number streams derived from a master seed
+#[allow(clippy::module_inception)]
+pub mod simulation;
+pub mod snapshot; // saving a simulation to a file and restoring it, to resume or branch a run
+pub mod summary; // drug use and resistance by drug class and subclass
+pub mod timeseries; // counts over time by region, age band and sex, written as CSV
+pub mod trace; // full daily state of a chosen cohort of individuals, written as JSON lines
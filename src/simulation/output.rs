@@ -0,0 +1,60 @@
+// src/simulation/output.rs
+//
+// Output sinks: where a running simulation sends its results.
+//
+// A sink is handed the whole simulation once before the first time step, after every time step and once at the
+// end, and writes whatever it reports (a console summary, a file per run, ...).  Sinks that ask for them are also
+// handed the individual-level events of each time step (see events.rs).  Sinks are added with
+// `SimulationBuilder::output` or `Simulation::add_output` and are called in the order they were added.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+
+use crate::simulation::events::EventRecord;
+use crate::simulation::simulation::Simulation;
+
+/// Something that records the results of a simulation as it runs.
+pub trait OutputSink: Send {
+    /// Called once, before the first time step.
+    fn start(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Whether the sink wants the individual-level events; they are only recorded if some sink does.
+    fn wants_events(&self) -> bool {
+        false
+    }
+
+    /// Called with the events of each time step, in individual order, before `step` (only if `wants_events`).
+    fn events(&mut self, _simulation: &Simulation, _time_step: usize, _events: &[EventRecord]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called after each time step has been applied to every individual.
+    fn step(&mut self, _simulation: &Simulation, _time_step: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once, after the last time step.
+    fn finish(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Adds the counts of `b` to those of `a`, e.g. to reduce the counts of a parallel pass over the population.
+pub(crate) fn merge_counts<K: Eq + Hash>(mut a: HashMap<K, u64>, b: HashMap<K, u64>) -> HashMap<K, u64> {
+    for (key, count) in b {
+        *a.entry(key).or_insert(0) += count;
+    }
+    a
+}
+
+// Quotes a CSV field if it contains a comma or a quote.
+pub(crate) fn csv_field(text: &str) -> String {
+    if text.contains(',') || text.contains('"') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
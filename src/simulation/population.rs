@@ -1,302 +1,1246 @@
 // src/simulation/population.rs
+//
+// The population is stored as a structure of arrays: one contiguous column per field of an individual, instead of
+// one struct per individual holding a dozen heap vectors and a bacteria × drug resistance matrix of its own.
+//
+//   - single-valued fields (age, region, hospital status, contact levels, ...) are one Vec each, one entry per row;
+//   - per-bacteria and per-drug numbers (level, immune response, drug level, dates) are one Vec each of
+//     rows × bacteria (or drugs), so that each individual's values are a slice;
+//   - per-bacteria and per-drug flags (sepsis, carriage, vaccination, drug use, ...) are bitsets of one bit per
+//     bacteria or drug, in as many u64 words per row as that takes (one for up to 64, two for up to 128, ...);
+//   - resistances are held sparsely, one `ResistanceRows` per row: a row of `Resistance`s (f32 levels, one per
+//     drug) only for each bacteria the individual is infected with or carries in its microbiome.  Everyone else's
+//     resistance is all zeros, which is what reading the row of a bacteria without one gives.
+//
+// With the default catalog (30 bacteria, 42 drugs) an individual takes about 1.5 KB, and 840 bytes more for each
+// bacteria present; the full 30 × 42 resistance matrix took about 25 KB.  The rules and the outputs go through the
+// rows held (`Resistances::iter`), so that looking at resistance costs as much as the infections and carriage there
+// are, not the full matrix for everybody every day.
+//
+// Individuals are read and updated through views of one row.  `Individual` (from `get`, `iter` and `par_iter`) has
+// the single-valued fields copied and the rest as slices; `IndividualMut` (from `iter_mut` and `par_iter_mut`), which
+// apply_rules works on, has a mutable reference to each field that can change, so that a field is read as
+// `*individual.age` and a flag set with `individual.sepsis.set(b_idx, true)`.
+
 use rand::Rng;
-use rand::distributions::{Distribution, Standard};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand_distr::Poisson;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::fmt; 
+use std::ops::Index;
+use serde::{Deserialize, Serialize};
+use crate::config::catalog::Catalog;
+use crate::config::demography::Demography;
+use crate::config::schedule::CalendarDate;
+use crate::simulation::rng::{individual_rng, RngStream};
 
 
-/*
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HospitalStatus {
+    InHospital,  // consider in future whether to have a variable for whether in icu
+    NotInHospital,
+}
 
-pub const BACTERIA_LIST: &[&str] = &[
-    "acinetobacter baumannii" 
-];
+impl HospitalStatus {
+    pub fn is_hospitalized(&self) -> bool {
+        matches!(self, HospitalStatus::InHospital)
+    }
+}
 
- 
+/// A region from the catalog (see config/catalog.rs), by its index there, or `Region::HOME`: where someone is when
+/// they are not travelling, which stands for the region they live in (see `resolve`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Region(u16);
 
-pub const DRUG_SHORT_NAMES: &[&str] = &[  // see below for classes and sub-classes
-    "penicilling", "ampicillin", "amoxicillin",
-];
+impl Region {
+    /// The region someone lives in, as their current region while they are not travelling.
+    pub const HOME: Region = Region(u16::MAX);
 
-*/
+    /// The region at this index of the catalog.
+    pub fn from_index(index: usize) -> Region {
+        debug_assert!(index < Region::HOME.0 as usize);
+        Region(index as u16)
+    }
 
+    /// The index of the region in the catalog, by which per-region tables are indexed.  Not for `HOME`, which has
+    /// to be resolved first.
+    pub fn index(self) -> usize {
+        debug_assert!(self != Region::HOME, "Region::HOME has no index; resolve it to the region lived in");
+        self.0 as usize
+    }
 
-pub const BACTERIA_LIST: &[&str] = &[
-    "acinetobacter baumannii", "citrobacter spp.", "enterobacter spp.", "enterococcus faecalis", 
-    "enterococcus faecium", "escherichia coli", "klebsiella pneumoniae", "morganella spp.", 
-    "proteus spp.", "serratia spp.", "pseudomonas aeruginosa", "staphylococcus aureus", 
-    "streptococcus pneumoniae", "salmonella enterica serovar typhi", 
-    "salmonella enterica serovar paratyphi a", "invasive non-typhoidal salmonella spp.", 
-    "shigella spp.", "neisseria gonorrhoeae", "streptococcus pyogenes", "streptococcus agalactiae", 
-    "haemophilus influenzae", "chlamydia trachomatis", "vibrio cholerae",
-    "neisseria_meningitidis", "listeria_monocytogenes", "clostridioides_difficile",
-    "campylobacter_jejuni", "enterobacter_cloacae", "yersinia_enterocolitica", "moraxella_catarrhalis"
-];
+    /// The region with this name in `catalog`.
+    pub fn from_name(catalog: &Catalog, name: &str) -> Option<Region> {
+        catalog.regions.iter().position(|r| r.name == name).map(Region::from_index)
+    }
 
+    /// Every region of `catalog`, in catalog order (not `HOME`).
+    pub fn all(catalog: &Catalog) -> impl Iterator<Item = Region> {
+        (0..catalog.regions.len()).map(Region::from_index)
+    }
 
-pub const DRUG_SHORT_NAMES: &[&str] = &[  // see below for classes and sub-classes
-    "penicilling", "ampicillin", "amoxicillin",
-    "piperacillin", "ticarcillin", "cephalexin", "cefazolin",
-    "cefuroxime", "ceftriaxone", "ceftazidime", "cefepime", "ceftaroline", "meropenem", "imipenem_c",
-    "ertapenem", "aztreonam", "erythromycin", "azithromycin", "clarithromycin", "clindamycin",
-    "gentamicin", "tobramycin", "amikacin", "ciprofloxacin", "levofloxacin", "moxifloxacin",
-    "ofloxacin", "tetracycline", "doxyclycline", "minocycline", "vancomycin", "teicoplanin",
-    "linezolid", "tedizolid", "quinu_dalfo", "trim_sulf", "chlorampheni", "nitrofurantoin",
-    "retapamulin", "fusidic_a", "metronidazole", "furazolidone"
-];
+    /// The name of the region in `catalog`, or "home" for `HOME`.
+    pub fn name(self, catalog: &Catalog) -> &str {
+        if self == Region::HOME {
+            "home"
+        } else {
+            &catalog.regions[self.index()].name
+        }
+    }
 
+    /// The geographic region this stands for, for someone living in `region_living`: `HOME` is the region lived
+    /// in, and any other region itself.
+    pub fn resolve(self, region_living: Region) -> Region {
+        if self == Region::HOME { region_living } else { self }
+    }
+}
 
 
+/// The resistance of one bacteria to one drug in one individual.  The levels are kept as f32: they lie between 0
+/// and a few units (activity_r) and f32 holds them to about 7 significant digits, which halves the largest part of
+/// an individual's state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Resistance {
+    pub microbiome_r: f32,
+    pub test_r: f32,
+    pub activity_r: f32,
+    pub any_r: f32, // Effective Resistance in minority or majority (0-1)
+    pub majority_r: f32, // Resistance in majority of bacteria infected with (0-1) - when majority_r is non zero 
+                         // it will always take the same value as any_r
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum HospitalStatus {
-    InHospital,  // consider in future whether to have a variable for whether in icu
-    NotInHospital,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Sex {
+    Male = 0, // `sex as usize` indexes the per-sex tables (life tables, mortality multipliers), male first
+    Female = 1,
 }
 
-impl HospitalStatus {
-    pub fn is_hospitalized(&self) -> bool {
-        matches!(self, HospitalStatus::InHospital)
+impl Sex {
+    /// "male" or "female", as sex at birth is written in parameter keys and outputs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sex::Male => "male",
+            Sex::Female => "female",
+        }
+    }
+}
+
+impl fmt::Display for Sex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Flags, one per bacteria or per drug, packed into the bits of a few words.  `flags[i]` reads a flag as `bool`.
+#[derive(Debug, Clone, Copy)]
+pub struct Flags<'a> {
+    words: &'a [u64],
+    len: usize,
+}
+
+impl<'a> Flags<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "flag {} out of range ({} flags)", i, self.len);
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Whether any flag is set.
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|&word| word != 0)
+    }
+
+    /// The number of flags set.
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + 'a {
+        let flags = *self;
+        (0..self.len).map(move |i| flags.get(i))
+    }
+}
+
+impl Index<usize> for Flags<'_> {
+    type Output = bool;
+
+    fn index(&self, i: usize) -> &bool {
+        if self.get(i) { &true } else { &false }
     }
 }
 
-// Add Display to the derive attribute and implement it
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Region {
-    NorthAmerica,
-    SouthAmerica,
-    Africa,
-    Asia,
-    Europe,
-    Oceania,
-    Home, // This represents the individual's home region, which could be any of the above.
-}
-
-impl Distribution<Region> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Region {
-        match rng.gen_range(0..6) { // 0 to 5 for the 6 geographic regions
-            0 => Region::NorthAmerica,
-            1 => Region::SouthAmerica,
-            2 => Region::Africa,
-            3 => Region::Asia,
-            4 => Region::Europe,
-            _ => Region::Oceania, // Default for 5
+/// Flags that can be changed (see `Flags`): `flags.set(i, true)`.
+#[derive(Debug)]
+pub struct FlagsMut<'a> {
+    words: &'a mut [u64],
+    len: usize,
+}
+
+impl FlagsMut<'_> {
+    pub fn as_flags(&self) -> Flags<'_> {
+        Flags { words: self.words, len: self.len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.as_flags().get(i)
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.len, "flag {} out of range ({} flags)", i, self.len);
+        if value {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
         }
     }
+
+    pub fn any(&self) -> bool {
+        self.as_flags().any()
+    }
+
+    pub fn count(&self) -> usize {
+        self.as_flags().count()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        self.as_flags().iter()
+    }
 }
 
-// Implement the Display trait for Region
-impl fmt::Display for Region {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let region_str = match self {
-            Region::NorthAmerica => "north_america",
-            Region::SouthAmerica => "south_america",
-            Region::Africa => "africa",
-            Region::Asia => "asia",
-            Region::Europe => "europe",
-            Region::Oceania => "oceania",
-            Region::Home => "home",
+impl Index<usize> for FlagsMut<'_> {
+    type Output = bool;
+
+    fn index(&self, i: usize) -> &bool {
+        if self.get(i) { &true } else { &false }
+    }
+}
+
+/// An individual's resistance state, held only for some bacteria: a row of `Resistance`s (one per drug) for each
+/// bacteria infecting it or carried in its microbiome, and for a bacteria just cleared until its levels are back
+/// to zero.  A bacteria without a row has no resistance to any drug.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResistanceRows {
+    bacteria: Vec<u16>,      // the bacteria with a row, in index order
+    values: Vec<Resistance>, // [position in bacteria * num_drugs + drug]
+}
+
+impl ResistanceRows {
+    fn position(&self, b_idx: usize) -> Result<usize, usize> {
+        self.bacteria.binary_search(&(b_idx as u16))
+    }
+
+    fn row(&self, position: usize, num_drugs: usize) -> &[Resistance] {
+        &self.values[position * num_drugs..(position + 1) * num_drugs]
+    }
+
+    fn row_mut(&mut self, position: usize, num_drugs: usize) -> &mut [Resistance] {
+        &mut self.values[position * num_drugs..(position + 1) * num_drugs]
+    }
+}
+
+/// An individual's resistance matrix: `resistances[b_idx]` is the row of a bacteria, indexed by drug (all zeros
+/// for a bacteria without a row, see `ResistanceRows`).
+#[derive(Debug, Clone, Copy)]
+pub struct Resistances<'a> {
+    rows: &'a ResistanceRows,
+    no_resistance: &'a [Resistance], // the row of a bacteria without one, all zeros; one entry per drug
+}
+
+impl<'a> Resistances<'a> {
+    /// The row of a bacteria, if it has one.
+    pub fn get(&self, b_idx: usize) -> Option<&'a [Resistance]> {
+        let position = self.rows.position(b_idx).ok()?;
+        Some(self.rows.row(position, self.no_resistance.len()))
+    }
+
+    /// The bacteria with a row and their rows, in bacteria order.  Every other bacteria has no resistance.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &'a [Resistance])> + 'a {
+        let bacteria = self.rows.bacteria.iter().map(|&b_idx| b_idx as usize);
+        bacteria.zip(self.rows.values.chunks_exact(self.no_resistance.len()))
+    }
+}
+
+impl Index<usize> for Resistances<'_> {
+    type Output = [Resistance];
+
+    fn index(&self, b_idx: usize) -> &[Resistance] {
+        self.get(b_idx).unwrap_or(self.no_resistance)
+    }
+}
+
+/// An individual's resistance matrix, which can be changed (see `Resistances`).  A row is added by `row_mut` when
+/// a bacteria first needs one, and dropped by `release` once it is all zeros again.
+#[derive(Debug)]
+pub struct ResistancesMut<'a> {
+    rows: &'a mut ResistanceRows,
+    no_resistance: &'a [Resistance], // as in `Resistances`
+}
+
+impl ResistancesMut<'_> {
+    pub fn as_resistances(&self) -> Resistances<'_> {
+        Resistances { rows: self.rows, no_resistance: self.no_resistance }
+    }
+
+    pub fn get(&self, b_idx: usize) -> Option<&[Resistance]> {
+        let position = self.rows.position(b_idx).ok()?;
+        Some(self.rows.row(position, self.no_resistance.len()))
+    }
+
+    /// The row of a bacteria to be changed, if it has one (a bacteria without one has nothing to reset).
+    pub fn get_mut(&mut self, b_idx: usize) -> Option<&mut [Resistance]> {
+        let position = self.rows.position(b_idx).ok()?;
+        Some(self.rows.row_mut(position, self.no_resistance.len()))
+    }
+
+    /// The row of a bacteria to be changed, added (all zeros) if it has none.
+    pub fn row_mut(&mut self, b_idx: usize) -> &mut [Resistance] {
+        let d = self.no_resistance.len();
+        let position = match self.rows.position(b_idx) {
+            Ok(position) => position,
+            Err(position) => {
+                self.rows.bacteria.insert(position, b_idx as u16);
+                self.rows.values.splice(position * d..position * d, std::iter::repeat_n(Resistance::default(), d));
+                position
+            }
         };
-        write!(f, "{}", region_str)
+        self.rows.row_mut(position, d)
+    }
+
+    /// Drops the row of a bacteria if it is all zeros, so that it takes no space and no time until it has some
+    /// resistance again.
+    pub fn release(&mut self, b_idx: usize) {
+        let d = self.no_resistance.len();
+        if let Ok(position) = self.rows.position(b_idx) {
+            if self.rows.row(position, d).iter().all(|r| *r == Resistance::default()) {
+                self.rows.bacteria.remove(position);
+                self.rows.values.drain(position * d..(position + 1) * d);
+            }
+        }
+    }
+
+    /// The bacteria with a row and their rows, in bacteria order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &[Resistance])> + '_ {
+        self.as_resistances().iter()
     }
 }
 
+impl Index<usize> for ResistancesMut<'_> {
+    type Output = [Resistance];
 
-#[derive(Debug, Clone)]
-pub struct Resistance {
-    pub microbiome_r: f64,
-    pub test_r: f64,
-    pub activity_r: f64,
-    pub any_r: f64, // Effective Resistance in minority or majority (0-1)
-    pub majority_r: f64, // Resistance in majority of bacteria infected with (0-1) - when majority_r is non zero 
-                         // it will always take the same value as any_r
+    fn index(&self, b_idx: usize) -> &[Resistance] {
+        self.get(b_idx).unwrap_or(self.no_resistance)
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct Individual {
+/// One individual, read from the population (`Population::get`, `iter`, `par_iter`): a copy of the single-valued
+/// fields, and the per-bacteria and per-drug fields as slices of the population's columns.
+#[derive(Debug, Clone, Copy)]
+pub struct Individual<'a> {
     pub id: usize,
-    pub age: i32, // age in days (negative = not yet born date)
-    pub sex_at_birth: String,
+    pub age: i32, // age in days
+    pub sex_at_birth: Sex,
     pub region_living: Region,
     pub region_cur_in: Region,
-    pub days_visiting: u32, 
+    pub days_visiting: u32,
     pub hospital_status: HospitalStatus,
-    pub days_hospitalized: u32, 
-    pub date_last_infected: Vec<i32>,              
-    pub infectious_syndrome: Vec<i32>,             
-    pub level: Vec<f64>,
-
-    pub immune_resp: Vec<f64>,                     
-    pub sepsis: Vec<bool>,                         
-    pub presence_microbiome: Vec<bool>,            
-    pub vaccination_status: Vec<bool>,             
-    pub cur_infection_from_environment: Vec<bool>, 
-    pub test_identified_infection: Vec<bool>,      
-    pub cur_use_drug: Vec<bool>,
-    pub cur_level_drug: Vec<f64>,  // standard level is 10 for a day on which a standard dose is taken / administered 
-    pub date_drug_initiated: Vec<i32>, // the time_step when each drug was last initiated
-    pub ever_taken_drug: Vec<bool>,
+    pub days_hospitalized: u32,
+    pub date_last_infected: &'a [i32],
+    pub infectious_syndrome: &'a [i32],
+    pub level: &'a [f64],
+    pub immune_resp: &'a [f64],
+    pub sepsis: Flags<'a>,
+    pub presence_microbiome: Flags<'a>,
+    pub vaccination_status: Flags<'a>,
+    pub cur_infection_from_environment: Flags<'a>,
+    pub test_identified_infection: Flags<'a>,
+    pub cur_use_drug: Flags<'a>,
+    pub cur_level_drug: &'a [f64], // standard level is 10 for a day on which a standard dose is taken / administered 
+    pub date_drug_initiated: &'a [i32], // the time_step when each drug was last initiated
+    pub ever_taken_drug: Flags<'a>,
     pub current_infection_related_death_risk: f64,
-    pub background_all_cause_mortality_rate: f64,  
+    pub background_all_cause_mortality_rate: f64,
     pub sexual_contact_level: f64,
     pub airborne_contact_level_with_adults: f64,
     pub airborne_contact_level_with_children: f64,
     pub oral_exposure_level: f64,
     pub mosquito_exposure_level: f64,
-    pub infection_hospital_acquired: Vec<bool>,    
+    pub infection_hospital_acquired: Flags<'a>,
     pub current_toxicity: f64,
-    pub mortality_risk_current_toxicity: f64, 
-    pub resistances: Vec<Vec<Resistance>>,
+    pub mortality_risk_current_toxicity: f64,
+    pub resistances: Resistances<'a>,
     pub date_of_death: Option<usize>,
-    pub cause_of_death: Option<String>,
-    pub is_severely_immunosuppressed: bool, 
-
-}
-
-impl Individual {
-    pub fn new(id: usize, age_days: i32, sex_at_birth: String) -> Self {
-        let mut rng = rand::thread_rng();
-        let num_bacteria = BACTERIA_LIST.len();
-        let num_drugs = DRUG_SHORT_NAMES.len();
-
-        let date_last_infected = vec![0; num_bacteria];
-        let infectious_syndrome = vec![0; num_bacteria];
-        let level = vec![0.0; num_bacteria];
-        let immune_resp = vec![0.0001; num_bacteria];
-        let sepsis = vec![false; num_bacteria];
-        let presence_microbiome = vec![false; num_bacteria];
-        let infection_hospital_acquired = vec![false; num_bacteria];
-        let cur_infection_from_environment = vec![false; num_bacteria];
-        let test_identified_infection = vec![false; num_bacteria];
-        let vaccination_status = (0..num_bacteria).map(|_| rng.gen_bool(0.5)).collect();
-
-        let mut resistances = Vec::with_capacity(num_bacteria);
-        for _ in 0..num_bacteria {
-            let mut drug_resistances = Vec::with_capacity(num_drugs);
-            for _ in 0..num_drugs {
-                drug_resistances.push(Resistance {
-                    microbiome_r: 0.0,
-                    test_r: 0.0,
-                    activity_r: 0.0,
-                    any_r: 0.0,
-                    majority_r: 0.0,
-                });
+    pub cause_of_death: Option<&'a str>,
+    pub is_severely_immunosuppressed: bool,
+}
+
+impl Individual<'_> {
+    /// The geographic region the individual is in: the region visited while travelling, else the region lived in.
+    pub fn region_present(&self) -> Region {
+        self.region_cur_in.resolve(self.region_living)
+    }
+}
+
+/// One individual, to be updated (`Population::iter_mut`, `par_iter_mut`; apply_rules): the fields that can change
+/// as references into the population's columns, and those fixed for life (id, sex, home region) as copies.
+#[derive(Debug)]
+pub struct IndividualMut<'a> {
+    pub id: usize,
+    pub age: &'a mut i32, // age in days
+    pub sex_at_birth: Sex,
+    pub region_living: Region,
+    pub region_cur_in: &'a mut Region,
+    pub days_visiting: &'a mut u32,
+    pub hospital_status: &'a mut HospitalStatus,
+    pub days_hospitalized: &'a mut u32,
+    pub date_last_infected: &'a mut [i32],
+    pub infectious_syndrome: &'a mut [i32],
+    pub level: &'a mut [f64],
+    pub immune_resp: &'a mut [f64],
+    pub sepsis: FlagsMut<'a>,
+    pub presence_microbiome: FlagsMut<'a>,
+    pub vaccination_status: FlagsMut<'a>,
+    pub cur_infection_from_environment: FlagsMut<'a>,
+    pub test_identified_infection: FlagsMut<'a>,
+    pub cur_use_drug: FlagsMut<'a>,
+    pub cur_level_drug: &'a mut [f64],
+    pub date_drug_initiated: &'a mut [i32],
+    pub ever_taken_drug: FlagsMut<'a>,
+    pub current_infection_related_death_risk: &'a mut f64,
+    pub background_all_cause_mortality_rate: &'a mut f64,
+    pub sexual_contact_level: &'a mut f64,
+    pub airborne_contact_level_with_adults: &'a mut f64,
+    pub airborne_contact_level_with_children: &'a mut f64,
+    pub oral_exposure_level: &'a mut f64,
+    pub mosquito_exposure_level: &'a mut f64,
+    pub infection_hospital_acquired: FlagsMut<'a>,
+    pub current_toxicity: &'a mut f64,
+    pub mortality_risk_current_toxicity: &'a mut f64,
+    pub resistances: ResistancesMut<'a>,
+    pub date_of_death: &'a mut Option<usize>,
+    pub cause_of_death: &'a mut Option<String>,
+    pub is_severely_immunosuppressed: &'a mut bool,
+}
+
+impl IndividualMut<'_> {
+    /// The geographic region the individual is in: the region visited while travelling, else the region lived in.
+    pub fn region_present(&self) -> Region {
+        self.region_cur_in.resolve(self.region_living)
+    }
+}
+
+// Rows handed to each rayon task by `Population::par_iter_mut`.
+const ROWS_PER_CHUNK: usize = 1024;
+
+/// The individuals of a simulation, stored field by field (see the top of this file).  Rows are positions in the
+/// columns; an individual's `id` is kept in a column of its own.
+#[derive(Serialize, Deserialize)]
+pub struct Population {
+    num_bacteria: usize,
+    num_drugs: usize,
+    num_regions: usize,
+    no_resistance: Vec<Resistance>, // num_drugs zeros: the resistance row of a bacteria without one
+    // one value per individual
+    id: Vec<usize>,
+    age: Vec<i32>,
+    sex_at_birth: Vec<Sex>,
+    region_living: Vec<Region>,
+    region_cur_in: Vec<Region>,
+    days_visiting: Vec<u32>,
+    hospital_status: Vec<HospitalStatus>,
+    days_hospitalized: Vec<u32>,
+    current_infection_related_death_risk: Vec<f64>,
+    background_all_cause_mortality_rate: Vec<f64>,
+    sexual_contact_level: Vec<f64>,
+    airborne_contact_level_with_adults: Vec<f64>,
+    airborne_contact_level_with_children: Vec<f64>,
+    oral_exposure_level: Vec<f64>,
+    mosquito_exposure_level: Vec<f64>,
+    current_toxicity: Vec<f64>,
+    mortality_risk_current_toxicity: Vec<f64>,
+    date_of_death: Vec<Option<usize>>,
+    cause_of_death: Vec<Option<String>>,
+    is_severely_immunosuppressed: Vec<bool>,
+    // num_bacteria values per individual
+    date_last_infected: Vec<i32>,
+    infectious_syndrome: Vec<i32>,
+    level: Vec<f64>,
+    immune_resp: Vec<f64>,
+    // num_drugs values per individual
+    cur_level_drug: Vec<f64>,
+    date_drug_initiated: Vec<i32>,
+    // flag_words(num_bacteria) words per individual
+    sepsis: Vec<u64>,
+    presence_microbiome: Vec<u64>,
+    vaccination_status: Vec<u64>,
+    cur_infection_from_environment: Vec<u64>,
+    test_identified_infection: Vec<u64>,
+    infection_hospital_acquired: Vec<u64>,
+    // flag_words(num_drugs) words per individual
+    cur_use_drug: Vec<u64>,
+    ever_taken_drug: Vec<u64>,
+    // one entry per individual, holding rows for only some bacteria
+    resistances: Vec<ResistanceRows>,
+    // the id of the next individual added
+    next_id: usize,
+    // deaths by cause of the rows removed by `compact`
+    compacted_deaths: BTreeMap<String, usize>,
+}
+
+// An age group and sex of a pyramid: start and span of the ages, in years, and the sex.
+type PyramidCell = (u32, u32, Sex);
+
+// Keeps the rows of a column (of `width` values per row) whose `keep` entry is true, in order.
+fn retain_rows<T: Copy>(column: &mut Vec<T>, width: usize, keep: &[bool]) {
+    let mut kept = 0;
+    for (row, _) in keep.iter().enumerate().filter(|(_, &kept)| kept) {
+        if kept != row {
+            column.copy_within(row * width..(row + 1) * width, kept * width);
+        }
+        kept += 1;
+    }
+    column.truncate(kept * width);
+}
+
+// The words holding `flags` flags.
+fn flag_words(flags: usize) -> usize {
+    flags.div_ceil(64)
+}
+
+impl Population {
+    /// A population of `size` individuals alive on `start_date`, each set up from its own random stream derived from
+    /// `master_seed`.  Where people live follows the region shares and their age and sex the region's pyramid for
+    /// the start year in `demography`, which must have passed `Demography::check` against `catalog` (as
+    /// `SimulationBuilder::build` makes sure).  Those born later are added by `add_births` as the run goes.
+    pub fn new(
+        size: usize,
+        start_date: CalendarDate,
+        master_seed: u64,
+        catalog: &Catalog,
+        demography: &Demography,
+    ) -> Self {
+        let start_year = start_date.fractional_year();
+        let regions = WeightedIndex::new(demography.region_shares(catalog, start_year))
+            .expect("the catalog checks the region shares");
+        // per region, the (start year, span in years, sex) cells of the pyramid and their weights
+        let pyramids: Vec<(WeightedIndex<f64>, Vec<PyramidCell>)> = Region::all(catalog)
+            .map(|region| {
+                let pyramid = demography.pyramid(catalog, region.name(catalog), start_date.year);
+                let mut weights = Vec::new();
+                let mut cells = Vec::new();
+                for group in &pyramid.groups {
+                    for (sex, people) in [(Sex::Male, group.male), (Sex::Female, group.female)] {
+                        weights.push(people);
+                        cells.push((group.start_years, group.span_years, sex));
+                    }
+                }
+                (WeightedIndex::new(weights).expect("the demographic data check the pyramids"), cells)
+            })
+            .collect();
+
+        let mut population = Population::empty(catalog);
+        population.reserve(size);
+        for i in 0..size {
+            let mut rng = individual_rng(master_seed, RngStream::Initialisation, i, 0);
+            let region = Region::from_index(regions.sample(&mut rng));
+            let (weights, cells) = &pyramids[region.index()];
+            let (start_years, span_years, sex) = cells[weights.sample(&mut rng)];
+            let age = (start_years * 365 + rng.gen_range(0..span_years * 365)) as i32;
+            population.push(i, age, sex, region, &mut rng);
+        }
+
+        population
+    }
+
+    /// Adds the births of the day `date` (time step `time_step`): in each region, a number drawn from a Poisson
+    /// distribution with mean the living population there times the region's crude birth rate for the date (per
+    /// day), each newborn living in the region of its parents with its sex drawn by the sex ratio at birth.  The
+    /// counts and sexes come from a random stream per region and time step, and the newborns' starting state from
+    /// their own stream as in `new`, so births do not depend on the number of threads.  Returns the number born.
+    pub fn add_births(
+        &mut self,
+        date: CalendarDate,
+        time_step: usize,
+        master_seed: u64,
+        catalog: &Catalog,
+        demography: &Demography,
+    ) -> usize {
+        let year = date.fractional_year();
+        let living = self.living_by_region();
+        let mut born = 0;
+        for region in Region::all(catalog) {
+            let indicators = demography.indicators(catalog, region.name(catalog), year);
+            let expected = living[region.index()] as f64 * indicators.crude_birth_rate / 1000.0 / 365.0;
+            if expected <= 0.0 {
+                continue;
             }
-            resistances.push(drug_resistances);
+            let mut rng = individual_rng(master_seed, RngStream::Births, region.index(), time_step);
+            let births = Poisson::new(expected).expect("a positive mean").sample(&mut rng) as usize;
+            self.reserve(births);
+            for _ in 0..births {
+                let sex = if rng.gen_bool(indicators.male_birth_probability()) { Sex::Male } else { Sex::Female };
+                let id = self.next_id;
+                let mut individual_rng = individual_rng(master_seed, RngStream::Initialisation, id, 0);
+                self.push(id, 0, sex, region, &mut individual_rng);
+            }
+            born += births;
         }
+        born
+    }
 
-        let background_all_cause_mortality_rate = if age_days < 0 {
-            0.0
-        } else {
-            0.000001
-        };
+    /// The number of people alive living in each region.
+    pub fn living_by_region(&self) -> Vec<usize> {
+        let mut living = vec![0; self.num_regions];
+        for row in 0..self.len() {
+            if self.date_of_death[row].is_none() {
+                living[self.region_living[row].index()] += 1;
+            }
+        }
+        living
+    }
+
+    /// Removes the rows of the dead, keeping the rest in order, so that a long run does not carry everyone who ever
+    /// died.  Their causes of death are kept in a tally (see `deaths_by_cause`); anything else about them is gone,
+    /// so outputs must have counted their deaths by then (outputs count deaths on the day).  Returns the number
+    /// removed.
+    pub fn compact(&mut self) -> usize {
+        let keep: Vec<bool> = self.date_of_death.iter().map(Option::is_none).collect();
+        let removed = keep.iter().filter(|&&kept| !kept).count();
+        if removed == 0 {
+            return 0;
+        }
+        for (cause, &kept) in self.cause_of_death.iter().zip(&keep) {
+            if let (false, Some(cause)) = (kept, cause) {
+                *self.compacted_deaths.entry(cause.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let (b, d) = (self.num_bacteria, self.num_drugs);
+        let (bw, dw) = (flag_words(b), flag_words(d));
+        retain_rows(&mut self.id, 1, &keep);
+        retain_rows(&mut self.age, 1, &keep);
+        retain_rows(&mut self.sex_at_birth, 1, &keep);
+        retain_rows(&mut self.region_living, 1, &keep);
+        retain_rows(&mut self.region_cur_in, 1, &keep);
+        retain_rows(&mut self.days_visiting, 1, &keep);
+        retain_rows(&mut self.hospital_status, 1, &keep);
+        retain_rows(&mut self.days_hospitalized, 1, &keep);
+        retain_rows(&mut self.current_infection_related_death_risk, 1, &keep);
+        retain_rows(&mut self.background_all_cause_mortality_rate, 1, &keep);
+        retain_rows(&mut self.sexual_contact_level, 1, &keep);
+        retain_rows(&mut self.airborne_contact_level_with_adults, 1, &keep);
+        retain_rows(&mut self.airborne_contact_level_with_children, 1, &keep);
+        retain_rows(&mut self.oral_exposure_level, 1, &keep);
+        retain_rows(&mut self.mosquito_exposure_level, 1, &keep);
+        retain_rows(&mut self.current_toxicity, 1, &keep);
+        retain_rows(&mut self.mortality_risk_current_toxicity, 1, &keep);
+        retain_rows(&mut self.date_of_death, 1, &keep);
+        retain_rows(&mut self.is_severely_immunosuppressed, 1, &keep);
+        retain_rows(&mut self.date_last_infected, b, &keep);
+        retain_rows(&mut self.infectious_syndrome, b, &keep);
+        retain_rows(&mut self.level, b, &keep);
+        retain_rows(&mut self.immune_resp, b, &keep);
+        retain_rows(&mut self.cur_level_drug, d, &keep);
+        retain_rows(&mut self.date_drug_initiated, d, &keep);
+        for flags in [
+            &mut self.sepsis,
+            &mut self.presence_microbiome,
+            &mut self.vaccination_status,
+            &mut self.cur_infection_from_environment,
+            &mut self.test_identified_infection,
+            &mut self.infection_hospital_acquired,
+        ] {
+            retain_rows(flags, bw, &keep);
+        }
+        retain_rows(&mut self.cur_use_drug, dw, &keep);
+        retain_rows(&mut self.ever_taken_drug, dw, &keep);
+        // the columns that are not Copy
+        let mut kept = keep.iter();
+        self.cause_of_death.retain(|_| *kept.next().unwrap());
+        let mut kept = keep.iter();
+        self.resistances.retain(|_| *kept.next().unwrap());
+        removed
+    }
+
+    /// Deaths by cause over the whole run: those of the rows held and those removed by `compact`.
+    pub fn deaths_by_cause(&self) -> BTreeMap<&str, usize> {
+        let mut deaths: BTreeMap<&str, usize> =
+            self.compacted_deaths.iter().map(|(cause, &count)| (cause.as_str(), count)).collect();
+        for cause in self.cause_of_death.iter().flatten() {
+            *deaths.entry(cause.as_str()).or_insert(0) += 1;
+        }
+        deaths
+    }
+
+    /// The id the next individual added gets: one more than the highest so far, whatever has been compacted away.
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
 
+    /// A population with nobody in it, sized for the bacteria, drugs and regions of `catalog`.
+    pub fn empty(catalog: &Catalog) -> Self {
+        let num_drugs = catalog.drugs.len();
+        Population {
+            num_bacteria: catalog.bacteria.len(),
+            num_drugs,
+            num_regions: catalog.regions.len(),
+            no_resistance: vec![Resistance::default(); num_drugs],
+            id: Vec::new(),
+            age: Vec::new(),
+            sex_at_birth: Vec::new(),
+            region_living: Vec::new(),
+            region_cur_in: Vec::new(),
+            days_visiting: Vec::new(),
+            hospital_status: Vec::new(),
+            days_hospitalized: Vec::new(),
+            current_infection_related_death_risk: Vec::new(),
+            background_all_cause_mortality_rate: Vec::new(),
+            sexual_contact_level: Vec::new(),
+            airborne_contact_level_with_adults: Vec::new(),
+            airborne_contact_level_with_children: Vec::new(),
+            oral_exposure_level: Vec::new(),
+            mosquito_exposure_level: Vec::new(),
+            current_toxicity: Vec::new(),
+            mortality_risk_current_toxicity: Vec::new(),
+            date_of_death: Vec::new(),
+            cause_of_death: Vec::new(),
+            is_severely_immunosuppressed: Vec::new(),
+            date_last_infected: Vec::new(),
+            infectious_syndrome: Vec::new(),
+            level: Vec::new(),
+            immune_resp: Vec::new(),
+            cur_level_drug: Vec::new(),
+            date_drug_initiated: Vec::new(),
+            sepsis: Vec::new(),
+            presence_microbiome: Vec::new(),
+            vaccination_status: Vec::new(),
+            cur_infection_from_environment: Vec::new(),
+            test_identified_infection: Vec::new(),
+            infection_hospital_acquired: Vec::new(),
+            cur_use_drug: Vec::new(),
+            ever_taken_drug: Vec::new(),
+            resistances: Vec::new(),
+            next_id: 0,
+            compacted_deaths: BTreeMap::new(),
+        }
+    }
 
+    /// Makes room for `additional` more individuals, so that pushing them does not move the columns again.
+    pub fn reserve(&mut self, additional: usize) {
+        let (b, d) = (self.num_bacteria, self.num_drugs);
+        let (bw, dw) = (flag_words(b), flag_words(d));
+        self.id.reserve(additional);
+        self.age.reserve(additional);
+        self.sex_at_birth.reserve(additional);
+        self.region_living.reserve(additional);
+        self.region_cur_in.reserve(additional);
+        self.days_visiting.reserve(additional);
+        self.hospital_status.reserve(additional);
+        self.days_hospitalized.reserve(additional);
+        self.current_infection_related_death_risk.reserve(additional);
+        self.background_all_cause_mortality_rate.reserve(additional);
+        self.sexual_contact_level.reserve(additional);
+        self.airborne_contact_level_with_adults.reserve(additional);
+        self.airborne_contact_level_with_children.reserve(additional);
+        self.oral_exposure_level.reserve(additional);
+        self.mosquito_exposure_level.reserve(additional);
+        self.current_toxicity.reserve(additional);
+        self.mortality_risk_current_toxicity.reserve(additional);
+        self.date_of_death.reserve(additional);
+        self.cause_of_death.reserve(additional);
+        self.is_severely_immunosuppressed.reserve(additional);
+        self.date_last_infected.reserve(additional * b);
+        self.infectious_syndrome.reserve(additional * b);
+        self.level.reserve(additional * b);
+        self.immune_resp.reserve(additional * b);
+        self.cur_level_drug.reserve(additional * d);
+        self.date_drug_initiated.reserve(additional * d);
+        for flags in [
+            &mut self.sepsis,
+            &mut self.presence_microbiome,
+            &mut self.vaccination_status,
+            &mut self.cur_infection_from_environment,
+            &mut self.test_identified_infection,
+            &mut self.infection_hospital_acquired,
+        ] {
+            flags.reserve(additional * bw);
+        }
+        self.cur_use_drug.reserve(additional * dw);
+        self.ever_taken_drug.reserve(additional * dw);
+        self.resistances.reserve(additional);
+    }
+
+    /// Adds an individual of this age (in days), sex and region lived in, with the rest of its starting state drawn
+    /// from `rng`.
+    pub fn push<R: Rng>(&mut self, id: usize, age_days: i32, sex_at_birth: Sex, region_living: Region, rng: &mut R) {
+        let (b, d) = (self.num_bacteria, self.num_drugs);
+        let (bw, dw) = (flag_words(b), flag_words(d));
+
+        self.id.push(id);
+        self.next_id = self.next_id.max(id + 1);
+        self.age.push(age_days);
+        self.sex_at_birth.push(sex_at_birth);
+        let row = self.vaccination_status.len();
+        self.vaccination_status.resize(row + bw, 0);
+        let mut vaccination_status = FlagsMut { words: &mut self.vaccination_status[row..], len: b };
+        for b_idx in 0..b {
+            vaccination_status.set(b_idx, rng.gen_bool(0.5));
+        }
+        self.region_living.push(region_living);
+        self.region_cur_in.push(Region::HOME);
+        self.days_visiting.push(0);
+        self.hospital_status.push(HospitalStatus::NotInHospital);
+        self.days_hospitalized.push(0);
+        self.current_infection_related_death_risk.push(0.0);
+        self.background_all_cause_mortality_rate.push(0.000001);
+        self.sexual_contact_level.push(rng.gen_range(0.0..=10.0));
+        self.airborne_contact_level_with_adults.push(rng.gen_range(0.0..=10.0));
+        self.airborne_contact_level_with_children.push(rng.gen_range(0.0..=10.0));
+        self.oral_exposure_level.push(rng.gen_range(0.0..=10.0));
+        self.mosquito_exposure_level.push(rng.gen_range(0.0..=10.0));
+        self.current_toxicity.push(rng.gen_range(0.0..=3.0));
+        self.mortality_risk_current_toxicity.push(0.0); // todo: probably should be removed as this death risk is implemented with separate logic
+        self.date_of_death.push(None);
+        self.cause_of_death.push(None);
+        self.is_severely_immunosuppressed.push(false);
+
+        self.date_last_infected.extend(std::iter::repeat_n(0, b));
+        self.infectious_syndrome.extend(std::iter::repeat_n(0, b));
+        self.level.extend(std::iter::repeat_n(0.0, b));
+        self.immune_resp.extend(std::iter::repeat_n(0.0001, b));
+        self.cur_level_drug.extend(std::iter::repeat_n(0.0, d));
+        self.date_drug_initiated.extend(std::iter::repeat_n(i32::MIN, d));
+        for flags in [
+            &mut self.sepsis,
+            &mut self.presence_microbiome,
+            &mut self.cur_infection_from_environment,
+            &mut self.test_identified_infection,
+            &mut self.infection_hospital_acquired,
+        ] {
+            flags.extend(std::iter::repeat_n(0, bw));
+        }
+        self.cur_use_drug.extend(std::iter::repeat_n(0, dw));
+        self.ever_taken_drug.extend(std::iter::repeat_n(0, dw));
+        self.resistances.push(ResistanceRows::default());
+    }
+
+    /// The number of individuals (living or dead).
+    pub fn len(&self) -> usize {
+        self.id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty()
+    }
+
+    /// The individual in a row.
+    pub fn get(&self, row: usize) -> Individual<'_> {
+        let (b, d) = (self.num_bacteria, self.num_drugs);
+        let span = |width: usize| row * width..(row + 1) * width;
         Individual {
+            id: self.id[row],
+            age: self.age[row],
+            sex_at_birth: self.sex_at_birth[row],
+            region_living: self.region_living[row],
+            region_cur_in: self.region_cur_in[row],
+            days_visiting: self.days_visiting[row],
+            hospital_status: self.hospital_status[row],
+            days_hospitalized: self.days_hospitalized[row],
+            date_last_infected: &self.date_last_infected[span(b)],
+            infectious_syndrome: &self.infectious_syndrome[span(b)],
+            level: &self.level[span(b)],
+            immune_resp: &self.immune_resp[span(b)],
+            sepsis: Flags { words: &self.sepsis[span(flag_words(b))], len: b },
+            presence_microbiome: Flags { words: &self.presence_microbiome[span(flag_words(b))], len: b },
+            vaccination_status: Flags { words: &self.vaccination_status[span(flag_words(b))], len: b },
+            cur_infection_from_environment: Flags { words: &self.cur_infection_from_environment[span(flag_words(b))], len: b },
+            test_identified_infection: Flags { words: &self.test_identified_infection[span(flag_words(b))], len: b },
+            cur_use_drug: Flags { words: &self.cur_use_drug[span(flag_words(d))], len: d },
+            cur_level_drug: &self.cur_level_drug[span(d)],
+            date_drug_initiated: &self.date_drug_initiated[span(d)],
+            ever_taken_drug: Flags { words: &self.ever_taken_drug[span(flag_words(d))], len: d },
+            current_infection_related_death_risk: self.current_infection_related_death_risk[row],
+            background_all_cause_mortality_rate: self.background_all_cause_mortality_rate[row],
+            sexual_contact_level: self.sexual_contact_level[row],
+            airborne_contact_level_with_adults: self.airborne_contact_level_with_adults[row],
+            airborne_contact_level_with_children: self.airborne_contact_level_with_children[row],
+            oral_exposure_level: self.oral_exposure_level[row],
+            mosquito_exposure_level: self.mosquito_exposure_level[row],
+            infection_hospital_acquired: Flags { words: &self.infection_hospital_acquired[span(flag_words(b))], len: b },
+            current_toxicity: self.current_toxicity[row],
+            mortality_risk_current_toxicity: self.mortality_risk_current_toxicity[row],
+            resistances: Resistances { rows: &self.resistances[row], no_resistance: &self.no_resistance },
+            date_of_death: self.date_of_death[row],
+            cause_of_death: self.cause_of_death[row].as_deref(),
+            is_severely_immunosuppressed: self.is_severely_immunosuppressed[row],
+        }
+    }
+
+    /// Every individual, in row order.
+    pub fn iter(&self) -> impl Iterator<Item = Individual<'_>> + '_ {
+        (0..self.len()).map(move |row| self.get(row))
+    }
+
+    /// Every individual, shared out between the rayon threads.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = Individual<'_>> + '_ {
+        (0..self.len()).into_par_iter().map(move |row| self.get(row))
+    }
+
+    /// Every individual, to be updated, in row order.
+    pub fn iter_mut(&mut self) -> RowsMut<'_> {
+        let (num_bacteria, num_drugs) = (self.num_bacteria, self.num_drugs);
+        RowsMut {
+            num_bacteria,
+            num_drugs,
+            no_resistance: &self.no_resistance,
+            id: &self.id,
+            age: &mut self.age,
+            sex_at_birth: &self.sex_at_birth,
+            region_living: &self.region_living,
+            region_cur_in: &mut self.region_cur_in,
+            days_visiting: &mut self.days_visiting,
+            hospital_status: &mut self.hospital_status,
+            days_hospitalized: &mut self.days_hospitalized,
+            current_infection_related_death_risk: &mut self.current_infection_related_death_risk,
+            background_all_cause_mortality_rate: &mut self.background_all_cause_mortality_rate,
+            sexual_contact_level: &mut self.sexual_contact_level,
+            airborne_contact_level_with_adults: &mut self.airborne_contact_level_with_adults,
+            airborne_contact_level_with_children: &mut self.airborne_contact_level_with_children,
+            oral_exposure_level: &mut self.oral_exposure_level,
+            mosquito_exposure_level: &mut self.mosquito_exposure_level,
+            current_toxicity: &mut self.current_toxicity,
+            mortality_risk_current_toxicity: &mut self.mortality_risk_current_toxicity,
+            date_of_death: &mut self.date_of_death,
+            cause_of_death: &mut self.cause_of_death,
+            is_severely_immunosuppressed: &mut self.is_severely_immunosuppressed,
+            date_last_infected: &mut self.date_last_infected,
+            infectious_syndrome: &mut self.infectious_syndrome,
+            level: &mut self.level,
+            immune_resp: &mut self.immune_resp,
+            cur_level_drug: &mut self.cur_level_drug,
+            date_drug_initiated: &mut self.date_drug_initiated,
+            sepsis: &mut self.sepsis,
+            presence_microbiome: &mut self.presence_microbiome,
+            vaccination_status: &mut self.vaccination_status,
+            cur_infection_from_environment: &mut self.cur_infection_from_environment,
+            test_identified_infection: &mut self.test_identified_infection,
+            infection_hospital_acquired: &mut self.infection_hospital_acquired,
+            cur_use_drug: &mut self.cur_use_drug,
+            ever_taken_drug: &mut self.ever_taken_drug,
+            resistances: &mut self.resistances,
+        }
+    }
+
+    /// Every individual, to be updated, shared out between the rayon threads in chunks of consecutive rows.
+    /// Collecting from it keeps row order.
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = IndividualMut<'_>> + '_ {
+        let mut rows = self.iter_mut();
+        let mut chunks = Vec::with_capacity(rows.len().div_ceil(ROWS_PER_CHUNK));
+        while !rows.id.is_empty() {
+            let size = rows.len().min(ROWS_PER_CHUNK);
+            chunks.push(rows.take_front(size));
+        }
+        chunks.into_par_iter().flat_map_iter(|chunk| chunk)
+    }
+}
+
+/// Consecutive rows of the population, yielding each individual to be updated (see `Population::iter_mut`).
+#[derive(Default)]
+pub struct RowsMut<'a> {
+    num_bacteria: usize,
+    num_drugs: usize,
+    no_resistance: &'a [Resistance],
+    id: &'a [usize],
+    age: &'a mut [i32],
+    sex_at_birth: &'a [Sex],
+    region_living: &'a [Region],
+    region_cur_in: &'a mut [Region],
+    days_visiting: &'a mut [u32],
+    hospital_status: &'a mut [HospitalStatus],
+    days_hospitalized: &'a mut [u32],
+    current_infection_related_death_risk: &'a mut [f64],
+    background_all_cause_mortality_rate: &'a mut [f64],
+    sexual_contact_level: &'a mut [f64],
+    airborne_contact_level_with_adults: &'a mut [f64],
+    airborne_contact_level_with_children: &'a mut [f64],
+    oral_exposure_level: &'a mut [f64],
+    mosquito_exposure_level: &'a mut [f64],
+    current_toxicity: &'a mut [f64],
+    mortality_risk_current_toxicity: &'a mut [f64],
+    date_of_death: &'a mut [Option<usize>],
+    cause_of_death: &'a mut [Option<String>],
+    is_severely_immunosuppressed: &'a mut [bool],
+    date_last_infected: &'a mut [i32],
+    infectious_syndrome: &'a mut [i32],
+    level: &'a mut [f64],
+    immune_resp: &'a mut [f64],
+    cur_level_drug: &'a mut [f64],
+    date_drug_initiated: &'a mut [i32],
+    sepsis: &'a mut [u64],
+    presence_microbiome: &'a mut [u64],
+    vaccination_status: &'a mut [u64],
+    cur_infection_from_environment: &'a mut [u64],
+    test_identified_infection: &'a mut [u64],
+    infection_hospital_acquired: &'a mut [u64],
+    cur_use_drug: &'a mut [u64],
+    ever_taken_drug: &'a mut [u64],
+    resistances: &'a mut [ResistanceRows],
+}
+
+// Takes the first `n` values off the front of a column.
+fn front<'a, T>(column: &mut &'a mut [T], n: usize) -> &'a mut [T] {
+    let (front, rest) = std::mem::take(column).split_at_mut(n);
+    *column = rest;
+    front
+}
+
+impl<'a> RowsMut<'a> {
+    // Takes the first `n` rows off the front.
+    fn take_front(&mut self, n: usize) -> RowsMut<'a> {
+        let (b, d) = (self.num_bacteria, self.num_drugs);
+        let (bw, dw) = (flag_words(b), flag_words(d));
+        let (id, rest) = self.id.split_at(n);
+        self.id = rest;
+        let (sex_at_birth, rest) = self.sex_at_birth.split_at(n);
+        self.sex_at_birth = rest;
+        let (region_living, rest) = self.region_living.split_at(n);
+        self.region_living = rest;
+        RowsMut {
+            num_bacteria: b,
+            num_drugs: d,
+            no_resistance: self.no_resistance,
             id,
-            age: age_days,
-            region_living: rng.gen(), 
-            region_cur_in: Region::Home, 
-            days_visiting: 0, 
-            hospital_status: HospitalStatus::NotInHospital, 
-            days_hospitalized: 0, 
+            age: front(&mut self.age, n),
             sex_at_birth,
-            date_last_infected,
-            infectious_syndrome,
-            level, 
-            immune_resp,
-            sepsis,
-            presence_microbiome,
-            vaccination_status, 
-            cur_use_drug: vec![false; num_drugs],
-            cur_level_drug: vec![0.0; num_drugs],
-            date_drug_initiated: vec![i32::MIN; num_drugs], 
-            ever_taken_drug: vec![false; num_drugs],
-            current_infection_related_death_risk: 0.0,
-            background_all_cause_mortality_rate,  
-            sexual_contact_level: rng.gen_range(0.0..=10.0),
-            airborne_contact_level_with_adults: rng.gen_range(0.0..=10.0),
-            airborne_contact_level_with_children: rng.gen_range(0.0..=10.0),
-            oral_exposure_level: rng.gen_range(0.0..=10.0),
-            mosquito_exposure_level: rng.gen_range(0.0..=10.0),
-            infection_hospital_acquired,
-            cur_infection_from_environment,
-            test_identified_infection,
-            current_toxicity: rng.gen_range(0.0..=3.0),
-            mortality_risk_current_toxicity: 0.0, // todo: probably should be removed as this death risk is implemented with separate logic
-            resistances,
-            date_of_death: None,
-            cause_of_death: None,
-            is_severely_immunosuppressed: false, 
+            region_living,
+            region_cur_in: front(&mut self.region_cur_in, n),
+            days_visiting: front(&mut self.days_visiting, n),
+            hospital_status: front(&mut self.hospital_status, n),
+            days_hospitalized: front(&mut self.days_hospitalized, n),
+            current_infection_related_death_risk: front(&mut self.current_infection_related_death_risk, n),
+            background_all_cause_mortality_rate: front(&mut self.background_all_cause_mortality_rate, n),
+            sexual_contact_level: front(&mut self.sexual_contact_level, n),
+            airborne_contact_level_with_adults: front(&mut self.airborne_contact_level_with_adults, n),
+            airborne_contact_level_with_children: front(&mut self.airborne_contact_level_with_children, n),
+            oral_exposure_level: front(&mut self.oral_exposure_level, n),
+            mosquito_exposure_level: front(&mut self.mosquito_exposure_level, n),
+            current_toxicity: front(&mut self.current_toxicity, n),
+            mortality_risk_current_toxicity: front(&mut self.mortality_risk_current_toxicity, n),
+            date_of_death: front(&mut self.date_of_death, n),
+            cause_of_death: front(&mut self.cause_of_death, n),
+            is_severely_immunosuppressed: front(&mut self.is_severely_immunosuppressed, n),
+            date_last_infected: front(&mut self.date_last_infected, n * b),
+            infectious_syndrome: front(&mut self.infectious_syndrome, n * b),
+            level: front(&mut self.level, n * b),
+            immune_resp: front(&mut self.immune_resp, n * b),
+            cur_level_drug: front(&mut self.cur_level_drug, n * d),
+            date_drug_initiated: front(&mut self.date_drug_initiated, n * d),
+            sepsis: front(&mut self.sepsis, n * bw),
+            presence_microbiome: front(&mut self.presence_microbiome, n * bw),
+            vaccination_status: front(&mut self.vaccination_status, n * bw),
+            cur_infection_from_environment: front(&mut self.cur_infection_from_environment, n * bw),
+            test_identified_infection: front(&mut self.test_identified_infection, n * bw),
+            infection_hospital_acquired: front(&mut self.infection_hospital_acquired, n * bw),
+            cur_use_drug: front(&mut self.cur_use_drug, n * dw),
+            ever_taken_drug: front(&mut self.ever_taken_drug, n * dw),
+            resistances: front(&mut self.resistances, n),
         }
     }
 }
 
-pub struct Population {
-    pub individuals: Vec<Individual>,
+impl<'a> Iterator for RowsMut<'a> {
+    type Item = IndividualMut<'a>;
+
+    fn next(&mut self) -> Option<IndividualMut<'a>> {
+        if self.id.is_empty() {
+            return None;
+        }
+        let (b, d) = (self.num_bacteria, self.num_drugs);
+        let row = self.take_front(1);
+        Some(IndividualMut {
+            id: row.id[0],
+            age: &mut row.age[0],
+            sex_at_birth: row.sex_at_birth[0],
+            region_living: row.region_living[0],
+            region_cur_in: &mut row.region_cur_in[0],
+            days_visiting: &mut row.days_visiting[0],
+            hospital_status: &mut row.hospital_status[0],
+            days_hospitalized: &mut row.days_hospitalized[0],
+            date_last_infected: row.date_last_infected,
+            infectious_syndrome: row.infectious_syndrome,
+            level: row.level,
+            immune_resp: row.immune_resp,
+            sepsis: FlagsMut { words: row.sepsis, len: b },
+            presence_microbiome: FlagsMut { words: row.presence_microbiome, len: b },
+            vaccination_status: FlagsMut { words: row.vaccination_status, len: b },
+            cur_infection_from_environment: FlagsMut { words: row.cur_infection_from_environment, len: b },
+            test_identified_infection: FlagsMut { words: row.test_identified_infection, len: b },
+            cur_use_drug: FlagsMut { words: row.cur_use_drug, len: d },
+            cur_level_drug: row.cur_level_drug,
+            date_drug_initiated: row.date_drug_initiated,
+            ever_taken_drug: FlagsMut { words: row.ever_taken_drug, len: d },
+            current_infection_related_death_risk: &mut row.current_infection_related_death_risk[0],
+            background_all_cause_mortality_rate: &mut row.background_all_cause_mortality_rate[0],
+            sexual_contact_level: &mut row.sexual_contact_level[0],
+            airborne_contact_level_with_adults: &mut row.airborne_contact_level_with_adults[0],
+            airborne_contact_level_with_children: &mut row.airborne_contact_level_with_children[0],
+            oral_exposure_level: &mut row.oral_exposure_level[0],
+            mosquito_exposure_level: &mut row.mosquito_exposure_level[0],
+            infection_hospital_acquired: FlagsMut { words: row.infection_hospital_acquired, len: b },
+            current_toxicity: &mut row.current_toxicity[0],
+            mortality_risk_current_toxicity: &mut row.mortality_risk_current_toxicity[0],
+            resistances: ResistancesMut { rows: &mut row.resistances[0], no_resistance: row.no_resistance },
+            date_of_death: &mut row.date_of_death[0],
+            cause_of_death: &mut row.cause_of_death[0],
+            is_severely_immunosuppressed: &mut row.is_severely_immunosuppressed[0],
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.id.len(), Some(self.id.len()))
+    }
 }
 
+impl ExactSizeIterator for RowsMut<'_> {}
 
-impl Population {
-    pub fn new(size: usize) -> Self {
-        let mut individuals = Vec::with_capacity(size);
-        let mut rng = rand::thread_rng();
-        for i in 0..size {
-//          let age = rng.gen_range(-36500..=36500); // Age range from 0 to 100 years in days - will need to change 0 to -36500
-            let age = rng.gen_range(-36500..=36500); // Age range from 0 to 100 years in days - will need to change 0 to -36500
-            let sex = if rng.gen_bool(0.5) { "male".to_string() } else { "female".to_string() };
-            individuals.push(Individual::new(i, age, sex));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_across_word_boundaries() {
+        let mut words = vec![0u64; flag_words(130)];
+        assert_eq!(words.len(), 3);
+        let mut flags = FlagsMut { words: &mut words, len: 130 };
+        for i in [0, 63, 64, 127, 128, 129] {
+            flags.set(i, true);
         }
-        Population { individuals }
-    }
-}
-
-
-/* 
-
-Drug	        Subclass
-
-penicilling	    Penicillin (β‑lactam, natural penicillins)
-ampicillin	    Aminopenicillin
-amoxicillin	    Aminopenicillin
-piperacillin	Extended‑spectrum penicillin
-ticarcillin	    Extended‑spectrum penicillin
-cephalexin	    Cephalosporin (1st gen)
-cefazolin	    Cephalosporin (1st gen)
-cefuroxime	    Cephalosporin (2nd gen) 
-ceftriaxone	    Cephalosporin (3rd gen)
-ceftazidime	    Cephalosporin (3rd gen)
-cefepime	    Cephalosporin (4th gen)
-ceftaroline	    Cephalosporin (5th gen)
-meropenem	    Carbapenem
-imipenem_c	    Carbapenem
-ertapenem	    Carbapenem
-aztreonam	    Monobactam
-erythromycin	Macrolide
-azithromycin	Macrolide
-clarithromycin	Macrolide
-clindamycin	    Lincosamide
-gentamicin	    Aminoglycoside
-tobramycin	    Aminoglycoside
-amikacin	    Aminoglycoside
-ciprofloxacin	Fluoroquinolone
-levofloxacin	Fluoroquinolone
-moxifloxacin	Fluoroquinolone
-ofloxacin	    Fluoroquinolone
-tetracycline	Tetracycline
-doxycycline	    Tetracycline (semi‑synthetic)
-minocycline	    Tetracycline (semi‑synthetic)
-vancomycin	    Glycopeptide
-teicoplanin	    Glycopeptide
-linezolid	    Oxazolidinone
-tedizolid	    Oxazolidinone
-quinu_dalfo     
-(quinupristin
-/dalfopristin)	Streptogramin
-trim_sulf       Folate pathway inhibitor (sulfonamide + trimethoprim)
-chloramphenicol	Amphenicol
-nitrofurantoin	Nitrofuran
-retapamulin	    Pleuromutilin
-fusidic_a 
-(fusidic acid)	Steroid‑like antibiotic
-metronidazole	Nitroimidazole
-
-*/
\ No newline at end of file
+        assert_eq!(flags.count(), 6);
+        assert!(flags[63] && flags[64] && flags[129]);
+        assert!(!flags[1] && !flags[62] && !flags[65]);
+
+        flags.set(64, false);
+        flags.set(64, false); // clearing a clear flag changes nothing
+        flags.set(0, true); // nor setting a set one
+        assert!(!flags[64] && flags[63]);
+        assert_eq!(flags.count(), 5);
+        let set: Vec<usize> = flags.iter().enumerate().filter(|(_, on)| *on).map(|(i, _)| i).collect();
+        assert_eq!(set, vec![0, 63, 127, 128, 129]);
+        assert_eq!(words, vec![1 | 1 << 63, 1 << 63, 0b11]);
+
+        let flags = Flags { words: &words, len: 130 };
+        assert!(flags.any());
+        assert_eq!(flags.iter().count(), 130);
+        assert!(!Flags { words: &[0, 0], len: 100 }.any());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn flags_past_the_end_are_refused() {
+        // the last word has room for more, but they are not flags
+        let mut words = vec![0u64; 2];
+        FlagsMut { words: &mut words, len: 70 }.set(70, true);
+    }
+
+    fn resistance(level: f32) -> Resistance {
+        Resistance { any_r: level, ..Resistance::default() }
+    }
+
+    #[test]
+    fn resistance_rows_are_added_released_and_reused() {
+        let mut rows = ResistanceRows::default();
+        let mut resistances = ResistancesMut { rows: &mut rows, no_resistance: &[Resistance::default(); 3] };
+        assert!(resistances.get(5).is_none());
+        assert_eq!(resistances[5], [Resistance::default(); 3]); // no row reads as no resistance
+
+        resistances.row_mut(5)[1] = resistance(0.5);
+        resistances.row_mut(2)[0] = resistance(0.25); // goes in front of 5
+        resistances.row_mut(9)[2] = resistance(1.0); // and this after it
+        let bacteria: Vec<usize> = resistances.iter().map(|(b_idx, _)| b_idx).collect();
+        assert_eq!(bacteria, vec![2, 5, 9]);
+        assert_eq!(resistances[2][0].any_r, 0.25);
+        assert_eq!(resistances[5][1].any_r, 0.5);
+        assert_eq!(resistances[9][2].any_r, 1.0);
+        assert_eq!(resistances[5][0], Resistance::default());
+        assert!(resistances.get_mut(7).is_none()); // get_mut does not add a row
+
+        // a row with some resistance left is kept
+        resistances.release(5);
+        assert!(resistances.get(5).is_some());
+        // once back to zero it goes, and the rows around it are untouched
+        resistances.row_mut(5)[1] = Resistance::default();
+        resistances.release(5);
+        resistances.release(7); // no row: nothing to do
+        assert!(resistances.get(5).is_none());
+        assert_eq!(resistances.iter().map(|(b_idx, _)| b_idx).collect::<Vec<_>>(), vec![2, 9]);
+        assert_eq!(resistances[2][0].any_r, 0.25);
+        assert_eq!(resistances[9][2].any_r, 1.0);
+
+        // a row added again starts from zero
+        assert_eq!(resistances.row_mut(5), [Resistance::default(); 3]);
+        assert_eq!(rows.bacteria, vec![2, 5, 9]);
+        assert_eq!(rows.values.len(), 9);
+    }
+
+    #[test]
+    fn retain_rows_keeps_whole_rows_in_order() {
+        let mut column: Vec<u32> = (0..12).collect(); // 4 rows of 3
+        retain_rows(&mut column, 3, &[false, true, false, true]);
+        assert_eq!(column, vec![3, 4, 5, 9, 10, 11]);
+        retain_rows(&mut column, 3, &[true, true]);
+        assert_eq!(column, vec![3, 4, 5, 9, 10, 11]);
+        retain_rows(&mut column, 3, &[false, false]);
+        assert!(column.is_empty());
+    }
+
+    #[test]
+    fn compact_keeps_every_column_aligned() {
+        let (catalog, demography) = (Catalog::default_catalog(), Demography::default_demography());
+        let mut population = Population::new(200, CalendarDate::from_ymd(2000, 1, 1).unwrap(), 7, &catalog, &demography);
+        let (b, d) = (population.num_bacteria, population.num_drugs);
+        // give each individual values of its own in every kind of column, and let every third die
+        for mut individual in population.iter_mut() {
+            let id = individual.id;
+            *individual.current_toxicity = id as f64;
+            individual.level[id % b] = 1.0 + id as f64;
+            individual.cur_level_drug[d - 1] = id as f64;
+            individual.sepsis.set(b - 1, id % 2 == 0);
+            individual.cur_use_drug.set(d - 1 - id % d, true);
+            individual.resistances.row_mut(id % b)[id % d].any_r = id as f32;
+            if id % 3 == 0 {
+                *individual.date_of_death = Some(0);
+                *individual.cause_of_death = Some(if id % 2 == 0 { "even" } else { "odd" }.to_string());
+            }
+        }
+        let survivors: Vec<String> =
+            population.iter().filter(|individual| individual.id % 3 != 0).map(|individual| format!("{:?}", individual)).collect();
+        let deaths_before = population.deaths_by_cause().into_iter().map(|(cause, n)| (cause.to_string(), n)).collect::<Vec<_>>();
+        let next_id = population.next_id();
+
+        assert_eq!(population.compact(), 67);
+        assert_eq!(population.len(), 133);
+        let after: Vec<String> = population.iter().map(|individual| format!("{:?}", individual)).collect();
+        assert_eq!(after, survivors);
+        // the deaths are still counted, and new ids carry on from the old ones
+        let deaths_after = population.deaths_by_cause().into_iter().map(|(cause, n)| (cause.to_string(), n)).collect::<Vec<_>>();
+        assert_eq!(deaths_after, deaths_before);
+        assert_eq!(deaths_after, vec![("even".to_string(), 34), ("odd".to_string(), 33)]);
+        assert_eq!(population.next_id(), next_id);
+        assert_eq!(population.compact(), 0);
+    }
+}
@@ -0,0 +1,53 @@
+// src/simulation/rng.rs
+//
+// Reproducible random numbers.
+//
+// Every random draw in a run comes from a master seed held by `Simulation`.  Rather than one generator shared (and
+// raced over) by the rayon threads, each individual gets its own ChaCha generator for each use: the key is made
+// from the master seed and the kind of use, the stream number is the individual's id, and the position in the
+// stream is set from the time step.  Which numbers an individual sees therefore depends only on (seed, use, id,
+// time step), never on the order in which threads reach it, so a run is bit-identical for any thread count.
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The generator used throughout the model.
+pub type SimRng = ChaCha8Rng;
+
+/// The separate uses of randomness, each with its own key so they never share numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    Initialisation = 1, // setting up each individual in Population::new
+    TimeStep = 2,       // apply_rules for each individual and time step
+    Tracing = 3,        // drawing the random sample of individuals to trace (trace.rs)
+    Fitting = 4,        // the parameter sets and run seeds of a fit (calibration/abc.rs), by generation
+    Design = 5,         // the points of a sensitivity analysis design (analysis/sensitivity.rs)
+    Replicate = 6,      // the seeds of the replicates of a scenario, by replicate number
+    Parameters = 7,     // the values of parameters declared as distributions (config/distribution.rs)
+    Births = 8,         // the number and sex of the births in each region, by time step (Population::add_births)
+}
+
+// Words of stream reserved for each time step (2^32 u32 draws, far more than one individual uses in a step).
+const WORDS_PER_TIME_STEP_SHIFT: u32 = 32;
+
+/// The generator for one individual at one time step (use time step 0 for one-off uses such as initialisation).
+pub fn individual_rng(master_seed: u64, stream: RngStream, id: usize, time_step: usize) -> SimRng {
+    let mut key = [0u8; 32];
+    key[..8].copy_from_slice(&master_seed.to_le_bytes());
+    key[8..16].copy_from_slice(&(stream as u64).to_le_bytes());
+    let mut rng = SimRng::from_seed(key);
+    rng.set_stream(id as u64);
+    rng.set_word_pos((time_step as u128) << WORDS_PER_TIME_STEP_SHIFT);
+    rng
+}
+
+/// A fresh master seed from the operating system, for runs where none was given.
+pub fn random_master_seed() -> u64 {
+    rand::rngs::OsRng.next_u64()
+}
+
+/// The seed of replicate `replicate` of a scenario run with `master_seed`: distinct for every replicate, and the same
+/// for the same master seed.
+pub fn replicate_seed(master_seed: u64, replicate: usize) -> u64 {
+    individual_rng(master_seed, RngStream::Replicate, replicate, 0).gen()
+}
@@ -1,374 +1,322 @@
 // src/simulation/simulation.rs
-use crate::simulation::population::{Population, BACTERIA_LIST, DRUG_SHORT_NAMES};
+use crate::simulation::population::{IndividualMut, Population};
 use crate::rules::apply_rules;
 use crate::config; // Import the config module
+use crate::config::catalog::Catalog;
+use crate::config::demography::Demography;
+use crate::config::loader::ParameterSet;
+use crate::config::schedule::CalendarDate;
+use crate::config::tables::ParameterTables;
+use crate::simulation::builder::SimulationBuilder;
+use crate::simulation::events::{EventBuffer, EventRecord};
+use crate::simulation::output::OutputSink;
+use crate::simulation::rng::{individual_rng, RngStream};
+use crate::simulation::snapshot::{self, SnapshotError};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 use rayon::prelude::*;
 
 pub struct Simulation {  // public rust struct which encapsulates the state and configuration of a simulation run.
     pub population: Population, // specifying the population of individuals in the simulation.
     pub time_steps: usize, // specifying how many discrete time steps the simulation will run.
-
-    // todo: ensure that when we count across individuals that we include only those alive
+    pub current_time_step: usize, // time steps run so far, i.e. the next one to run (more than 0 after a resume).
+    pub start_date: CalendarDate, // calendar date of time step 0; time step t is the day start_date + t.
+    pub seed: u64, // master seed from which every random draw in the run is derived (see rng.rs).
 
     pub global_majority_r_proportions: HashMap<(usize, usize), f64>,  // Maps (bacteria_index, drug_index) pairs to a global proportion 
                                                                       // value to track summary statistics over time.
-    pub bacteria_indices: HashMap<&'static str, usize>, // A string-to-index map converting bacteria names (&'static str) to integer indices.
-    pub drug_indices: HashMap<&'static str, usize>, // as above, but for drugs.
-    pub cross_resistance_groups: HashMap<usize, Vec<Vec<usize>>>, // New: (b_idx -> [[d_idx, d_idx], ...])
+    pub parameters: ParameterSet, // the parameter set of this run (defaults plus overrides).
+    pub params: ParameterTables, // parameter values resolved for the current calendar day, read by apply_rules.
+    pub catalog: Arc<Catalog>, // the bacteria, drugs and regions of this run, in index order (see catalog.rs).
+    pub demography: Arc<Demography>, // the pyramids, birth rates and life tables of this run (see demography.rs).
+    pub quiet: bool, // no progress messages on the console, for tools making many runs (e.g. a fit).
+    pub compact_dead_every: usize, // time steps between removals of the rows of the dead (0 = never; see Population::compact).
+    outputs: Vec<Box<dyn OutputSink>>, // output sinks, called before, during and after the run (see output.rs).
 }
 
 impl Simulation {
-    pub fn new(population_size: usize, time_steps: usize) -> Self {
+    /// A simulation using the default catalog, demographic data and parameters.
+    /// (`Simulation::builder()` gives more control: catalog, parameter files, start date, run length and outputs.)
+    pub fn new(population_size: usize, time_steps: usize, seed: u64) -> Self {
+        let catalog = Arc::new(Catalog::default_catalog());
+        let demography = Arc::new(Demography::default_demography());
+        let parameters = ParameterSet::defaults(&catalog);
+        Simulation::with_parameters(population_size, time_steps, seed, parameters, catalog, demography)
+    }
+
+    /// A simulation using the given (already validated) parameter set, catalog and demographic data.
+    pub fn with_parameters(
+        population_size: usize,
+        time_steps: usize,
+        seed: u64,
+        parameters: ParameterSet,
+        catalog: Arc<Catalog>,
+        demography: Arc<Demography>,
+    ) -> Self {
 
         // public function named new (rust’s conventional constructor pattern).  
-        // Takes two inputs: population_size: how many individuals to initialize.
+        // Takes four inputs: population_size: how many individuals alive at the start to initialize.
         // time_steps: how many time steps the simulation should run.
+        // seed: the master seed; the same seed gives the same run, whatever the number of threads.
+        // parameters: the parameter values of this run.
+        // catalog, demography: the bacteria, drugs and regions, and the demographic data, of this run.
         // Returns Self → shorthand for returning an instance of Simulation.
 
-        let population = Population::new(population_size); 
+        let start_date = config::simulation_start_date(&parameters);
+        let population = Population::new(population_size, start_date, seed, &catalog, &demography);
 
-        // calls a new constructor for the Population struct.  Passes in "population_size", returning a Population instance 
-        // and stores it in the local population variable.
+        // calls a new constructor for the Population struct.  Passes in "population_size" (the people alive at the
+        // start), the start date (for the age-sex pyramids of that year) and the seed, returning a Population
+        // instance and stores it in the local population variable.
 
-        // Initialize bacteria_indices and drug_indices
-        let mut bacteria_indices: HashMap<&'static str, usize> = HashMap::new();
-        for (i, &bacteria) in BACTERIA_LIST.iter().enumerate() { // Iterate over the bacteria list and create a mapping from bacteria names to their indices.
-            bacteria_indices.insert(bacteria, i); // Inserts each bacteria name and its index into the HashMap.
-        }
+        Simulation::from_state(population, time_steps, 0, seed, parameters, catalog, demography)
+    }
 
-        let mut drug_indices: HashMap<&'static str, usize> = HashMap::new(); // Create a HashMap to map drug names to their indices.
-        for (i, &drug) in DRUG_SHORT_NAMES.iter().enumerate() { // Iterate over the drug list and create a mapping from drug names to their indices.
-            drug_indices.insert(drug, i);
-        }
+    /// A simulation continuing from a saved state (see snapshot.rs): `current_time_step` steps have been run on
+    /// `population` already.
+    pub(crate) fn from_state(
+        population: Population,
+        time_steps: usize,
+        current_time_step: usize,
+        seed: u64,
+        parameters: ParameterSet,
+        catalog: Arc<Catalog>,
+        demography: Arc<Demography>,
+    ) -> Self {
+        let start_date = config::simulation_start_date(&parameters);
+        let current_date = start_date.add_days(current_time_step as i64);
+        // resolved once here; again during the run only when a scheduled value changes
+        let params = ParameterTables::resolve(&parameters, &catalog, &demography, current_date.day_number());
 
-        // New: Load and process cross-resistance groups
-        let mut cross_resistance_groups = HashMap::new();
-        let raw_groups = config::get_cross_resistance_groups();
-        for (bacteria_name, groups) in raw_groups.iter() {
-            if let Some(&b_idx) = bacteria_indices.get(bacteria_name) {
-                let indexed_groups: Vec<Vec<usize>> = groups.iter().map(|group| {
-                    group.iter().filter_map(|drug_name| drug_indices.get(drug_name).copied()).collect()
-                }).collect();
-                cross_resistance_groups.insert(b_idx, indexed_groups);
-            }
+        Simulation { // Constructs and returns a new Simulation instance with the initialized population, time steps, and other data structures.
+            population,
+            time_steps,
+            current_time_step,
+            start_date,
+            seed,
+            global_majority_r_proportions: HashMap::new(),
+            parameters,
+            params,
+            catalog,
+            demography,
+            quiet: false,
+            compact_dead_every: 0,
+            outputs: Vec::new(),
         }
+    }
 
-        let global_majority_r_proportions = HashMap::new(); // Initialize an empty HashMap to store global majority_r proportions for bacteria/drug pairs.
+    /// Restores a simulation saved with `save_snapshot` (see snapshot.rs); `run` continues where the saved run
+    /// stopped.  Extend `time_steps` (or use `SimulationBuilder::resume`) to run further.  The snapshot has to have
+    /// been saved with the bacteria, drugs and regions of `catalog`.
+    pub fn load_snapshot<P: AsRef<Path>>(
+        path: P,
+        catalog: Arc<Catalog>,
+        demography: Arc<Demography>,
+    ) -> Result<Self, SnapshotError> {
+        snapshot::load_snapshot(path.as_ref(), catalog, demography)
+    }
 
-        // --- Initial State Logging for Individual 0
+    /// Saves the full state of the simulation (population, time step, seed, parameters) to a snapshot file.
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        snapshot::save_snapshot(self, path.as_ref())
+    }
 
-        println!(" ");
-        println!("--- simulation.rs  initial state of individual 0 ---");
-        println!(" ");
-        println!("id: {}", population.individuals[0].id);
-        println!("age: {} days", population.individuals[0].age);
-        println!("sex at birth: {}", population.individuals[0].sex_at_birth);
-        println!("region living: {:?}", population.individuals[0].region_living);
-        println!("region currently in: {:?}", population.individuals[0].region_cur_in);
-        println!("current_infection_related_death_risk: {:.2}", population.individuals[0].current_infection_related_death_risk);
-        println!("background_all_cause_mortality_rate: {:.4}", population.individuals[0].background_all_cause_mortality_rate);
-        println!("sexual_contact_level: {:.2}", population.individuals[0].sexual_contact_level);
-        println!("airborne_contact_level_with_adults: {:.2}", population.individuals[0].airborne_contact_level_with_adults);
-        println!("airborne_contact_level_with_children: {:.2}", population.individuals[0].airborne_contact_level_with_children);
-        println!("oral_exposure_level: {:.2}", population.individuals[0].oral_exposure_level);
-        println!("mosquito_exposure_level: {:.2}", population.individuals[0].mosquito_exposure_level);
-        println!("current_toxicity: {:.2}", population.individuals[0].current_toxicity);
-        println!("mortality_risk_current_toxicity: {:.2}", population.individuals[0].mortality_risk_current_toxicity);
-        println!(" ");
+    /// Replaces the parameter set, e.g. to continue a restored simulation under another scenario.  The values are
+    /// resolved for the current day straight away; the start date stays as it is.
+    pub fn set_parameters(&mut self, parameters: ParameterSet) {
+        let day = self.date_at(self.current_time_step).day_number();
+        self.params = ParameterTables::resolve(&parameters, &self.catalog, &self.demography, day);
+        self.parameters = parameters;
+    }
 
-        Simulation { // Constructs and returns a new Simulation instance with the initialized population, time steps, and other data structures.
-            population,
-            time_steps,
-            global_majority_r_proportions,
-            bacteria_indices,
-            drug_indices,
-            cross_resistance_groups, // Add new field
-        }
+    /// Starts building a simulation (see builder.rs).
+    pub fn builder() -> SimulationBuilder {
+        SimulationBuilder::new()
     }
 
-    pub fn run(&mut self) {
-        // public function named run, which executes the simulation for the specified number of time steps.
+    /// Adds an output sink, called after those already added.
+    pub fn add_output(&mut self, output: Box<dyn OutputSink>) {
+        self.outputs.push(output);
+    }
 
-        println!(" ");
-        println!("--- starting to run over time steps");
-        println!(" ");
+    /// The calendar date of a time step.
+    pub fn date_at(&self, time_step: usize) -> CalendarDate {
+        self.start_date.add_days(time_step as i64)
+    }
 
-        for t in 0..self.time_steps {
-//          println!("simulation.rs time step: {}", t);
+    pub fn run(&mut self) -> std::io::Result<()> {
+        // public function named run, which executes the simulation for the specified number of time steps.
+        // returns the first error raised by an output sink.
 
+        if !self.quiet {
+            println!(" ");
+            println!("--- starting to run over time steps (time step 0 = {})", self.start_date);
+            if self.current_time_step > 0 {
+                println!("--- resuming at time step {} ({})", self.current_time_step, self.date_at(self.current_time_step));
+            }
+            println!(" ");
+        }
 
-            let mut current_majority_r_positive_values_by_combo: HashMap<(usize, bool, usize, usize), Vec<f64>> = HashMap::new();
-            let mut current_infected_counts_with_majority_r: HashMap<(usize, usize), usize> = HashMap::new();
-            let mut current_infected_counts_total: HashMap<usize, usize> = HashMap::new();
+        let has_schedules = self.parameters.has_schedules();
+        let first_time_step = self.current_time_step;
+        let mut scheduled_values = self.parameters.scheduled_values_at(self.date_at(first_time_step).day_number());
 
-            // --- counters  ---
-            let mut _individuals_with_any_bacterial_infection = 0;
-            let mut _individuals_with_any_r_positive_for_any_bacteria = 0;
-            // --- ---
+        // the sinks are taken out while they run so that each can be handed the whole simulation
+        let mut outputs = std::mem::take(&mut self.outputs);
+        let wants_events = outputs.iter().any(|output| output.wants_events());
+        for output in outputs.iter_mut() {
+            output.start(self)?;
+        }
 
-            for individual in self.population.individuals.iter() {
-                let region_idx = individual.region_cur_in as usize;
-                let hospital_status_bool = individual.hospital_status.is_hospitalized();
-                let mut individual_has_any_infection = false;
-                let mut individual_has_any_r_positive = false;
-                for (b_idx, &_bacteria_name) in BACTERIA_LIST.iter().enumerate() {
-                    if individual.level[b_idx] > 0.001 {
-                        individual_has_any_infection = true;
-                        *current_infected_counts_total.entry(b_idx).or_insert(0) += 1;
-                        for (d_idx, &_drug_name) in DRUG_SHORT_NAMES.iter().enumerate() {
-                            let resistance_data = &individual.resistances[b_idx][d_idx];
-                            if resistance_data.majority_r > 0.0 {
-                                current_majority_r_positive_values_by_combo
-                                    .entry((region_idx, hospital_status_bool, b_idx, d_idx))
-                                    .or_insert_with(Vec::new)
-                                    .push(resistance_data.majority_r);
-                                *current_infected_counts_with_majority_r.entry((b_idx, d_idx)).or_insert(0) += 1;
-                            }
-                            if resistance_data.any_r > 0.0 {
-                                individual_has_any_r_positive = true;
-                            }
-                        }
-                    }
-                }
-                if individual_has_any_infection {
-                    _individuals_with_any_bacterial_infection += 1;
-                }
-                if individual_has_any_r_positive {
-                    _individuals_with_any_r_positive_for_any_bacteria += 1;
+        for t in first_time_step..self.time_steps {
+            // scheduled parameters take the values in force on this day; the tables are resolved again only when
+            // one of them has moved (a step schedule moves on few days, an interpolated one every day between points)
+            if t > first_time_step && has_schedules {
+                let day = self.date_at(t).day_number();
+                let values = self.parameters.scheduled_values_at(day);
+                if values != scheduled_values {
+                    self.params = ParameterTables::resolve(&self.parameters, &self.catalog, &self.demography, day);
+                    scheduled_values = values;
                 }
             }
+            if self.params.background_mortality_year != self.date_at(t).year {
+                // the life tables of a new year
+                self.params.set_background_mortality_year(&self.catalog, &self.demography, self.date_at(t).year);
+            }
+            // the dead of earlier days have been counted by the outputs, so their rows can go
+            if self.compact_dead_every > 0 && t > first_time_step && t % self.compact_dead_every == 0 {
+                self.population.compact();
+            }
+            // the day's newborns, from the birth rates of each region, take part in this time step
+            self.population.add_births(self.date_at(t), t, self.seed, &self.catalog, &self.demography);
+
+            // (counts by region, age band and sex are reported by the TimeSeriesWriter output; see timeseries.rs)
+            let current_majority_r_positive_values_by_combo = majority_r_pools(&self.population);
 
             // --- parallel application of rules to individuals ---
-            self.population.individuals.par_iter_mut().for_each(|individual| {
+            // (each individual draws from its own stream for this step, so the order the threads run in does not matter)
+            let seed = self.seed;
+            let apply = |individual: &mut IndividualMut, events: &mut EventBuffer| {
+                let mut rng = individual_rng(seed, RngStream::TimeStep, individual.id, t);
                 apply_rules(
                     individual,
                     t,
-                    &self.global_majority_r_proportions,
                     &current_majority_r_positive_values_by_combo,
-                    &self.bacteria_indices,
-                    &self.drug_indices,
-                    &self.cross_resistance_groups, // Pass new data
+                    &self.params,
+                    &mut rng,
+                    events,
                 );
-            });
-
-
-/* per time step printing block
-
-            // --- print activity_r for all infected bacteria/drug pairs for individual 0 after update ---
-            let individual_0 = &self.population.individuals[0];
-            for (b_idx, &bacteria_name) in BACTERIA_LIST.iter().enumerate() { 
-                if individual_0.level[b_idx] > 0.0001 {
-                    for (drug_idx, &drug_name) in DRUG_SHORT_NAMES.iter().enumerate() {
-                        if individual_0.cur_level_drug[drug_idx] > 0.0 {
-                            let resistance_data = &individual_0.resistances[b_idx][drug_idx];
-                            println!("   "); 
-                            println!(
-                                "simulation.rs  {} (infected) + {} (present): activity_r = {:.4}, any_r = {:.4}, drug_level = {:.4}",
-                                bacteria_name,
-                                drug_name,
-                                resistance_data.activity_r,
-                                resistance_data.any_r,
-                                individual_0.cur_level_drug[drug_idx]
-                            );
-                            println!("   "); 
-                        }
-                    }
-                }
-            }
-
-
-            // Print drug details for individual 0, regardless of infection status
-            // Note: Using threshold of 0.001 to avoid showing negligible drug levels
-            let mut drugs_present_found_overall = false; // Declare and initialize here
-            for (drug_idx, &drug_name_static) in DRUG_SHORT_NAMES.iter().enumerate() {
-                if individual_0.cur_level_drug[drug_idx] > 0.001 {
-                    let status = if individual_0.cur_use_drug[drug_idx] {
-                        " simulation.rs (currently being taken)"
-                    } else {
-                        " simulation.rs (decaying)"
-                    };
-                    println!("simulation.rs ");
-                    println!("{}: level = {:.4}{}", drug_name_static, individual_0.cur_level_drug[drug_idx], status);
-                    println!(" ");
-                    drugs_present_found_overall = true; // Use the newly declared variable
+            };
+            // the events are only collected if an output wants them, in individual order whatever the thread count
+            let events: Vec<EventRecord> = if wants_events {
+                self.population
+                    .par_iter_mut()
+                    .flat_map_iter(|mut individual| {
+                        let mut buffer = EventBuffer::recording();
+                        apply(&mut individual, &mut buffer);
+                        let id = individual.id;
+                        buffer.into_events().into_iter().map(move |event| EventRecord { time_step: t, individual: id, event })
+                    })
+                    .collect()
+            } else {
+                self.population.par_iter_mut().for_each(|mut individual| apply(&mut individual, &mut EventBuffer::discarding()));
+                Vec::new()
+            };
+
+            // (individuals are followed day by day with the CohortTracer output; see trace.rs)
+            self.current_time_step = t + 1;
+            for output in outputs.iter_mut() {
+                if output.wants_events() {
+                    output.events(self, t, &events)?;
                 }
-            }
-            if !drugs_present_found_overall {
-                println!("simulation.rs  no antibiotics currently in system");
+                output.step(self, t)?;
             }
 
+        }
 
-            let mut has_infection = false;
-            for (b_idx, &bacteria_name) in BACTERIA_LIST.iter().enumerate() {
-                let level = individual_0.level[b_idx];
-                if level > 0.0001 {
-                    has_infection = true;
-                    println!(" ");  
-                    println!("simulation.rs  ");  
-                    println!(" ");  
-                    println!("bacteria level = {:.4}", level);
-                    println!("bacteria: {}", bacteria_name);
-                    println!("infected = true");
+        for output in outputs.iter_mut() {
+            output.finish(self)?;
+        }
+        self.outputs = outputs;
+        Ok(())
+    }
+}
 
-                    println!("immune response = {:.4}", individual_0.immune_resp[b_idx]);
-                    println!("infection from environment = {}", individual_0.cur_infection_from_environment[b_idx]);
-                    println!("hospital acquired infection = {}", individual_0.infection_hospital_acquired[b_idx]);
-                    println!("test identified infection = {}", individual_0.test_identified_infection[b_idx]);
-                    let mut drugs_present_found = false;
-                    println!("antibiotics present in system (current level > 0):");
-                    for (drug_idx, &drug_name_static) in DRUG_SHORT_NAMES.iter().enumerate() {
-                        if individual_0.cur_level_drug[drug_idx] > 0.0 {
-                            let status = if individual_0.cur_use_drug[drug_idx] {
-                                " (currently being taken)"
-                            } else {
-                                " (decaying)"
-                            };
-                            println!("{}: level = {:.4}{}", drug_name_static, individual_0.cur_level_drug[drug_idx], status);
-                            drugs_present_found = true;
-                        }
-                    }
-                    if !drugs_present_found {
-                        println!("simulation.rs  no antibiotics currently in system");
+// The majority_r values above 0 among living individuals, by (region, hospital status, bacteria, drug), where the
+// region is the geographic region each individual is in (its living region unless travelling), so that a
+// community-acquired infection samples resistance from the people around it.
+fn majority_r_pools(population: &Population) -> HashMap<(usize, bool, usize, usize), Vec<f32>> {
+    let mut pools: HashMap<(usize, bool, usize, usize), Vec<f32>> = HashMap::new();
+    for individual in population.iter().filter(|i| i.date_of_death.is_none()) {
+        let region_idx = individual.region_present().index();
+        let hospital_status_bool = individual.hospital_status.is_hospitalized();
+        // (only bacteria present hold resistance state, so only they are looked at)
+        for (b_idx, resistance_row) in individual.resistances.iter() {
+            if individual.level[b_idx] > 0.001 {
+                for (d_idx, resistance_data) in resistance_row.iter().enumerate() {
+                    if resistance_data.majority_r > 0.0 {
+                        pools.entry((region_idx, hospital_status_bool, b_idx, d_idx)).or_default().push(resistance_data.majority_r);
                     }
-                    let mut effective_antibiotics_found = false;
-  
-                    for (drug_idx, &drug_name_static) in DRUG_SHORT_NAMES.iter().enumerate() {
-                        if individual_0.cur_level_drug[drug_idx] > 0.0 {
-                            let resistance_data = &individual_0.resistances[b_idx][drug_idx];
-                            println!("any_r {}:", bacteria_name);    
-                            println!(
-                                "simulation.rs  {}: level = {:.4}, any_r = {:.4}, activity_r = {:.4}, majority_r = {:.4}",
-                                drug_name_static,
-                                individual_0.cur_level_drug[drug_idx],
-                                resistance_data.any_r,
-                                resistance_data.activity_r,
-                                resistance_data.majority_r
-                            );
-                            if resistance_data.activity_r > 0.0 {
-                                effective_antibiotics_found = true;
-                            }
-                        }
-                    }
-                    if !effective_antibiotics_found {
-                        println!("simulation.rs  no effective antibiotics in system against this bacteria");
-                    }
-                    println!();
                 }
             }
-            if !has_infection {
-                println!("simulation.rs  no active bacterial infection as of end of the time step");
-                println!();
-            }
-
-
-            println!(" ");
-            println!("simulation.rs  infection and resistance summary outputs:");
-            println!(" ");
-
-            let age_in_years = (self.population.individuals[0].age as f64 / 365.0).round() as i32;
-            let ever_taken_drug_vector: Vec<u8> = self.population.individuals[0].ever_taken_drug.iter().map(|&taken| if taken { 1 } else { 0 }).collect();
-            println!("                                ");
-            println!("age_in_years: {}", age_in_years);
-            println!("region_living: {:?}", self.population.individuals[0].region_living);                                      
-            println!("region_cur_in: {:?}", self.population.individuals[0].region_cur_in);                                      
-            println!("hospital_status: {:?}", self.population.individuals[0].hospital_status);                                      
-            println!("is_severely_immunosuppressed: {:?}", self.population.individuals[0].is_severely_immunosuppressed);                                      
-            println!("date_last_infected: {:?}", self.population.individuals[0].date_last_infected);                                      
-            println!("ever_taken_drug: {:?}", ever_taken_drug_vector);
-            println!("date of death: {:?}", self.population.individuals[0].date_of_death);   
-            println!("                                ");
-
-            // Print resistance summary for all infected individuals at this time step
-//          self.print_resistance_summary(t);
-
-
-*/ // end of per timestep printing block
-
-
         }
-
     }
+    pools
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::ParameterFileFormat;
+    use crate::simulation::population::Region;
+
+    // The drug initiation rate in force after running `days` days from 2000-01-01, under a step schedule that
+    // moves from 0.001 to 0.002 on 2000-01-04 (day 3).
+    fn rate_after(days: usize) -> f64 {
+        let catalog = Catalog::default_catalog();
+        let mut parameters = ParameterSet::defaults(&catalog);
+        let schedule = r#"
+            simulation_start_date = "2000-01-01"
+            [drug_base_initiation_rate_per_day]
+            interpolation = "step"
+            points = [["2000-01-01", 0.001], ["2000-01-04", 0.002]]
+        "#;
+        parameters.apply_str(&catalog, schedule, ParameterFileFormat::Toml, Path::new("test.toml")).unwrap();
+        let builder = Simulation::builder().parameters(parameters).population_size(20).days(days).seed(7).quiet(true);
+        let mut simulation = builder.build().unwrap();
+        simulation.run().unwrap();
+        simulation.params.drug_base_initiation_rate_per_day
+    }
 
+    #[test]
+    fn the_tables_follow_a_step_schedule() {
+        assert_eq!(rate_after(1), 0.001);
+        assert_eq!(rate_after(3), 0.001);
+        assert_eq!(rate_after(4), 0.002);
+        assert_eq!(rate_after(6), 0.002);
+    }
 
-
-
-
-
-/*   
-
-    fn print_resistance_summary(&self, time_step: usize) {
-        // Calculate bacteria infection counts first
-        let mut bacteria_infection_counts: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
-        
-        for individual in &self.population.individuals {
-            for (bacteria, &b_idx) in self.bacteria_indices.iter() {
-                if individual.level[b_idx] > 0.001 {
-                    *bacteria_infection_counts.entry(bacteria).or_insert(0) += 1;
-                }
-            }
-        }
-
-        
-        // Print bacteria and resistance summary
-        println!("\n--- Time step {} - Bacteria infection and resistance summary ---", time_step);
-        for (bacteria, &count) in &bacteria_infection_counts {
-            println!("{}: {} infected", bacteria, count);   
-            for (drug, _) in self.drug_indices.iter() {
-                // Collect the full distribution of any_r for this bacteria/drug pair
-                let mut any_r_values = Vec::new();
-                for individual in &self.population.individuals {
-                    if let Some(&b_idx) = self.bacteria_indices.get(bacteria) {
-                        if individual.level[b_idx] > 0.001 {
-                            if let Some(&d_idx) = self.drug_indices.get(drug) {
-                                let any_r = individual.resistances[b_idx][d_idx].any_r;
-                                any_r_values.push(any_r);
-                            }
-                        }
-                    }
-                }
-
-                // Print summary statistics for the distribution
-                if !any_r_values.is_empty() {
-                    let n = any_r_values.len() as f64;
-                    let mut count_0 = 0;
-                    let mut count_001_025 = 0;
-                    let mut count_0251_05 = 0;
-                    let mut count_0501_075 = 0;
-                    let mut count_0751_1 = 0;
-                    for &val in &any_r_values {
-                        if val == 0.0 {
-                            count_0 += 1;
-                        } else if val > 0.0 && val <= 0.25 {
-                            count_001_025 += 1;
-                        } else if val > 0.25 && val <= 0.5 {
-                            count_0251_05 += 1;
-                        } else if val > 0.5 && val <= 0.75 {
-                            count_0501_075 += 1;
-                        } else if val > 0.75 && val <= 1.0 {
-                            count_0751_1 += 1;
-                        }
-                    }
-                    println!(
-                        "    {}: n = {}, prop 0.00 = {:.3}, prop 0.25 = {:.3}, prop 0.5 = {:.3}, prop 0.75 = {:.3}, prop 1.00 = {:.3}",
-                        drug,
-                        n as usize,
-                        count_0 as f64 / n,
-                        count_001_025 as f64 / n,
-                        count_0251_05 as f64 / n,
-                        count_0501_075 as f64 / n,
-                        count_0751_1 as f64 / n
-                    );
-                } else {
-                    println!("    {}: n = 0", drug);
-                }
+    #[test]
+    fn a_traveller_joins_the_resistance_pool_of_the_region_visited() {
+        let mut simulation = Simulation::new(20, 1, 3);
+        let mut home_and_away = None;
+        for mut individual in simulation.population.iter_mut() {
+            individual.level.fill(0.0);
+            if individual.id == 0 {
+                // individual 0 is visiting the next region, infected with majority_r to the first drug
+                let away = Region::from_index((individual.region_living.index() + 1) % simulation.catalog.regions.len());
+                *individual.region_cur_in = away;
+                individual.level[0] = 1.0;
+                individual.resistances.row_mut(0)[0].majority_r = 0.5;
+                home_and_away = Some((individual.region_living.index(), away.index()));
             }
         }
+        let (home, away) = home_and_away.unwrap();
+        let pools = majority_r_pools(&simulation.population);
+        let pooled: Vec<(usize, &Vec<f32>)> =
+            pools.iter().filter(|(key, _)| key.2 == 0 && key.3 == 0).map(|(key, values)| (key.0, values)).collect();
+        assert_eq!(pooled, [(away, &vec![0.5])]);
+        assert!(pools.keys().all(|key| key.0 != home));
     }
-*/
-
-
-
 }
-
@@ -0,0 +1,226 @@
+// src/simulation/snapshot.rs
+//
+// Snapshots: the full state of a simulation saved to a file and restored later, so that a long run can be picked
+// up after a crash, and a warm-up run (say 1942 to 2000) can be done once and then continued under several
+// intervention scenarios.
+//
+// A snapshot holds the population (every individual held, including its resistance rows, and the tally of deaths
+// of those compacted away), the number of time steps run so far, the master seed, the parameter set and the
+// aggregates carried from one time step to the next.  No generator state has to be kept: an individual's random
+// numbers for a time step depend only on (seed, id, time step), and the births of a day on (seed, region, time step)
+// (see rng.rs), so a restored run draws exactly what the uninterrupted run would have.  The bacteria, drug and
+// region names are stored as well, and a snapshot is refused under a different catalog, since every per-bacteria,
+// per-drug and per-region vector is indexed by them.  The catalog and demographic data themselves are not stored:
+// they are given again when the snapshot is restored.
+//
+// The file is an 8-byte tag and a format version, then the state encoded with bincode and deflate-compressed (most
+// of each resistance matrix is zeros).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::config::catalog::Catalog;
+use crate::config::demography::Demography;
+use crate::config::loader::ParameterSet;
+use crate::simulation::output::OutputSink;
+use crate::simulation::population::Population;
+use crate::simulation::simulation::Simulation;
+
+const SNAPSHOT_TAG: &[u8; 8] = b"AMRSNAP\0";
+
+/// Version of the snapshot layout; snapshots written with another version are refused.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 6;
+
+/// Errors raised while saving or restoring a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io { path: PathBuf, source: io::Error },
+    Format { path: PathBuf, message: String },
+    CatalogMismatch { path: PathBuf, message: String },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io { path, source } => write!(f, "could not access snapshot {}: {}", path.display(), source),
+            SnapshotError::Format { path, message } => {
+                write!(f, "could not read snapshot {}: {}", path.display(), message)
+            }
+            SnapshotError::CatalogMismatch { path, message } => {
+                write!(f, "snapshot {} was saved with a different catalog: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// What is written: borrowed from the simulation, so saving does not copy the population.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    bacteria: Vec<&'a str>,
+    drugs: Vec<&'a str>,
+    regions: Vec<&'a str>,
+    seed: u64,
+    time_steps: usize,
+    current_time_step: usize,
+    parameters: &'a ParameterSet,
+    global_majority_r_proportions: &'a HashMap<(usize, usize), f64>,
+    population: &'a Population,
+}
+
+// What is read back; the fields are in the same order as SnapshotRef.
+#[derive(Deserialize)]
+struct SnapshotData {
+    bacteria: Vec<String>,
+    drugs: Vec<String>,
+    regions: Vec<String>,
+    seed: u64,
+    time_steps: usize,
+    current_time_step: usize,
+    parameters: ParameterSet,
+    global_majority_r_proportions: HashMap<(usize, usize), f64>,
+    population: Population,
+}
+
+/// Saves the state of a simulation to `path`.  The snapshot is written to a temporary file next to `path` and then
+/// renamed, so an interrupted save never leaves a damaged snapshot in place of a good one.
+pub fn save_snapshot(simulation: &Simulation, path: &Path) -> Result<(), SnapshotError> {
+    let io_error = |source| SnapshotError::Io { path: path.to_path_buf(), source };
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let mut out = BufWriter::new(File::create(&partial).map_err(io_error)?);
+    out.write_all(SNAPSHOT_TAG).map_err(io_error)?;
+    out.write_all(&SNAPSHOT_FORMAT_VERSION.to_le_bytes()).map_err(io_error)?;
+    let mut encoder = DeflateEncoder::new(out, Compression::fast());
+    let catalog = &simulation.catalog;
+    let state = SnapshotRef {
+        bacteria: catalog.bacteria_names(),
+        drugs: catalog.drug_names(),
+        regions: catalog.region_names(),
+        seed: simulation.seed,
+        time_steps: simulation.time_steps,
+        current_time_step: simulation.current_time_step,
+        parameters: &simulation.parameters,
+        global_majority_r_proportions: &simulation.global_majority_r_proportions,
+        population: &simulation.population,
+    };
+    bincode::serialize_into(&mut encoder, &state)
+        .map_err(|e| SnapshotError::Format { path: path.to_path_buf(), message: e.to_string() })?;
+    encoder.finish().map_err(io_error)?.flush().map_err(io_error)?;
+    fs::rename(&partial, path).map_err(io_error)
+}
+
+/// Restores a simulation saved with `save_snapshot` under `catalog`, which goes on with `catalog` and `demography`.
+/// It has no outputs; its `current_time_step` is where the saved run had got to, and `run` continues from there.
+pub fn load_snapshot(
+    path: &Path,
+    catalog: Arc<Catalog>,
+    demography: Arc<Demography>,
+) -> Result<Simulation, SnapshotError> {
+    let format_error = |message: String| SnapshotError::Format { path: path.to_path_buf(), message };
+    let mut input = BufReader::new(File::open(path).map_err(|source| SnapshotError::Io { path: path.to_path_buf(), source })?);
+
+    let mut header = [0u8; 12];
+    input.read_exact(&mut header).map_err(|_| format_error("the file is too short to be a snapshot".to_string()))?;
+    if &header[..8] != SNAPSHOT_TAG {
+        return Err(format_error("the file is not a snapshot".to_string()));
+    }
+    let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    if version != SNAPSHOT_FORMAT_VERSION {
+        return Err(format_error(format!(
+            "it has format version {}, this build reads version {}",
+            version, SNAPSHOT_FORMAT_VERSION
+        )));
+    }
+
+    let state: SnapshotData =
+        bincode::deserialize_from(DeflateDecoder::new(input)).map_err(|e| format_error(e.to_string()))?;
+    check_names(path, "bacteria", &state.bacteria, &catalog.bacteria_names())?;
+    check_names(path, "drugs", &state.drugs, &catalog.drug_names())?;
+    check_names(path, "regions", &state.regions, &catalog.region_names())?;
+    if state.current_time_step > state.time_steps {
+        return Err(format_error(format!(
+            "it is at time step {} of a {}-step run",
+            state.current_time_step, state.time_steps
+        )));
+    }
+
+    let mut simulation = Simulation::from_state(
+        state.population,
+        state.time_steps,
+        state.current_time_step,
+        state.seed,
+        state.parameters,
+        catalog,
+        demography,
+    );
+    simulation.global_majority_r_proportions = state.global_majority_r_proportions;
+    Ok(simulation)
+}
+
+fn check_names(path: &Path, what: &str, saved: &[String], in_use: &[&str]) -> Result<(), SnapshotError> {
+    if saved.iter().map(String::as_str).eq(in_use.iter().copied()) {
+        return Ok(());
+    }
+    let message = match saved.iter().zip(in_use).position(|(s, u)| s != u) {
+        Some(i) => format!("{} number {} is \"{}\" in the snapshot but \"{}\" in the catalog", what, i, saved[i], in_use[i]),
+        None => format!("the snapshot has {} {}, the catalog {}", saved.len(), what, in_use.len()),
+    };
+    Err(SnapshotError::CatalogMismatch { path: path.to_path_buf(), message })
+}
+
+/// Output sink saving snapshots of the simulation: at the end of the run, and, if `every` is set, after every that
+/// many time steps as well, overwriting the previous one, so a crashed run can be resumed from the last checkpoint.
+pub struct SnapshotWriter {
+    path: PathBuf,
+    every: Option<usize>,
+}
+
+impl SnapshotWriter {
+    /// Saves a snapshot to `path` at the end of the run.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        SnapshotWriter { path: path.into(), every: None }
+    }
+
+    /// Also saves one after every `time_steps` time steps (counted from time step 0).
+    pub fn every(mut self, time_steps: usize) -> Self {
+        self.every = (time_steps > 0).then_some(time_steps);
+        self
+    }
+}
+
+impl OutputSink for SnapshotWriter {
+    fn step(&mut self, simulation: &Simulation, _time_step: usize) -> io::Result<()> {
+        let done = simulation.current_time_step;
+        match self.every {
+            // the last step is left to finish
+            Some(every) if done.is_multiple_of(every) && done < simulation.time_steps => {
+                save_snapshot(simulation, &self.path).map_err(io::Error::other)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn finish(&mut self, simulation: &Simulation) -> io::Result<()> {
+        save_snapshot(simulation, &self.path).map_err(io::Error::other)
+    }
+}
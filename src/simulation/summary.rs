@@ -0,0 +1,130 @@
+// src/simulation/summary.rs
+//
+// Summaries of drug use and resistance by drug class and subclass (e.g. "carbapenem use per 100k",
+// "resistance to third-generation cephalosporins"), with the classes taken from the catalog, and the end-of-run
+// summary sink that prints them along with deaths by cause.
+
+use std::io::{self, Write};
+
+use crate::config::catalog::{Catalog, DrugGroup};
+use crate::simulation::output::OutputSink;
+use crate::simulation::population::Population;
+use crate::simulation::simulation::Simulation;
+
+/// Drug use and resistance for one drug class or subclass at one point in time.
+#[derive(Debug, Clone)]
+pub struct DrugGroupSummary {
+    pub group: DrugGroup,
+    pub alive: usize,
+    pub currently_using: usize, // alive individuals currently on at least one drug in the group
+    pub ever_taken: usize,      // alive individuals who have ever taken a drug in the group
+    pub infected: Vec<usize>,   // [bacteria] alive individuals infected (level > 0.001)
+    pub resistant: Vec<usize>,  // [bacteria] of those, how many have any_r > 0 to at least one drug in the group
+}
+
+impl DrugGroupSummary {
+    /// Individuals currently using a drug in the group, per 100,000 alive.
+    pub fn use_per_100k(&self) -> f64 {
+        per_100k(self.currently_using, self.alive)
+    }
+
+    /// Individuals who have ever taken a drug in the group, per 100,000 alive.
+    pub fn ever_taken_per_100k(&self) -> f64 {
+        per_100k(self.ever_taken, self.alive)
+    }
+
+    /// Proportion of infections with a bacteria that are resistant to the group, if there are any infections.
+    pub fn proportion_resistant(&self, b_idx: usize) -> Option<f64> {
+        (self.infected[b_idx] > 0).then(|| self.resistant[b_idx] as f64 / self.infected[b_idx] as f64)
+    }
+}
+
+fn per_100k(count: usize, alive: usize) -> f64 {
+    if alive == 0 {
+        0.0
+    } else {
+        count as f64 * 100_000.0 / alive as f64
+    }
+}
+
+/// One summary per drug class and subclass present in `catalog`, the catalog of the population (classes first).
+pub fn drug_group_summaries(population: &Population, catalog: &Catalog) -> Vec<DrugGroupSummary> {
+    catalog
+        .drug_groups()
+        .into_iter()
+        .map(|group| {
+            let drugs = catalog.drug_indices_in(group);
+            let mut summary = DrugGroupSummary {
+                group,
+                alive: 0,
+                currently_using: 0,
+                ever_taken: 0,
+                infected: vec![0; catalog.bacteria.len()],
+                resistant: vec![0; catalog.bacteria.len()],
+            };
+            for individual in population.iter().filter(|i| i.date_of_death.is_none()) {
+                summary.alive += 1;
+                if drugs.iter().any(|&d| individual.cur_use_drug[d]) {
+                    summary.currently_using += 1;
+                }
+                if drugs.iter().any(|&d| individual.ever_taken_drug[d]) {
+                    summary.ever_taken += 1;
+                }
+                for b_idx in 0..catalog.bacteria.len() {
+                    if individual.level[b_idx] > 0.001 {
+                        summary.infected[b_idx] += 1;
+                        if drugs.iter().any(|&d| individual.resistances[b_idx][d].any_r > 0.0) {
+                            summary.resistant[b_idx] += 1;
+                        }
+                    }
+                }
+            }
+            summary
+        })
+        .collect()
+}
+
+/// Output sink writing the end-of-run summary: deaths by cause, then drug use and resistance by class and subclass.
+pub struct EndOfRunSummary<W: Write + Send> {
+    out: W,
+}
+
+impl<W: Write + Send> EndOfRunSummary<W> {
+    pub fn new(out: W) -> Self {
+        EndOfRunSummary { out }
+    }
+}
+
+impl<W: Write + Send> OutputSink for EndOfRunSummary<W> {
+    fn finish(&mut self, simulation: &Simulation) -> io::Result<()> {
+        let out = &mut self.out;
+
+        // deaths, with causes in a fixed order so runs with the same seed print the same output
+        // (including those whose rows were compacted away)
+        let death_causes_count = simulation.population.deaths_by_cause();
+        let total_deaths: usize = death_causes_count.values().sum();
+        writeln!(out, "total deaths during simulation: {}", total_deaths)?;
+        writeln!(out, "breakdown by cause of death:")?;
+        for (cause, count) in death_causes_count {
+            writeln!(out, "{}: {}", cause, count)?;
+        }
+
+        // drug use and resistance by drug class and subclass
+        writeln!(out, "\n--- drug class and subclass summary ---")?;
+        for summary in drug_group_summaries(&simulation.population, &simulation.catalog) {
+            writeln!(
+                out,
+                "{}: currently using per 100k = {:.1}, ever taken per 100k = {:.1}",
+                summary.group,
+                summary.use_per_100k(),
+                summary.ever_taken_per_100k()
+            )?;
+            for (b_idx, bacteria) in simulation.catalog.bacteria_names().into_iter().enumerate() {
+                if let Some(proportion) = summary.proportion_resistant(b_idx) {
+                    writeln!(out, "    {}: n infected = {}, prop resistant = {:.3}", bacteria, summary.infected[b_idx], proportion)?;
+                }
+            }
+        }
+        out.flush()
+    }
+}
@@ -0,0 +1,355 @@
+// src/simulation/timeseries.rs
+//
+// Time-series output: counts of infection, drug use, resistance, sepsis and deaths over the course of a run,
+// stratified by region lived in, age band and sex at birth, written as CSV.
+//
+// One row per (reporting day, stratum, measure, bacteria, drug):
+//
+//     date,time_step,region,age_band,sex,measure,bacteria,drug,value
+//     1942-01-07,6,africa,15-49,female,infected,escherichia coli,,12
+//     1942-01-07,6,africa,15-49,female,any_r,escherichia coli,amoxicillin,3
+//
+// Only living individuals are counted (not dead at the end of the day); deaths and new infections are
+// counted on the day they happen.  With a reporting interval of more than one day, the point measures (alive,
+// infected, sepsis, ...) are those at the end of the last day of the interval and the event measures (new_infections,
+// deaths) are summed over the interval.  Rows with a count of zero are left out, except "alive", so a missing row
+// means zero.
+//
+// Measures, with the denominators they are meant to be read against:
+//   alive                               living individuals in the stratum
+//   infected, sepsis          [bacteria] infected with the bacteria (level > 0.001), and of those with sepsis
+//   new_infections            [bacteria] infections acquired during the interval
+//   microbiome_carriage       [bacteria] carrying the bacteria in the microbiome
+//   using_drug                [drug]     currently taking the drug
+//   any_r, majority_r   [bacteria, drug] infected with the bacteria and with any_r / majority_r > 0 to the drug
+//   microbiome_r        [bacteria, drug] carrying the bacteria in the microbiome with microbiome_r > 0 to the drug
+//   deaths, deaths_<cause>              deaths during the interval, in total and by cause
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use rayon::prelude::*;
+
+use crate::config::catalog::Catalog;
+use crate::simulation::output::{csv_field, merge_counts, OutputSink};
+use crate::simulation::population::{Individual, Region, Sex};
+use crate::simulation::simulation::Simulation;
+
+/// Default age bands, as the lower limit of each band in years: 0-4, 5-14, 15-49, 50-69 and 70+.
+pub const DEFAULT_AGE_BANDS: [u32; 5] = [0, 5, 15, 50, 70];
+
+const SEXES: [Sex; 2] = [Sex::Male, Sex::Female]; // in the order of their discriminants, as kept in a CellKey
+
+/// What a row counts (see the top of this file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Measure {
+    Alive,
+    Infected,
+    NewInfections,
+    Sepsis,
+    MicrobiomeCarriage,
+    UsingDrug,
+    AnyR,
+    MajorityR,
+    MicrobiomeR,
+    Deaths,
+    DeathsBackgroundMortality,
+    DeathsSepsisRelated,
+    DeathsDrugToxicityRelated,
+}
+
+impl Measure {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Measure::Alive => "alive",
+            Measure::Infected => "infected",
+            Measure::NewInfections => "new_infections",
+            Measure::Sepsis => "sepsis",
+            Measure::MicrobiomeCarriage => "microbiome_carriage",
+            Measure::UsingDrug => "using_drug",
+            Measure::AnyR => "any_r",
+            Measure::MajorityR => "majority_r",
+            Measure::MicrobiomeR => "microbiome_r",
+            Measure::Deaths => "deaths",
+            Measure::DeathsBackgroundMortality => "deaths_background_mortality",
+            Measure::DeathsSepsisRelated => "deaths_sepsis_related",
+            Measure::DeathsDrugToxicityRelated => "deaths_drug_toxicity_related",
+        }
+    }
+
+    // The by-cause measure for a cause of death recorded by apply_rules.
+    fn deaths_from(cause: Option<&str>) -> Option<Measure> {
+        match cause {
+            Some("background_mortality") => Some(Measure::DeathsBackgroundMortality),
+            Some("sepsis_related") => Some(Measure::DeathsSepsisRelated),
+            Some("drug_toxicity_related") => Some(Measure::DeathsDrugToxicityRelated),
+            _ => None,
+        }
+    }
+}
+
+/// One cell of the output: stratum (region, age band, sex), measure, bacteria and drug (usize::MAX where the
+/// measure is not per bacteria or per drug).  Sorting these gives the row order.
+pub type CellKey = (Region, u8, u8, Measure, usize, usize);
+
+/// A time series kept in memory rather than written out: the count of each cell on each reporting day, keyed by
+/// (time step, cell).  Filled in by a `TimeSeriesWriter` given a handle with `keep_counts`.
+pub type TimeSeriesCounts = BTreeMap<(usize, CellKey), u64>;
+
+pub type TimeSeriesHandle = Arc<Mutex<TimeSeriesCounts>>;
+
+const NONE: usize = usize::MAX;
+
+type Counts = HashMap<CellKey, u64>;
+
+// How individuals are split into strata.
+struct Strata {
+    age_bands: Vec<u32>, // lower limit of each band in years
+}
+
+// The region, age band, sex, measure, bacteria and drug columns of a cell, as CSV, with the names of `catalog`.
+pub(crate) fn cell_columns(catalog: &Catalog, age_bands: &[u32], key: CellKey) -> String {
+    let (region, band, sex, measure, b_idx, d_idx) = key;
+    let age_band = match age_bands.get(band as usize + 1) {
+        Some(next) => format!("{}-{}", age_bands[band as usize], next - 1),
+        None => format!("{}+", age_bands[band as usize]),
+    };
+    let bacteria = if b_idx == NONE { "" } else { catalog.bacteria[b_idx].name.as_str() };
+    let drug = if d_idx == NONE { "" } else { catalog.drugs[d_idx].name.as_str() };
+    format!(
+        "{},{},{},{},{},{}",
+        region.name(catalog),
+        age_band,
+        SEXES[sex as usize].as_str(),
+        measure.name(),
+        csv_field(bacteria),
+        csv_field(drug)
+    )
+}
+
+impl Strata {
+    fn stratum(&self, individual: &Individual) -> (Region, u8, u8) {
+        let years = (individual.age.max(0) / 365) as u32;
+        let band = self.age_bands.iter().rposition(|&lower| years >= lower).unwrap_or(0);
+        (individual.region_living, band as u8, individual.sex_at_birth as u8)
+    }
+
+    // New infections and deaths on one time step.
+    fn count_events(&self, simulation: &Simulation, time_step: usize) -> Counts {
+        simulation
+            .population
+            .par_iter()
+            .fold(Counts::new, |mut counts, individual| {
+                let (region, band, sex) = self.stratum(&individual);
+                let mut add = |measure, b_idx, d_idx| *counts.entry((region, band, sex, measure, b_idx, d_idx)).or_insert(0) += 1;
+                if individual.date_of_death == Some(time_step) {
+                    add(Measure::Deaths, NONE, NONE);
+                    if let Some(measure) = Measure::deaths_from(individual.cause_of_death) {
+                        add(measure, NONE, NONE);
+                    }
+                }
+                for b_idx in 0..individual.level.len() {
+                    if individual.level[b_idx] > 0.001 && individual.date_last_infected[b_idx] == time_step as i32 {
+                        add(Measure::NewInfections, b_idx, NONE);
+                    }
+                }
+                counts
+            })
+            .reduce(Counts::new, merge_counts)
+    }
+
+    // The point measures at the end of a time step.
+    fn count_state(&self, simulation: &Simulation) -> Counts {
+        simulation
+            .population
+            .par_iter()
+            .filter(|individual| individual.date_of_death.is_none())
+            .fold(Counts::new, |mut counts, individual| {
+                let (region, band, sex) = self.stratum(&individual);
+                let mut add = |measure, b_idx, d_idx| *counts.entry((region, band, sex, measure, b_idx, d_idx)).or_insert(0) += 1;
+                add(Measure::Alive, NONE, NONE);
+                for b_idx in 0..individual.level.len() {
+                    let infected = individual.level[b_idx] > 0.001;
+                    let carried = individual.presence_microbiome[b_idx];
+                    if infected {
+                        add(Measure::Infected, b_idx, NONE);
+                        if individual.sepsis[b_idx] {
+                            add(Measure::Sepsis, b_idx, NONE);
+                        }
+                    }
+                    if carried {
+                        add(Measure::MicrobiomeCarriage, b_idx, NONE);
+                    }
+                    if !infected && !carried {
+                        continue;
+                    }
+                    for (d_idx, resistance) in individual.resistances[b_idx].iter().enumerate() {
+                        if infected && resistance.any_r > 0.0 {
+                            add(Measure::AnyR, b_idx, d_idx);
+                        }
+                        if infected && resistance.majority_r > 0.0 {
+                            add(Measure::MajorityR, b_idx, d_idx);
+                        }
+                        if carried && resistance.microbiome_r > 0.0 {
+                            add(Measure::MicrobiomeR, b_idx, d_idx);
+                        }
+                    }
+                }
+                for (d_idx, using) in individual.cur_use_drug.iter().enumerate() {
+                    if using {
+                        add(Measure::UsingDrug, NONE, d_idx);
+                    }
+                }
+                counts
+            })
+            .reduce(Counts::new, merge_counts)
+    }
+}
+
+/// Output sink writing the time series as CSV.
+pub struct TimeSeriesWriter<W: Write + Send> {
+    out: W,
+    interval: usize,
+    strata: Strata,
+    events: Counts, // new infections and deaths since the last reporting day
+    pending: bool,  // time steps have run since the last reporting day
+    kept: Option<TimeSeriesHandle>,
+}
+
+impl<W: Write + Send> TimeSeriesWriter<W> {
+    /// A daily time series with the default age bands.
+    pub fn new(out: W) -> Self {
+        TimeSeriesWriter {
+            out,
+            interval: 1,
+            strata: Strata { age_bands: DEFAULT_AGE_BANDS.to_vec() },
+            events: Counts::new(),
+            pending: false,
+            kept: None,
+        }
+    }
+
+    /// Report every `days` days (counted from time step 0) instead of daily.
+    pub fn every(mut self, days: usize) -> Self {
+        self.interval = days.max(1);
+        self
+    }
+
+    /// Age bands as the lower limit of each band in years, in increasing order; the first should be 0.
+    pub fn age_bands(mut self, lower_limits_years: Vec<u32>) -> Self {
+        self.strata.age_bands = lower_limits_years;
+        self
+    }
+
+    /// Also keep the counts in memory, in `handle` (e.g. to summarise replicates; see analysis/replicates.rs).
+    pub fn keep_counts(mut self, handle: TimeSeriesHandle) -> Self {
+        self.kept = Some(handle);
+        self
+    }
+
+    fn write_report(&mut self, simulation: &Simulation, time_step: usize) -> io::Result<()> {
+        let mut counts = merge_counts(self.strata.count_state(simulation), std::mem::take(&mut self.events));
+        // every stratum gets an "alive" row, so the denominators are complete
+        for region in Region::all(&simulation.catalog) {
+            for band in 0..self.strata.age_bands.len() as u8 {
+                for sex in 0..2 {
+                    counts.entry((region, band, sex, Measure::Alive, NONE, NONE)).or_insert(0);
+                }
+            }
+        }
+        let mut cells: Vec<(CellKey, u64)> = counts.into_iter().collect();
+        cells.sort_unstable_by_key(|&(key, _)| key);
+
+        let date = simulation.date_at(time_step);
+        for &(key, value) in &cells {
+            writeln!(self.out, "{},{},{},{}", date, time_step, cell_columns(&simulation.catalog, &self.strata.age_bands, key), value)?;
+        }
+        if let Some(kept) = &self.kept {
+            kept.lock().unwrap().extend(cells.into_iter().map(|(key, value)| ((time_step, key), value)));
+        }
+        self.pending = false;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> OutputSink for TimeSeriesWriter<W> {
+    fn start(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        writeln!(self.out, "date,time_step,region,age_band,sex,measure,bacteria,drug,value")
+    }
+
+    fn step(&mut self, simulation: &Simulation, time_step: usize) -> io::Result<()> {
+        let events = self.strata.count_events(simulation, time_step);
+        self.events = merge_counts(std::mem::take(&mut self.events), events);
+        self.pending = true;
+        if (time_step + 1).is_multiple_of(self.interval) {
+            self.write_report(simulation, time_step)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, simulation: &Simulation) -> io::Result<()> {
+        // a last, shorter interval
+        if self.pending {
+            self.write_report(simulation, simulation.current_time_step - 1)?;
+        }
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The total of a measure over every stratum on one reporting day.
+    fn total(counts: &TimeSeriesCounts, time_step: usize, measure: Measure) -> u64 {
+        counts.iter().filter(|((t, key), _)| *t == time_step && key.3 == measure).map(|(_, &count)| count).sum()
+    }
+
+    #[test]
+    fn event_measures_are_summed_over_the_interval() {
+        // ten people, none infected, and events set by hand rather than by the rules
+        let mut simulation = Simulation::new(10, 3, 1);
+        for individual in simulation.population.iter_mut() {
+            individual.level.fill(0.0);
+        }
+        let handle = TimeSeriesHandle::default();
+        let mut writer = TimeSeriesWriter::new(io::sink()).every(2).keep_counts(handle.clone());
+        writer.start(&simulation).unwrap();
+        for t in 0..3 {
+            for individual in simulation.population.iter_mut() {
+                match (t, individual.id) {
+                    // infected with the first bacteria on day 0 (individual 0) and day 2 (individual 2)
+                    (0, 0) | (2, 2) => {
+                        individual.level[0] = 1.0;
+                        individual.date_last_infected[0] = t as i32;
+                    }
+                    (1, 1) => {
+                        *individual.date_of_death = Some(1);
+                        *individual.cause_of_death = Some("background_mortality".to_string());
+                    }
+                    _ => {}
+                }
+            }
+            simulation.current_time_step = t + 1;
+            writer.step(&simulation, t).unwrap();
+        }
+        writer.finish(&simulation).unwrap();
+
+        let counts = handle.lock().unwrap();
+        let mut reported: Vec<usize> = counts.keys().map(|&(t, _)| t).collect();
+        reported.dedup();
+        assert_eq!(reported, [1, 2]); // the end of the first interval, then the last, shorter one
+        // days 0 and 1: one infection and one death
+        assert_eq!(total(&counts, 1, Measure::NewInfections), 1);
+        assert_eq!(total(&counts, 1, Measure::Deaths), 1);
+        assert_eq!(total(&counts, 1, Measure::DeathsBackgroundMortality), 1);
+        assert_eq!(total(&counts, 1, Measure::Alive), 9);
+        assert_eq!(total(&counts, 1, Measure::Infected), 1);
+        // day 2: the second infection, and the first still infected
+        assert_eq!(total(&counts, 2, Measure::NewInfections), 1);
+        assert_eq!(total(&counts, 2, Measure::Deaths), 0);
+        assert_eq!(total(&counts, 2, Measure::Alive), 9);
+        assert_eq!(total(&counts, 2, Measure::Infected), 2);
+    }
+}
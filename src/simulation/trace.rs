@@ -0,0 +1,373 @@
+// src/simulation/trace.rs
+//
+// Cohort tracing: the full daily state of selected individuals, for following them through a run without editing
+// the code.
+//
+// The cohort is made of individuals given by id, a random sample of the population (drawn from the master seed,
+// so the same seed traces the same individuals) and everyone who at the end of some day meets a condition such as
+//
+//     infected(klebsiella pneumoniae) and on_drug(meropenem)
+//
+// An individual who meets the condition joins the cohort on that day and is traced from then on, whether or not
+// the condition still holds, until death.  Conditions are joined with "and":
+//
+//     infected(bacteria)   sepsis(bacteria)   carrying(bacteria)      in the microbiome
+//     on_drug(drug)        resistant(bacteria, drug)                  any_r > 0
+//     hospitalised         immunosuppressed   region(africa)          region lived in
+//     age_at_least(years)  age_under(years)
+//
+// The trace is written as JSON lines, one object per traced individual per day, after the day's rules have been
+// applied.  Per-bacteria and per-drug values are keyed by name, with zeros left out; resistances are listed for
+// each bacteria-drug pair with any non-zero value.
+
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use rand::seq::index;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::config::catalog::Catalog;
+use crate::simulation::output::OutputSink;
+use crate::simulation::population::{Flags, Individual, Region};
+use crate::simulation::rng::{individual_rng, RngStream};
+use crate::simulation::simulation::Simulation;
+
+/// One condition of a trace filter (see the top of this file).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceCondition {
+    Infected(usize),
+    Sepsis(usize),
+    Carrying(usize),
+    OnDrug(usize),
+    Resistant(usize, usize),
+    Hospitalised,
+    Immunosuppressed,
+    LivingIn(Region),
+    AgeAtLeast(u32),
+    AgeUnder(u32),
+}
+
+impl TraceCondition {
+    pub fn holds(&self, individual: &Individual) -> bool {
+        let years = individual.age / 365;
+        match *self {
+            TraceCondition::Infected(b_idx) => individual.level[b_idx] > 0.001,
+            TraceCondition::Sepsis(b_idx) => individual.sepsis[b_idx],
+            TraceCondition::Carrying(b_idx) => individual.presence_microbiome[b_idx],
+            TraceCondition::OnDrug(d_idx) => individual.cur_use_drug[d_idx],
+            TraceCondition::Resistant(b_idx, d_idx) => individual.resistances[b_idx][d_idx].any_r > 0.0,
+            TraceCondition::Hospitalised => individual.hospital_status.is_hospitalized(),
+            TraceCondition::Immunosuppressed => individual.is_severely_immunosuppressed,
+            TraceCondition::LivingIn(region) => individual.region_living == region,
+            TraceCondition::AgeAtLeast(limit) => years >= limit as i32,
+            TraceCondition::AgeUnder(limit) => years < limit as i32,
+        }
+    }
+}
+
+/// Conditions that must all hold, e.g. "infected(klebsiella pneumoniae) and on_drug(meropenem)".
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceFilter {
+    pub conditions: Vec<TraceCondition>,
+}
+
+impl TraceFilter {
+    /// Parses a filter, with bacteria, drug and region names from `catalog`.
+    pub fn parse(catalog: &Catalog, text: &str) -> Result<Self, String> {
+        let conditions = text
+            .split(" and ")
+            .map(|part| parse_condition(catalog, part.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TraceFilter { conditions })
+    }
+
+    pub fn matches(&self, individual: &Individual) -> bool {
+        self.conditions.iter().all(|condition| condition.holds(individual))
+    }
+}
+
+fn parse_condition(catalog: &Catalog, text: &str) -> Result<TraceCondition, String> {
+    let (name, args) = match text.split_once('(') {
+        Some((name, rest)) => {
+            let args = rest.strip_suffix(')').ok_or_else(|| format!("missing \")\" in \"{}\"", text))?;
+            (name.trim(), args.split(',').map(str::trim).collect::<Vec<_>>())
+        }
+        None => (text, Vec::new()),
+    };
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(format!("\"{}\" takes {} argument(s)", name, n))
+        }
+    };
+    let bacteria = |arg: &str| {
+        catalog.bacteria.iter().position(|b| b.name == arg).ok_or_else(|| format!("unknown bacteria \"{}\"", arg))
+    };
+    let drug = |arg: &str| catalog.drugs.iter().position(|d| d.name == arg).ok_or_else(|| format!("unknown drug \"{}\"", arg));
+    let years = |arg: &str| arg.parse::<u32>().map_err(|_| format!("\"{}\" is not a number of years", arg));
+
+    match name {
+        "infected" => arity(1).and(bacteria(args[0]).map(TraceCondition::Infected)),
+        "sepsis" => arity(1).and(bacteria(args[0]).map(TraceCondition::Sepsis)),
+        "carrying" => arity(1).and(bacteria(args[0]).map(TraceCondition::Carrying)),
+        "on_drug" => arity(1).and(drug(args[0]).map(TraceCondition::OnDrug)),
+        "resistant" => {
+            arity(2)?;
+            Ok(TraceCondition::Resistant(bacteria(args[0])?, drug(args[1])?))
+        }
+        "hospitalised" => arity(0).map(|_| TraceCondition::Hospitalised),
+        "immunosuppressed" => arity(0).map(|_| TraceCondition::Immunosuppressed),
+        "region" => {
+            arity(1)?;
+            Region::from_name(catalog, args[0])
+                .map(TraceCondition::LivingIn)
+                .ok_or_else(|| format!("unknown region \"{}\"", args[0]))
+        }
+        "age_at_least" => arity(1).and(years(args[0]).map(TraceCondition::AgeAtLeast)),
+        "age_under" => arity(1).and(years(args[0]).map(TraceCondition::AgeUnder)),
+        _ => Err(format!("unknown condition \"{}\"", name)),
+    }
+}
+
+// One line of the trace.  The resistance levels are f32 and written as such, in their shortest decimal form rather
+// than as the f64 they widen to.
+#[derive(Serialize)]
+struct TracedState<'a> {
+    date: String,
+    time_step: usize,
+    id: usize,
+    alive: bool,
+    cause_of_death: Option<&'a str>,
+    age_days: i32,
+    sex_at_birth: &'static str,
+    region_living: &'a str,
+    region_cur_in: &'a str,
+    hospitalised: bool,
+    days_hospitalized: u32,
+    immunosuppressed: bool,
+    current_toxicity: f64,
+    level: Value,
+    immune_resp: Value,
+    sepsis: Vec<&'a str>,
+    microbiome: Vec<&'a str>,
+    test_identified: Vec<&'a str>,
+    infection_from_environment: Vec<&'a str>,
+    infection_hospital_acquired: Vec<&'a str>,
+    using_drug: Vec<&'a str>,
+    drug_level: Value,
+    ever_taken_drug: Vec<&'a str>,
+    resistances: Vec<TracedResistance<'a>>,
+}
+
+#[derive(Serialize)]
+struct TracedResistance<'a> {
+    bacteria: &'a str,
+    drug: &'a str,
+    any_r: f32,
+    majority_r: f32,
+    microbiome_r: f32,
+    test_r: f32,
+    activity_r: f32,
+}
+
+// The names of the bacteria or drugs whose flag is set.
+fn flagged<'a>(flags: Flags, names: &[&'a str]) -> Vec<&'a str> {
+    flags.iter().zip(names).filter(|&(flag, _)| flag).map(|(_, &name)| name).collect()
+}
+
+/// Output sink tracing a cohort of individuals as JSON lines (see the top of this file).
+pub struct CohortTracer<W: Write + Send> {
+    out: W,
+    ids: Vec<usize>,
+    sample_size: usize,
+    filter: Option<TraceFilter>,
+    cohort: BTreeSet<usize>,
+}
+
+impl<W: Write + Send> CohortTracer<W> {
+    /// A tracer with an empty cohort; add to it with `ids`, `sample` and `filter`.
+    pub fn new(out: W) -> Self {
+        CohortTracer { out, ids: Vec::new(), sample_size: 0, filter: None, cohort: BTreeSet::new() }
+    }
+
+    /// Trace the individuals with these ids.
+    pub fn ids<I: IntoIterator<Item = usize>>(mut self, ids: I) -> Self {
+        self.ids.extend(ids);
+        self
+    }
+
+    /// Trace a random sample of this many individuals.
+    pub fn sample(mut self, size: usize) -> Self {
+        self.sample_size = size;
+        self
+    }
+
+    /// Trace everyone who meets the filter at the end of some day, from that day on.
+    pub fn filter(mut self, filter: TraceFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    fn write_state(&mut self, simulation: &Simulation, time_step: usize, individual: &Individual) -> io::Result<()> {
+        let catalog = &simulation.catalog;
+        let (bacteria_names, drug_names) = (catalog.bacteria_names(), catalog.drug_names());
+        let nonzero = |values: &[f64], names: &[&str]| -> Value {
+            let map: Map<String, Value> = values
+                .iter()
+                .zip(names)
+                .filter(|(&value, _)| value != 0.0)
+                .map(|(&value, &name)| (name.to_string(), json!(value)))
+                .collect();
+            Value::Object(map)
+        };
+
+        let mut resistances = Vec::new();
+        for (b_idx, row) in individual.resistances.iter() {
+            for (d_idx, r) in row.iter().enumerate() {
+                if r.any_r != 0.0 || r.majority_r != 0.0 || r.microbiome_r != 0.0 || r.test_r != 0.0 || r.activity_r != 0.0 {
+                    resistances.push(TracedResistance {
+                        bacteria: bacteria_names[b_idx],
+                        drug: drug_names[d_idx],
+                        any_r: r.any_r,
+                        majority_r: r.majority_r,
+                        microbiome_r: r.microbiome_r,
+                        test_r: r.test_r,
+                        activity_r: r.activity_r,
+                    });
+                }
+            }
+        }
+
+        let record = TracedState {
+            date: simulation.date_at(time_step).to_string(),
+            time_step,
+            id: individual.id,
+            alive: individual.date_of_death.is_none(),
+            cause_of_death: individual.cause_of_death,
+            age_days: individual.age,
+            sex_at_birth: individual.sex_at_birth.as_str(),
+            region_living: individual.region_living.name(catalog),
+            region_cur_in: individual.region_cur_in.name(catalog),
+            hospitalised: individual.hospital_status.is_hospitalized(),
+            days_hospitalized: individual.days_hospitalized,
+            immunosuppressed: individual.is_severely_immunosuppressed,
+            current_toxicity: individual.current_toxicity,
+            level: nonzero(individual.level, &bacteria_names),
+            immune_resp: nonzero(individual.immune_resp, &bacteria_names),
+            sepsis: flagged(individual.sepsis, &bacteria_names),
+            microbiome: flagged(individual.presence_microbiome, &bacteria_names),
+            test_identified: flagged(individual.test_identified_infection, &bacteria_names),
+            infection_from_environment: flagged(individual.cur_infection_from_environment, &bacteria_names),
+            infection_hospital_acquired: flagged(individual.infection_hospital_acquired, &bacteria_names),
+            using_drug: flagged(individual.cur_use_drug, &drug_names),
+            drug_level: nonzero(individual.cur_level_drug, &drug_names),
+            ever_taken_drug: flagged(individual.ever_taken_drug, &drug_names),
+            resistances,
+        };
+        serde_json::to_writer(&mut self.out, &record)?;
+        writeln!(self.out)
+    }
+}
+
+impl<W: Write + Send> OutputSink for CohortTracer<W> {
+    fn start(&mut self, simulation: &Simulation) -> io::Result<()> {
+        let size = simulation.population.len();
+        self.cohort.extend(self.ids.iter().copied());
+        if self.sample_size > 0 {
+            let mut rng = individual_rng(simulation.seed, RngStream::Tracing, 0, 0);
+            let chosen = index::sample(&mut rng, size, self.sample_size.min(size));
+            self.cohort.extend(chosen.iter().map(|i| simulation.population.get(i).id));
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, simulation: &Simulation, time_step: usize) -> io::Result<()> {
+        if let Some(filter) = &self.filter {
+            let joining: Vec<usize> = simulation
+                .population
+                .par_iter()
+                .filter(|i| i.date_of_death.is_none() && filter.matches(i))
+                .map(|i| i.id)
+                .collect();
+            self.cohort.extend(joining);
+        }
+        if self.cohort.is_empty() {
+            return Ok(());
+        }
+        // everyone still alive, and those who died today
+        let traced: Vec<Individual> = simulation
+            .population
+            .iter()
+            .filter(|i| self.cohort.contains(&i.id) && i.date_of_death.is_none_or(|day| day == time_step))
+            .collect();
+        for individual in traced {
+            self.write_state(simulation, time_step, &individual)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _simulation: &Simulation) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<TraceFilter, String> {
+        TraceFilter::parse(&Catalog::default_catalog(), text)
+    }
+
+    fn bacteria(name: &str) -> usize {
+        Catalog::default_catalog().bacteria_names().iter().position(|&b| b == name).unwrap()
+    }
+
+    fn drug(name: &str) -> usize {
+        Catalog::default_catalog().drug_names().iter().position(|&d| d == name).unwrap()
+    }
+
+    #[test]
+    fn filters_are_parsed_into_their_conditions() {
+        let filter = parse("infected(klebsiella pneumoniae) and on_drug(meropenem) and hospitalised").unwrap();
+        assert_eq!(
+            filter.conditions,
+            vec![
+                TraceCondition::Infected(bacteria("klebsiella pneumoniae")),
+                TraceCondition::OnDrug(drug("meropenem")),
+                TraceCondition::Hospitalised,
+            ]
+        );
+
+        // spaces around the names and arguments do not matter
+        let filter =
+            parse(" resistant( escherichia coli , ciprofloxacin ) and age_at_least(65) and region(africa)").unwrap();
+        assert_eq!(
+            filter.conditions,
+            vec![
+                TraceCondition::Resistant(bacteria("escherichia coli"), drug("ciprofloxacin")),
+                TraceCondition::AgeAtLeast(65),
+                TraceCondition::LivingIn(Region::from_name(&Catalog::default_catalog(), "africa").unwrap()),
+            ]
+        );
+        let filter = parse("sepsis(staphylococcus aureus) and carrying(staphylococcus aureus) and age_under(5)");
+        assert_eq!(filter.unwrap().conditions.len(), 3);
+        assert_eq!(parse("immunosuppressed").unwrap().conditions, vec![TraceCondition::Immunosuppressed]);
+    }
+
+    #[test]
+    fn filters_with_mistakes_are_refused() {
+        let error = |text: &str| parse(text).unwrap_err();
+        assert_eq!(error("infected(e. coli)"), "unknown bacteria \"e. coli\"");
+        assert_eq!(error("on_drug(penicillin)"), "unknown drug \"penicillin\"");
+        assert_eq!(error("region(atlantis)"), "unknown region \"atlantis\"");
+        assert_eq!(error("infected(escherichia coli) and asleep"), "unknown condition \"asleep\"");
+        assert_eq!(error("resistant(escherichia coli)"), "\"resistant\" takes 2 argument(s)");
+        assert_eq!(error("hospitalised(today)"), "\"hospitalised\" takes 0 argument(s)");
+        assert_eq!(error("infected(escherichia coli"), "missing \")\" in \"infected(escherichia coli\"");
+        assert_eq!(error("age_under(five)"), "\"five\" is not a number of years");
+    }
+}
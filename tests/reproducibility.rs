@@ -0,0 +1,80 @@
+// tests/reproducibility.rs
+//
+// A run is fixed by its seed: every random draw comes from a stream derived from the master seed, the individual
+// and the time step (see simulation/rng.rs), so neither the number of worker threads nor stopping and resuming
+// the run changes the result.
+
+use std::sync::{Arc, Mutex};
+
+use amr_project::{CalendarDate, Simulation, SimulationBuilder, TimeSeriesHandle, TimeSeriesWriter};
+use amr_project::simulation::timeseries::TimeSeriesCounts;
+
+const POPULATION: usize = 300;
+const DAYS: usize = 20;
+const SEED: u64 = 20240117;
+
+fn builder() -> SimulationBuilder {
+    Simulation::builder()
+        .population_size(POPULATION)
+        .start_date(CalendarDate::parse("2000-01-01").unwrap())
+        .seed(SEED)
+        .quiet(true)
+}
+
+// Runs the simulation built by `builder` to the end and returns its daily time-series counts.
+fn counts_of(builder: SimulationBuilder) -> TimeSeriesCounts {
+    let handle: TimeSeriesHandle = Arc::new(Mutex::new(TimeSeriesCounts::new()));
+    let mut simulation = builder
+        .output(TimeSeriesWriter::new(std::io::sink()).keep_counts(handle.clone()))
+        .build()
+        .unwrap();
+    simulation.run().unwrap();
+    drop(simulation);
+    Arc::try_unwrap(handle).unwrap().into_inner().unwrap()
+}
+
+fn with_threads<T: Send>(threads: usize, work: impl FnOnce() -> T + Send) -> T {
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap().install(work)
+}
+
+#[test]
+fn same_seed_same_counts_whatever_the_thread_count() {
+    let one_thread = with_threads(1, || counts_of(builder().days(DAYS)));
+    let four_threads = with_threads(4, || counts_of(builder().days(DAYS)));
+    assert!(!one_thread.is_empty());
+    assert_eq!(one_thread, four_threads);
+}
+
+#[test]
+fn snapshot_and_resume_matches_an_uninterrupted_run() {
+    let uninterrupted = counts_of(builder().days(DAYS));
+
+    // the first half, saved to a snapshot
+    let handle: TimeSeriesHandle = Arc::new(Mutex::new(TimeSeriesCounts::new()));
+    let mut first_half = builder()
+        .days(DAYS / 2)
+        .output(TimeSeriesWriter::new(std::io::sink()).keep_counts(handle.clone()))
+        .build()
+        .unwrap();
+    first_half.run().unwrap();
+    let path = std::env::temp_dir().join(format!("amr_reproducibility_{}.snap", std::process::id()));
+    first_half.save_snapshot(&path).unwrap();
+    let (catalog, demography) = (first_half.catalog.clone(), first_half.demography.clone());
+    drop(first_half);
+
+    // the second half, from the snapshot
+    let restored = Simulation::load_snapshot(&path, catalog, demography).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut resumed = Simulation::builder()
+        .resume(restored)
+        .days(DAYS - DAYS / 2)
+        .quiet(true)
+        .output(TimeSeriesWriter::new(std::io::sink()).keep_counts(handle.clone()))
+        .build()
+        .unwrap();
+    resumed.run().unwrap();
+    drop(resumed);
+
+    let pieced_together = Arc::try_unwrap(handle).unwrap().into_inner().unwrap();
+    assert_eq!(pieced_together, uninterrupted);
+}